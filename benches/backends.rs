@@ -0,0 +1,135 @@
+//! 復号器と一括処理の速度の比較（criterion）
+//!
+//! `cargo bench --bench backends --all-features [フィルタ]`
+//!
+//! 結果は1語あたりの時間（スループット）で表示され，`target/criterion`に保存した前回の結果と比べられる．
+//! `--save-baseline 名前`と`--baseline 名前`で，変更の前後を比べる．
+//! 受信語の列は固定の乱数で作るので，機器を変えても同じ入力で比べられる．
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, Criterion, Throughput};
+use criterion::measurement::WallTime;
+use golay_code::decoder::{self, Decoder};
+
+/// 受信語の数
+const N_WORDS: usize = 1024;
+
+/// xorshift32
+fn rng(x: &mut u32) -> u32 {
+    *x ^= *x << 13;
+    *x ^= *x >> 17;
+    *x ^= *x << 5;
+    *x
+}
+
+/// `errors`bitの誤りを入れた受信語の列
+fn words(errors: u32) -> Vec<u32> {
+    let mut x = 0x1234_5678;
+    (0..N_WORDS).map(|_| {
+        let mut e = 0u32;
+        while e.count_ones() < errors {
+            e |= 1 << (rng(&mut x) % 24);
+        }
+        golay_code::encode(rng(&mut x) as u16 & 0xFFF) ^ e
+    }).collect()
+}
+
+fn hard<D: Decoder>(g: &mut BenchmarkGroup<WallTime>, name: &str, d: &D) {
+    for errors in 0..=3 {
+        let w = words(errors);
+        g.bench_function(format!("{}/{}bit", name, errors), |b| b.iter(|| {
+            for r in w.iter() {
+                black_box(d.ecc(black_box(*r)));
+            }
+        }));
+    }
+}
+
+fn encode(c: &mut Criterion) {
+    let data: Vec<u16> = (0..N_WORDS as u16).map(|i| i.wrapping_mul(2654) & 0xFFF).collect();
+    let mut g = c.benchmark_group("encode");
+    g.throughput(Throughput::Elements(N_WORDS as u64));
+    g.bench_function("table", |b| b.iter(|| {
+        for a in data.iter() {
+            black_box(golay_code::encode(black_box(*a)));
+        }
+    }));
+    g.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let mut g = c.benchmark_group("decode");
+    g.throughput(Throughput::Elements(N_WORDS as u64));
+    hard(&mut g, "arithmetic", &decoder::Arithmetic);
+    hard(&mut g, "kasami", &decoder::Kasami);
+    hard(&mut g, "step", &decoder::StepByStep);
+    hard(&mut g, "permutation", &decoder::Permutation);
+    hard(&mut g, "majority", &decoder::Majority);
+    hard(&mut g, "fixed", &decoder::FixedTime);
+    #[cfg(feature = "accel")]
+    hard(&mut g, "table", &golay_code::accel::Table);
+    g.finish();
+}
+
+/// 軟判定（BPSK，雑音なしの値に固定の乱れを加える）
+fn soft(c: &mut Criterion) {
+    use golay_code::product::Siso;
+
+    let mut x = 0x9E37_79B9;
+    let soft: Vec<[f32; 24]> = words(2).iter().map(|r| {
+        let mut y = [0f32; 24];
+        for (k, v) in y.iter_mut().enumerate() {
+            let s = if (r >> (23 - k)) & 1 == 1 { -1.0 } else { 1.0 };
+            *v = s * (0.5 + (rng(&mut x) % 100) as f32 / 100.0);
+        }
+        y
+    }).take(128).collect();
+    let mut g = c.benchmark_group("soft");
+    g.throughput(Throughput::Elements(soft.len() as u64));
+    let mut chase = golay_code::product::chase::Chase::new(4).unwrap();
+    g.bench_function("chase4", |b| b.iter(|| {
+        for y in soft.iter() {
+            black_box(chase.siso(black_box(y), 0.5));
+        }
+    }));
+    let mut bcjr = Box::new(golay_code::product::bcjr::Bcjr::new(1.0));
+    g.bench_function("bcjr", |b| b.iter(|| {
+        for y in soft.iter() {
+            black_box(bcjr.app(black_box(y)));
+        }
+    }));
+    g.finish();
+}
+
+/// 一括処理
+fn batch(c: &mut Criterion) {
+    let w = words(1);
+    let mut buf = w.clone();
+    let mut g = c.benchmark_group("batch");
+    g.throughput(Throughput::Elements(N_WORDS as u64));
+    g.bench_function("in_place_recover", |b| b.iter(|| {
+        buf.copy_from_slice(&w);
+        black_box(golay_code::in_place::recover(&mut buf)).ok();
+    }));
+    let bytes: Vec<u8> = (0..3 * N_WORDS / 2).map(|i| i as u8).collect();
+    let mut protected = vec![0u8; golay_code::protect::protected_len(bytes.len())];
+    g.bench_function("chunked_encode", |b| b.iter(|| {
+        let mut enc = golay_code::chunked::ChunkedEncoder::new(&bytes, &mut protected, 64).unwrap();
+        while enc.poll() == golay_code::chunked::Status::Pending {}
+    }));
+    #[cfg(feature = "accel")]
+    g.bench_function("accel_ecc_batch", |b| b.iter(|| {
+        buf.copy_from_slice(&w);
+        black_box(golay_code::accel::ecc_batch(&mut buf));
+    }));
+    #[cfg(feature = "std")]
+    g.bench_function("ecc_parallel4", |b| b.iter(|| {
+        buf.copy_from_slice(&w);
+        black_box(golay_code::hosted::ecc_parallel(&mut buf, 4));
+    }));
+    g.finish();
+}
+
+criterion_group!(benches, encode, decode, soft, batch);
+criterion_main!(benches);
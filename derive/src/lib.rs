@@ -0,0 +1,166 @@
+//! golay-codeの`GolayProtect`を実装するderiveマクロ
+//!
+//! 名前付きフィールドの構造体とタプル構造体に対応する．
+//! 各フィールドの型は`GolayProtect`を実装している必要がある．
+//! ジェネリクスを持つ構造体やenumには対応しない．
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// `GolayProtect`を実装する．
+///
+/// フィールドを宣言順に直列化する．
+#[proc_macro_derive(GolayProtect)]
+pub fn derive_golay_protect(input: TokenStream) -> TokenStream {
+    match parse_struct(input) {
+        Ok((name, fields)) => generate(&name, &fields),
+        Err(msg) => format!("compile_error!(\"{}\");", msg).parse().unwrap(),
+    }
+}
+
+/// 構造体のフィールド
+enum Fields {
+    /// 名前付きフィールド（名前，型）
+    Named(Vec<(String, String)>),
+    /// タプル構造体のフィールド（型）
+    Unnamed(Vec<String>),
+}
+
+/// 構造体名とフィールドを取り出す．
+fn parse_struct(input: TokenStream) -> Result<(String, Fields), &'static str> {
+    let mut tokens = input.into_iter().peekable();
+    // 属性と可視性を読み飛ばして`struct`を探す
+    loop {
+        match tokens.next() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => break,
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "enum" || ident.to_string() == "union" => {
+                return Err("GolayProtect can only be derived for structs");
+            },
+            Some(_) => {},
+            None => return Err("expected struct"),
+        }
+    }
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("expected struct name"),
+    };
+    match tokens.next() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+            let fields = split_fields(g.stream())?;
+            let mut named = Vec::new();
+            for field in fields {
+                let mut it = field.into_iter();
+                let ident = match it.next() {
+                    Some(TokenTree::Ident(ident)) => ident.to_string(),
+                    _ => return Err("expected field name"),
+                };
+                match it.next() {
+                    Some(TokenTree::Punct(p)) if p.as_char() == ':' => {},
+                    _ => return Err("expected `:`"),
+                }
+                named.push( (ident, it.collect::<TokenStream>().to_string()) );
+            }
+            Ok( (name, Fields::Named(named)) )
+        },
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => {
+            let fields = split_fields(g.stream())?;
+            let unnamed = fields.into_iter().map(|f| f.into_iter().collect::<TokenStream>().to_string());
+            Ok( (name, Fields::Unnamed(unnamed.collect())) )
+        },
+        Some(TokenTree::Punct(p)) if p.as_char() == '<' => Err("GolayProtect does not support generic structs"),
+        _ => Ok( (name, Fields::Unnamed(Vec::new())) ),  // ユニット構造体
+    }
+}
+
+/// フィールドの並びをカンマで分け，属性と可視性を取り除く．
+fn split_fields(stream: TokenStream) -> Result<Vec<Vec<TokenTree>>, &'static str> {
+    let mut fields = Vec::new();
+    let mut current = Vec::new();
+    let mut depth = 0;  // 型引数の<>の深さ
+    for tt in stream {
+        match &tt {
+            TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                fields.push( core::mem::take(&mut current) );
+                continue;
+            },
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' => depth -= 1,
+            _ => {},
+        }
+        current.push(tt);
+    }
+    if !current.is_empty() {
+        fields.push(current);
+    }
+
+    let mut out = Vec::new();
+    for field in fields {
+        let mut i = 0;
+        loop {
+            match (field.get(i), field.get(i + 1)) {
+                // #[...]
+                (Some(TokenTree::Punct(p)), Some(TokenTree::Group(_))) if p.as_char() == '#' => i += 2,
+                // pub(crate) など
+                (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(g)))
+                    if ident.to_string() == "pub" && g.delimiter() == Delimiter::Parenthesis => i += 2,
+                (Some(TokenTree::Ident(ident)), _) if ident.to_string() == "pub" => i += 1,
+                (Some(_), _) => break,
+                (None, _) => return Err("expected field"),
+            }
+        }
+        out.push( field[i..].to_vec() );
+    }
+    Ok(out)
+}
+
+/// 実装を生成する．
+fn generate(name: &str, fields: &Fields) -> TokenStream {
+    let (types, accessors): (Vec<&String>, Vec<String>) = match fields {
+        Fields::Named(f) => f.iter().map(|(n, t)| (t, n.clone())).unzip(),
+        Fields::Unnamed(f) => f.iter().enumerate().map(|(i, t)| (t, i.to_string())).unzip(),
+    };
+    let path = "::golay_code::protect";
+
+    let raw_len = if types.is_empty() {
+        "0".to_string()
+    } else {
+        types.iter()
+            .map(|t| format!("<{} as {}::GolayProtect>::RAW_LEN", t, path))
+            .collect::<Vec<_>>()
+            .join(" + ")
+    };
+    let writes: String = accessors.iter()
+        .map(|a| format!("{}::GolayProtect::write_raw(&self.{}, w);", path, a))
+        .collect();
+    let reads: Vec<String> = types.iter()
+        .map(|t| format!("<{} as {}::GolayProtect>::read_raw(r)", t, path))
+        .collect();
+    let construct = match fields {
+        Fields::Named(f) => {
+            let inits: Vec<String> = f.iter().zip(reads.iter())
+                .map(|((n, _), r)| format!("{}: {}", n, r))
+                .collect();
+            format!("{} {{ {} }}", name, inits.join(", "))
+        },
+        Fields::Unnamed(f) if f.is_empty() => name.to_string(),
+        Fields::Unnamed(_) => format!("{}({})", name, reads.join(", ")),
+    };
+
+    format!(
+        "impl {path}::GolayProtect for {name} {{
+            const RAW_LEN: usize = {raw_len};
+
+            fn write_raw(&self, w: &mut {path}::RawWriter) {{
+                let _ = &w;
+                {writes}
+            }}
+
+            fn read_raw(r: &mut {path}::RawReader) -> Self {{
+                let _ = &r;
+                {construct}
+            }}
+        }}",
+        path = path, name = name, raw_len = raw_len, writes = writes, construct = construct,
+    ).parse().unwrap()
+}
@@ -0,0 +1,205 @@
+//! 高速化のための実装（`accel`フィーチャ）
+//!
+//! `Table`はシンドロームから誤りパターンを1回の表引きで求める復号器．
+//! 表（4096要素，16KiB）はコンパイル時に作る．シンドロームを12bitに切り詰めてから引くので，
+//! 範囲検査はコンパイラが省ける．
+//! 表のチェックサムは`selftest::verify_tables`で確かめる．
+//!
+//! `ecc_batch`は受信語の列をまとめて訂正する．x86とx86_64でSSE2が有効なとき
+//! （`target_feature = "sse2"`，x86_64では常に有効）はシンドロームを4語ずつSIMDで計算し，
+//! それ以外のターゲットでは1語ずつ計算する．どちらも誤りパターンは`PATTERNS`から引く．
+//!
+//! クレートの中で`unsafe`を使うのはこのモジュールの`simd`だけで，`accel`フィーチャを
+//! 有効にしない既定のビルドは`forbid(unsafe_code)`のまま．
+
+use crate::decoder::Decoder;
+use crate::H_T;
+
+/// 訂正できない（4bit誤りの）シンドロームの印
+const UNCORRECTABLE: u32 = u32::MAX;
+
+/// シンドロームから誤りパターンへの表
+pub(crate) static PATTERNS: [u32; 4096] = patterns();
+
+/// 受信語のシンドローム（コンパイル時にも使う）
+const fn syndrome(r: u32) -> u32 {
+    let mut s = 0;
+    let mut i = 0;
+    while i < 24 {
+        if (r >> (23 - i)) & 1 == 1 {
+            s ^= H_T[i];
+        }
+        i += 1;
+    }
+    s
+}
+
+/// 重み3以下の全ての誤りパターンについて，シンドロームの位置に書き込む．
+const fn patterns() -> [u32; 4096] {
+    let mut table = [UNCORRECTABLE; 4096];
+    table[0] = 0;
+    let mut i = 0;
+    while i < 24 {
+        let mut j = i;
+        while j < 24 {
+            let mut k = j;
+            while k < 24 {
+                let e = (1 << i) | (1 << j) | (1 << k);
+                table[syndrome(e) as usize] = e;
+                k += 1;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+/// 表引きによる復号器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Table;
+
+impl Decoder for Table {
+    #[inline]
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        let s = crate::syndrome(r & 0xFFFFFF) & 0xFFF;
+        let e = PATTERNS[s as usize];
+        if e == UNCORRECTABLE { None } else { Some(e) }
+    }
+}
+
+/// 受信語の列をまとめて訂正する．
+///
+/// 訂正できない語は受信語のまま残す．
+///
+/// * return: 訂正できなかった語の数
+pub fn ecc_batch(words: &mut [u32]) -> usize {
+    let mut failed = 0;
+    let mut chunks = words.chunks_exact_mut(simd::LANES);
+    for chunk in &mut chunks {
+        let s = simd::syndromes(chunk);
+        for (r, s) in chunk.iter_mut().zip(s.iter()) {
+            failed += correct(r, *s);
+        }
+    }
+    for r in chunks.into_remainder() {
+        failed += correct(r, crate::syndrome(*r & 0xFFFFFF));
+    }
+    failed
+}
+
+/// シンドローム`s`から表で誤りパターンを引いて`r`を訂正する．
+///
+/// * return: 訂正できなければ1
+#[inline]
+fn correct(r: &mut u32, s: u32) -> usize {
+    let e = PATTERNS[(s & 0xFFF) as usize];
+    if e == UNCORRECTABLE {
+        1
+    } else {
+        *r ^= e;
+        0
+    }
+}
+
+/// 1語ずつ計算するシンドローム（SIMDが使えない場合）
+#[allow(dead_code)]
+fn syndromes_scalar(r: &[u32]) -> [u32; simd::LANES] {
+    let mut s = [0; simd::LANES];
+    for (s, r) in s.iter_mut().zip(r) {
+        *s = crate::syndrome(*r & 0xFFFFFF);
+    }
+    s
+}
+
+/// SSE2で4語のシンドロームをまとめて計算する．
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2"))]
+mod simd {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use crate::H_T;
+
+    /// 一度に処理する語数
+    pub(super) const LANES: usize = 4;
+
+    /// `r`の先頭`LANES`語のシンドロームを計算する．
+    #[allow(unsafe_code)]
+    pub(super) fn syndromes(r: &[u32]) -> [u32; LANES] {
+        // SAFETY: このモジュールは`target_feature = "sse2"`でビルドしたときだけ使うので，
+        // 実行するCPUは必ずSSE2を持つ．
+        unsafe { syndromes_sse2(r) }
+    }
+
+    #[target_feature(enable = "sse2")]
+    fn syndromes_sse2(r: &[u32]) -> [u32; LANES] {
+        // 受信語の最上位（bit 23）を符号ビットの位置に揃える
+        let mut x = _mm_slli_epi32::<8>( _mm_set_epi32(r[3] as i32, r[2] as i32, r[1] as i32, r[0] as i32) );
+        let mut s = _mm_setzero_si128();
+        for h_t_line in H_T.iter() {
+            // 符号ビットが1の語だけ全ビット1にする
+            let mask = _mm_srai_epi32::<31>(x);
+            s = _mm_xor_si128(s, _mm_and_si128(mask, _mm_set1_epi32(*h_t_line as i32)));
+            x = _mm_slli_epi32::<1>(x);
+        }
+        [
+            _mm_cvtsi128_si32(s) as u32,
+            _mm_cvtsi128_si32( _mm_shuffle_epi32::<0b01>(s) ) as u32,
+            _mm_cvtsi128_si32( _mm_shuffle_epi32::<0b10>(s) ) as u32,
+            _mm_cvtsi128_si32( _mm_shuffle_epi32::<0b11>(s) ) as u32,
+        ]
+    }
+}
+
+/// SIMDを使えないターゲットでは1語ずつ計算する．
+#[cfg(not(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2")))]
+mod simd {
+    /// 一度に処理する語数
+    pub(super) const LANES: usize = 4;
+
+    pub(super) use super::syndromes_scalar as syndromes;
+}
+
+#[test]
+fn test_accel() {
+    let code = crate::encode(0x6E1);
+    for i in 0..24 {
+        for j in i..24 {
+            for k in j..24 {
+                let e = (1 << i) | (1 << j) | (1 << k);
+                assert_eq!(Table.ecc(code ^ e), Some(code));
+                assert_eq!(Table.ecc(code ^ e ^ 0x200001), crate::decoder::Arithmetic.ecc(code ^ e ^ 0x200001));
+            }
+        }
+    }
+    // 表の中身: 訂正できないシンドロームは4bit誤りの剰余類（4096 - 2325個）
+    assert_eq!(PATTERNS.iter().filter(|e| **e == UNCORRECTABLE).count(), 4096 - 2325);
+
+    // 一括訂正: SIMDと1語ずつの計算が一致し，結果は`ecc`と同じ（端数の3語も含む）
+    let mut words = [0u32; 4099];
+    let mut x = 0x2545_F491u32;
+    for w in words.iter_mut() {
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        *w = x;
+    }
+    for chunk in words.chunks_exact(simd::LANES) {
+        assert_eq!(simd::syndromes(chunk), syndromes_scalar(chunk));
+    }
+    let mut batch = words;
+    let failed = ecc_batch(&mut batch);
+    let mut expected_failed = 0;
+    for (b, w) in batch.iter().zip(words.iter()) {
+        match crate::ecc(*w) {
+            Some(code) => assert_eq!(*b, code),
+            None => {
+                assert_eq!(*b, *w);
+                expected_failed += 1;
+            },
+        }
+    }
+    assert_eq!(failed, expected_failed);
+}
@@ -0,0 +1,207 @@
+//! 適応符号化率の制御
+//!
+//! 伝送路の品質に合わせて，符号化なし，パンクチャした符号，全ての検査ビットを送る符号を切り替える．
+//! 受信側で`monitor::Stats`などからビット誤り率を推定して`RateController::update`に渡し，
+//! 方式が変わったら`Mode::to_control`の制御語を帰還路で送信側に知らせる．
+//! 送信側は受け取った制御語を`Mode::from_control`で戻して切り替える．
+//!
+//! 誤り率が上がったときはすぐに強い方式に切り替え，下がったときは`hold`回続けて条件を満たしてから切り替える．
+//! 符号化なしの間は誤りを測れないので，ときどき符号化した試験語を送って推定する必要がある．
+
+use crate::monitor::Stats;
+use crate::puncture::Puncture;
+
+/// 符号化の方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// 符号化なし（12bit）
+    Uncoded,
+    /// パンクチャした符号
+    Punctured(Puncture),
+    /// 24bitの符号語
+    Full,
+}
+
+impl Mode {
+    /// 1語あたりの送信ビット数
+    pub fn bits(&self) -> u32 {
+        match self {
+            Mode::Uncoded => 12,
+            Mode::Punctured(p) => p.bits(),
+            Mode::Full => 24,
+        }
+    }
+
+    /// 12bitのデータを送信する語にする（下位`bits()`bit）．
+    pub fn encode(&self, data: u16) -> u32 {
+        match self {
+            Mode::Uncoded => (data & 0xFFF) as u32,
+            Mode::Punctured(p) => p.encode(data),
+            Mode::Full => crate::encode(data),
+        }
+    }
+
+    /// 受信した語からデータを取り出す．訂正できなければNone．
+    pub fn decode(&self, r: u32) -> Option<u16> {
+        match self {
+            Mode::Uncoded => Some( (r & 0xFFF) as u16 ),
+            Mode::Punctured(p) => p.ecc(r).map(crate::decode),
+            Mode::Full => crate::ecc(r).map(crate::decode),
+        }
+    }
+
+    /// 帰還路で送る12bitの制御語．bit 8〜11が方式，下位8bitは0（0x100，0x200，0x300）．
+    ///
+    /// パンクチャパターンは送受信で事前に決めておくので，制御語には入れない．
+    pub fn to_control(&self) -> u16 {
+        match self {
+            Mode::Uncoded => 0x100,
+            Mode::Punctured(_) => 0x200,
+            Mode::Full => 0x300,
+        }
+    }
+
+    /// 制御語から方式を戻す．
+    ///
+    /// * `puncture`: 事前に決めたパンクチャパターン
+    pub fn from_control(control: u16, puncture: Puncture) -> Option<Self> {
+        match control & 0xFFF {
+            0x100 => Some(Mode::Uncoded),
+            0x200 => Some(Mode::Punctured(puncture)),
+            0x300 => Some(Mode::Full),
+            _ => None,
+        }
+    }
+}
+
+/// 方式を切り替える閾値
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Thresholds {
+    /// 誤り率がこれ未満なら符号化なしにする
+    pub uncoded: f32,
+    /// 誤り率がこれ未満ならパンクチャした符号にする
+    pub punctured: f32,
+    /// 弱い方式に切り替えるまでに，続けて条件を満たす回数
+    pub hold: u32,
+}
+
+/// 適応符号化率の制御器
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateController {
+    puncture: Puncture,
+    thresholds: Thresholds,
+    mode: Mode,
+    streak: u32,
+}
+
+impl RateController {
+    /// 全ての検査ビットを送る方式から始める．
+    pub fn new(puncture: Puncture, thresholds: Thresholds) -> Self {
+        Self{ puncture, thresholds, mode: Mode::Full, streak: 0 }
+    }
+
+    /// 今の方式
+    #[inline]
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// 推定したビット誤り率から方式を決める．
+    ///
+    /// * return: 方式が変わった場合は新しい方式（帰還路で知らせる）
+    pub fn update(&mut self, ber: f32) -> Option<Mode> {
+        let target = if ber < self.thresholds.uncoded {
+            Mode::Uncoded
+        } else if ber < self.thresholds.punctured {
+            Mode::Punctured(self.puncture)
+        } else {
+            Mode::Full
+        };
+
+        if strength(target) > strength(self.mode) {
+            self.streak = 0;
+            self.mode = target;
+            return Some(target);
+        }
+        if strength(target) < strength(self.mode) {
+            self.streak += 1;
+            if self.streak >= self.thresholds.hold {
+                // 1段ずつ弱くする
+                self.streak = 0;
+                self.mode = if self.mode == Mode::Full { Mode::Punctured(self.puncture) } else { Mode::Uncoded };
+                return Some(self.mode);
+            }
+        } else {
+            self.streak = 0;
+        }
+        None
+    }
+
+    /// 窓の統計から誤り率を推定して方式を決める．
+    #[inline]
+    pub fn update_stats(&mut self, stats: &Stats) -> Option<Mode> {
+        self.update( bit_error_rate(stats) )
+    }
+
+    /// 帰還路で受け取った制御語に従って方式を切り替える（送信側）．
+    ///
+    /// * return: 制御語が正しくなければfalse
+    pub fn on_control(&mut self, control: u16) -> bool {
+        match Mode::from_control(control, self.puncture) {
+            Some(mode) => {
+                self.mode = mode;
+                self.streak = 0;
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// 方式の強さ（大きいほど強い）
+fn strength(mode: Mode) -> u8 {
+    match mode {
+        Mode::Uncoded => 0,
+        Mode::Punctured(_) => 1,
+        Mode::Full => 2,
+    }
+}
+
+/// 窓の統計からビット誤り率を推定する．検出した語は4bitの誤りとみなす．
+pub fn bit_error_rate(stats: &Stats) -> f32 {
+    if stats.words == 0 {
+        return 0.0;
+    }
+    (stats.corrected_bits + 4 * stats.detected) as f32 / (24 * stats.words) as f32
+}
+
+#[test]
+fn test_adaptive() {
+    let p = Puncture::new(0b0000_0001_1111).unwrap();
+    let th = Thresholds{ uncoded: 1e-4, punctured: 1e-2, hold: 3 };
+    let mut rx = RateController::new(p, th);
+    let mut tx = RateController::new(p, th);
+
+    // 伝送路が良くなると1段ずつ弱くする
+    assert_eq!(rx.update(1e-5), None);
+    assert_eq!(rx.update(1e-5), None);
+    let m = rx.update(1e-5).unwrap();
+    assert_eq!(m, Mode::Punctured(p));
+    assert!(tx.on_control(m.to_control()));
+    assert_eq!(tx.mode(), Mode::Punctured(p));
+    for _ in 0..2 {
+        assert_eq!(rx.update(1e-5), None);
+    }
+    assert_eq!(rx.update(1e-5), Some(Mode::Uncoded));
+
+    // 悪くなるとすぐに強くする
+    assert_eq!(rx.update(0.05), Some(Mode::Full));
+    assert!(!tx.on_control(0x123));
+
+    let code = Mode::Punctured(p).encode(0xABC);
+    assert_eq!(Mode::Punctured(p).decode(code ^ 0x10), Some(0xABC));
+    assert_eq!(Mode::Uncoded.bits(), 12);
+
+    let stats = Stats{ words: 10, clean: 8, corrected: 1, detected: 1, corrected_bits: 2, syndrome_weight: 9 };
+    assert!((bit_error_rate(&stats) - 6.0 / 240.0).abs() < 1e-6);
+}
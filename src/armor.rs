@@ -0,0 +1,92 @@
+//! 1符号語を4文字にするASCIIアーマー
+//!
+//! 24bitの符号語を6bitずつ，上位から4文字の印字可能文字にする．
+//! 現場での保守で，符号語を声で読み上げたり端末に打ち込んだりするためのもの．
+//!
+//! 読み間違えやすい文字（`0 O o 1 I l`）は使わない．
+//! 打ち間違いは受信語の誤りとして`Codeword::ecc`で訂正できることがある（1文字で最大6bit変わる）．
+
+use crate::layout::{BitOrder, Layout, Word};
+
+/// 6bitの値に対応する文字
+pub const ALPHABET: &[u8; 64] = b"23456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnpqrstuvwxyz#$%&*+=?";
+
+/// アーマーの文字数
+pub const ARMOR_LEN: usize = 4;
+
+/// アーマーの読み込みのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArmorError {
+    /// 4文字でない
+    InvalidLength,
+    /// 使えない文字がある
+    InvalidChar,
+}
+
+/// 24bitの語を4文字にする．
+pub fn format(r: u32) -> [u8; ARMOR_LEN] {
+    let mut out = [0; ARMOR_LEN];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = ALPHABET[((r >> (18 - 6 * i)) & 0x3F) as usize];
+    }
+    out
+}
+
+/// 4文字から24bitの語に戻す．
+pub fn parse(s: &[u8]) -> Result<u32, ArmorError> {
+    if s.len() != ARMOR_LEN {
+        return Err(ArmorError::InvalidLength);
+    }
+    let mut r = 0;
+    for c in s {
+        let v = ALPHABET.iter().position(|a| a == c).ok_or(ArmorError::InvalidChar)?;
+        r = (r << 6) | v as u32;
+    }
+    Ok(r)
+}
+
+impl<L: Layout, O: BitOrder> Word<L, O> {
+    /// 配置`L`の24bitの値を4文字のアーマーにする．
+    #[inline]
+    pub fn to_armor(&self) -> [u8; ARMOR_LEN] {
+        format(self.raw())
+    }
+
+    /// 4文字のアーマーから読み込む．前後の空白は無視する．
+    #[inline]
+    pub fn from_armor(s: &str) -> Result<Self, ArmorError> {
+        parse(s.trim().as_bytes()).map(Self::from_raw)
+    }
+}
+
+#[test]
+fn test_armor() {
+    use crate::codeword::Codeword;
+
+    for c in b"0Oo1Il".iter() {
+        assert!(!ALPHABET.contains(c));
+    }
+    for (i, a) in ALPHABET.iter().enumerate() {
+        assert!(a.is_ascii_graphic());
+        assert!(!ALPHABET[i + 1..].contains(a));
+    }
+
+    for data in (0..4096).step_by(7) {
+        let c = Codeword::encode(data);
+        let armor = c.to_armor();
+        let s = core::str::from_utf8(&armor).unwrap();
+        assert_eq!(Codeword::from_armor(s), Ok(c));
+    }
+    assert_eq!(format(0), *b"2222");
+    assert_eq!(format(0xFFFFFF), *b"????");
+    assert_eq!(Codeword::from_armor(" 2222\n"), Ok(Codeword::from(0)));
+    assert_eq!(parse(b"222"), Err(ArmorError::InvalidLength));
+    assert_eq!(parse(b"22O2"), Err(ArmorError::InvalidChar));
+
+    // 最後の文字の打ち間違い（隣の文字）は訂正できる
+    let c = Codeword::encode(0x3C5);
+    let mut armor = c.to_armor();
+    let v = ALPHABET.iter().position(|a| *a == armor[3]).unwrap();
+    armor[3] = ALPHABET[v ^ 1];
+    assert_eq!(parse(&armor).ok().and_then(|r| Codeword::from(r).ecc()), Some(c));
+}
@@ -0,0 +1,183 @@
+//! 誤り検出を使った簡単なARQ（交互ビット方式）
+//!
+//! 受信側は訂正できない誤りを検出したらNACKを返し，送信側は再送する．
+//! 応答が来ないまま時間切れになった場合も再送する．
+//! 時刻は呼び出し側が任意の単位の`u32`（一周しても良い）で渡す．
+//!
+//! データ語（12bit）は最上位ビットがシーケンスビットで，残りの11bitがペイロード．
+//! ACK/NACKも符号化した符号語で送る．上位4bitが種類，最下位ビットがシーケンスビット．
+
+/// ACKの種類を表す上位4bit
+const ACK: u16 = 0x5;
+
+/// NACKの種類を表す上位4bit
+const NACK: u16 = 0xA;
+
+/// 応答の符号語を作る．
+#[inline]
+fn feedback(kind: u16, seq: bool) -> u32 {
+    super::encode( (kind << 8) | seq as u16 )
+}
+
+/// 送信側で起きたこと
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxEvent {
+    /// 相手に届いた
+    Delivered,
+    /// この符号語を再送する
+    Retransmit(u32),
+    /// 再送回数の上限に達したので諦めた
+    GaveUp,
+}
+
+/// 送信中のため新しいデータを受け付けられない
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Busy;
+
+/// 送信側
+#[derive(Clone, Copy, Debug)]
+pub struct ArqTx {
+    seq: bool,
+    pending: Option<u32>,  // 応答待ちの符号語
+    deadline: u32,
+    timeout: u32,
+    retries: u8,
+    max_retries: u8,
+}
+
+impl ArqTx {
+    /// * `timeout`: 応答を待つ時間
+    /// * `max_retries`: 再送回数の上限
+    pub fn new(timeout: u32, max_retries: u8) -> Self {
+        Self{ seq: false, pending: None, deadline: 0, timeout, retries: 0, max_retries }
+    }
+
+    /// 応答待ちのデータがあればtrue
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// 11bitのペイロードを送る．
+    ///
+    /// * return: 送信する符号語
+    pub fn send(&mut self, payload: u16, now: u32) -> Result<u32, Busy> {
+        if self.pending.is_some() {
+            return Err(Busy);
+        }
+        let code = super::encode( ((self.seq as u16) << 11) | (payload & 0x7FF) );
+        self.pending = Some(code);
+        self.retries = 0;
+        self.deadline = now.wrapping_add(self.timeout);
+        Ok(code)
+    }
+
+    fn retransmit(&mut self, now: u32) -> Option<TxEvent> {
+        let code = self.pending?;
+        if self.retries >= self.max_retries {
+            self.pending = None;
+            self.seq = !self.seq;
+            return Some(TxEvent::GaveUp);
+        }
+        self.retries += 1;
+        self.deadline = now.wrapping_add(self.timeout);
+        Some( TxEvent::Retransmit(code) )
+    }
+
+    /// 受信した応答の符号語を処理する．
+    ///
+    /// 訂正できない応答や，今のシーケンスと関係ない応答は無視してNoneを返す．
+    pub fn on_feedback(&mut self, r: u32, now: u32) -> Option<TxEvent> {
+        self.pending?;
+        let word = super::decode( super::ecc(r)? );
+        if word & 1 != self.seq as u16 || word & 0x0FE != 0 {
+            return None;
+        }
+        match word >> 8 {
+            ACK => {
+                self.pending = None;
+                self.seq = !self.seq;
+                Some(TxEvent::Delivered)
+            },
+            NACK => self.retransmit(now),
+            _ => None,
+        }
+    }
+
+    /// 時間切れを確かめる．定期的に呼ぶ．
+    pub fn poll(&mut self, now: u32) -> Option<TxEvent> {
+        self.pending?;
+        // 一周しても良いように差で比べる
+        if (now.wrapping_sub(self.deadline) as i32) < 0 {
+            return None;
+        }
+        self.retransmit(now)
+    }
+}
+
+/// 受信側
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ArqRx {
+    expected: bool,
+}
+
+impl ArqRx {
+    pub fn new() -> Self {
+        Self{ expected: false }
+    }
+
+    /// データの受信語を処理する．
+    ///
+    /// * return: 新しく受け取ったペイロード（重複や訂正できない場合はNone）と，返す応答の符号語
+    pub fn receive(&mut self, r: u32) -> (Option<u16>, u32) {
+        match super::ecc(r) {
+            Some(code) => {
+                let word = super::decode(code);
+                let seq = word & 0x800 != 0;
+                if seq == self.expected {
+                    self.expected = !self.expected;
+                    (Some(word & 0x7FF), feedback(ACK, seq))
+                } else {
+                    // ACKが届かずに再送された重複
+                    (None, feedback(ACK, seq))
+                }
+            },
+            None => (None, feedback(NACK, self.expected)),
+        }
+    }
+}
+
+#[test]
+fn test_arq() {
+    let mut tx = ArqTx::new(100, 2);
+    let mut rx = ArqRx::new();
+
+    // 訂正できない誤り → NACK → 再送
+    let code = tx.send(0x123, 0).unwrap();
+    assert_eq!(tx.send(0x456, 0), Err(Busy));
+    let (data, fb) = rx.receive(code ^ 0xF);
+    assert_eq!(data, None);
+    let ev = tx.on_feedback(fb ^ 0x100001, 10).unwrap();  // 応答にも誤り
+    assert_eq!(ev, TxEvent::Retransmit(code));
+    let (data, fb) = rx.receive(code);
+    assert_eq!(data, Some(0x123));
+    assert_eq!(tx.on_feedback(fb, 20), Some(TxEvent::Delivered));
+    assert!(!tx.is_busy());
+
+    // ACKが失われて時間切れ → 再送した重複は捨てる
+    let code = tx.send(0x7FF, 1000).unwrap();
+    let (data, _) = rx.receive(code);
+    assert_eq!(data, Some(0x7FF));
+    assert_eq!(tx.poll(1050), None);
+    assert_eq!(tx.poll(1100), Some(TxEvent::Retransmit(code)));
+    let (data, fb) = rx.receive(code);
+    assert_eq!(data, None);
+    assert_eq!(tx.on_feedback(fb, 1110), Some(TxEvent::Delivered));
+
+    // 再送の上限
+    tx.send(0, u32::MAX - 10).unwrap();
+    assert!(tx.poll(u32::MAX).is_none());
+    assert!(matches!(tx.poll(89), Some(TxEvent::Retransmit(_))));
+    assert!(matches!(tx.poll(189), Some(TxEvent::Retransmit(_))));
+    assert_eq!(tx.poll(289), Some(TxEvent::GaveUp));
+}
@@ -0,0 +1,266 @@
+//! 非同期の入出力（`async`フィーチャ）
+//!
+//! Embassyなどの非同期ランタイムのタスクから，保護したバイト列を流すためのもの．
+//! 符号語を1つ読み書きするたびに実行器に処理を返すので，長いフレームでも他のタスクを止めない．
+//!
+//! 入出力は`embedded-io-async`の`Read`，`Write`で表すので，Embassyなどのドライバをそのまま渡せる．
+//! 符号化の形式は`protect`や`hosted::ProtectWriter`と同じ（12bitごとに3バイト，上位バイトから）．
+//!
+//! 符号語を単位にやり取りする場合は`stream`の`GolaySink`，`GolayStream`を使う（`futures`フィーチャ）．
+
+#[cfg(feature = "futures")]
+pub mod stream;
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use embedded_io_async as io;
+
+/// 非同期の入出力のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AsyncError<E> {
+    /// 入出力のエラー
+    Io(E),
+    /// 符号語の途中で終わった，または書き込めなくなった
+    UnexpectedEof,
+    /// 訂正できない符号語がある
+    Uncorrectable,
+}
+
+/// 一度だけ`Pending`を返して，実行器に処理を返す．
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// 実行器に処理を返す．
+pub async fn yield_now() {
+    YieldNow(false).await
+}
+
+/// 書き込んだバイト列を符号化して`inner`に書き込む．
+///
+/// 最後に`finish`を呼んで，残りのビットを0で埋めて書き出すこと．
+pub struct AsyncProtectWriter<W: io::Write> {
+    inner: W,
+    bits: u32,
+    n_bits: u32,
+}
+
+impl<W: io::Write> AsyncProtectWriter<W> {
+    /// * `inner`: 符号化したバイト列の書き込み先
+    pub fn new(inner: W) -> Self {
+        Self{ inner, bits: 0, n_bits: 0 }
+    }
+
+    async fn put(&mut self, a: u16) -> Result<(), AsyncError<W::Error>> {
+        let bytes = crate::encode(a).to_be_bytes();
+        let mut rest = &bytes[1..];
+        while !rest.is_empty() {
+            match self.inner.write(rest).await.map_err(AsyncError::Io)? {
+                0 => return Err(AsyncError::UnexpectedEof),
+                n => rest = &rest[n..],
+            }
+        }
+        yield_now().await;
+        Ok(())
+    }
+
+    /// バイト列を全て書き込む．
+    pub async fn write_all(&mut self, data: &[u8]) -> Result<(), AsyncError<W::Error>> {
+        for b in data {
+            self.bits = (self.bits << 8) | *b as u32;
+            self.n_bits += 8;
+            if self.n_bits >= 12 {
+                self.n_bits -= 12;
+                let a = (self.bits >> self.n_bits) as u16 & 0xFFF;
+                self.put(a).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 残りのビットを0で埋めて書き出し，`inner`を返す．
+    pub async fn finish(mut self) -> Result<W, AsyncError<W::Error>> {
+        if self.n_bits > 0 {
+            let a = (self.bits << (12 - self.n_bits)) as u16 & 0xFFF;
+            self.put(a).await?;
+        }
+        self.inner.flush().await.map_err(AsyncError::Io)?;
+        Ok(self.inner)
+    }
+}
+
+/// `inner`から符号語を読んで訂正し，元のバイト列として読み出す．
+///
+/// 長さは記録していないので，元のデータが3で割って2余るバイト数だった場合は最後に0が1バイト付く．
+pub struct AsyncRecoverReader<R: io::Read> {
+    inner: R,
+    bits: u32,
+    n_bits: u32,
+    corrected: u64,
+    pending: Option<AsyncError<R::Error>>,  // 読み出した分を返した後で報告するエラー
+}
+
+impl<R: io::Read> AsyncRecoverReader<R> {
+    /// * `inner`: 符号化したバイト列の読み出し元
+    pub fn new(inner: R) -> Self {
+        Self{ inner, bits: 0, n_bits: 0, corrected: 0, pending: None }
+    }
+
+    /// これまでに訂正したビット数
+    #[inline]
+    pub fn corrected(&self) -> u64 {
+        self.corrected
+    }
+
+    /// `inner`を返す．
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// 符号語を1つ読む．終わりならfalse．
+    async fn fill(&mut self) -> Result<bool, AsyncError<R::Error>> {
+        let mut c = [0u8; 3];
+        let mut n = 0;
+        while n < 3 {
+            match self.inner.read(&mut c[n..]).await.map_err(AsyncError::Io)? {
+                0 if n == 0 => return Ok(false),
+                0 => return Err(AsyncError::UnexpectedEof),
+                k => n += k,
+            }
+        }
+        let c = crate::ecc_verbose( u32::from_be_bytes([0, c[0], c[1], c[2]]) )
+            .ok_or(AsyncError::Uncorrectable)?;
+        self.corrected += c.errors as u64;
+        self.bits = (self.bits << 12) | crate::decode(c.code) as u32;
+        self.n_bits += 12;
+        yield_now().await;
+        Ok(true)
+    }
+
+    /// `buf`に読み出す．
+    ///
+    /// 途中でエラーになった場合は，それまでに読み出したバイト数を返し，エラーは次の呼び出しで返す．
+    ///
+    /// * return: 読み出したバイト数．終わりに達すると`buf`の長さより少なくなる．
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, AsyncError<R::Error>> {
+        if let Some(e) = self.pending.take() {
+            return Err(e);
+        }
+        let mut n = 0;
+        for b in buf.iter_mut() {
+            if self.n_bits < 8 {
+                match self.fill().await {
+                    Ok(true) => (),
+                    Ok(false) => break,
+                    Err(e) if n > 0 => {
+                        self.pending = Some(e);
+                        break;
+                    },
+                    Err(e) => return Err(e),
+                }
+            }
+            self.n_bits -= 8;
+            *b = (self.bits >> self.n_bits) as u8;
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_asynch() {
+    use core::convert::Infallible;
+    use core::task::Waker;
+
+    /// 実行器の代わりに，終わるまでポーリングする．
+    fn block_on<F: Future>(f: F) -> (F::Output, usize) {
+        let mut f = core::pin::pin!(f);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut pending = 0;
+        loop {
+            if let Poll::Ready(out) = f.as_mut().poll(&mut cx) {
+                return (out, pending);
+            }
+            pending += 1;
+        }
+    }
+
+    /// 1回に2バイトまでしか読み書きしない相手
+    struct Pipe {
+        buf: [u8; 64],
+        len: usize,
+        pos: usize,
+    }
+
+    impl io::ErrorType for Pipe {
+        type Error = Infallible;
+    }
+
+    impl io::Write for Pipe {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+            let n = buf.len().min(2).min(self.buf.len() - self.len);
+            self.buf[self.len..self.len + n].copy_from_slice(&buf[..n]);
+            self.len += n;
+            Ok(n)
+        }
+
+        async fn flush(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    impl io::Read for Pipe {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+            let n = buf.len().min(2).min(self.len - self.pos);
+            buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    let data = *b"embassy task";
+    let mut pipe = Pipe{ buf: [0; 64], len: 0, pos: 0 };
+    let (result, pending) = block_on(async {
+        let mut w = AsyncProtectWriter::new(&mut pipe);
+        w.write_all(&data[..5]).await?;
+        w.write_all(&data[5..]).await?;
+        w.finish().await.map(|_| ())
+    });
+    assert_eq!(result, Ok(()));
+    assert_eq!(pending, 8);  // 符号語ごとに1回
+    assert_eq!(pipe.len, crate::protect::protected_len(data.len()));
+
+    pipe.buf[4] ^= 0x81;
+    let mut out = [0u8; 16];
+    let (result, _) = block_on(async {
+        let mut r = AsyncRecoverReader::new(&mut pipe);
+        let n = r.read(&mut out).await?;
+        Ok::<_, AsyncError<Infallible>>((n, r.corrected()))
+    });
+    assert_eq!(result, Ok((12, 2)));
+    assert_eq!(out[..12], data);
+
+    pipe.pos = 0;
+    pipe.buf[7] ^= 0x0F;
+    pipe.buf[8] ^= 0xF0;
+    // 訂正できない符号語の前までを返し，エラーは次の呼び出しで返す
+    let (result, _) = block_on(async {
+        let mut r = AsyncRecoverReader::new(&mut pipe);
+        (r.read(&mut out).await, r.read(&mut out).await)
+    });
+    assert_eq!(result, (Ok(3), Err(AsyncError::Uncorrectable)));
+    assert_eq!(out[..3], data[..3]);
+}
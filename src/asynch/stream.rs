@@ -0,0 +1,188 @@
+//! 符号語のストリームとシンク
+//!
+//! `GolaySink`は12bitのデータを受け取って符号化し，符号語のシンクに送る．
+//! `GolayStream`は符号語のストリームを訂正して，12bitのデータを返す．
+//! 保護したシリアル回線を，非同期のプログラムの他の部分にそのまま繋ぐためのもの．
+//!
+//! `futures_core::Stream`と`futures_sink::Sink`を実装するので（`futures`フィーチャ），
+//! `StreamExt`や`SinkExt`のコンビネータがそのまま使える．
+
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::Detected;
+
+/// 12bitのデータを符号化して符号語のシンクに送る．
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GolaySink<S> {
+    inner: S,
+}
+
+impl<S> GolaySink<S> {
+    /// * `inner`: 符号語（下位24bit）のシンク
+    pub fn new(inner: S) -> Self {
+        Self{ inner }
+    }
+
+    /// `inner`を返す．
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Sink<u32> + Unpin> Sink<u16> for GolaySink<S> {
+    type Error = S::Error;
+
+    #[inline]
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    #[inline]
+    fn start_send(mut self: Pin<&mut Self>, data: u16) -> Result<(), S::Error> {
+        Pin::new(&mut self.inner).start_send( crate::encode(data) )
+    }
+
+    #[inline]
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+/// 符号語のストリームを訂正して12bitのデータを返す．
+///
+/// 訂正できない符号語は`Err(Detected)`として返し，ストリームは続ける．
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GolayStream<S> {
+    inner: S,
+    corrected: u64,
+}
+
+impl<S> GolayStream<S> {
+    /// * `inner`: 受信語（下位24bit）のストリーム
+    pub fn new(inner: S) -> Self {
+        Self{ inner, corrected: 0 }
+    }
+
+    /// これまでに訂正したビット数
+    #[inline]
+    pub fn corrected(&self) -> u64 {
+        self.corrected
+    }
+
+    /// `inner`を返す．
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Stream<Item = u32> + Unpin> Stream for GolayStream<S> {
+    type Item = Result<u16, Detected>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.inner).poll_next(cx) {
+            Poll::Ready(Some(r)) => {
+                let result = match crate::ecc_verbose(r) {
+                    Some(c) => {
+                        self.corrected += c.errors as u64;
+                        Ok( crate::decode(c.code) )
+                    },
+                    None => Err(Detected),
+                };
+                Poll::Ready(Some(result))
+            },
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[test]
+fn test_stream() {
+    use core::task::Waker;
+
+    /// 符号語を貯めるシンクと，貯めたものを返すストリーム
+    struct Queue {
+        words: [u32; 8],
+        len: usize,
+        pos: usize,
+        closed: bool,
+    }
+
+    impl Sink<u32> for Queue {
+        type Error = ();
+
+        fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            Poll::Ready(if self.len < self.words.len() { Ok(()) } else { Err(()) })
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, code: u32) -> Result<(), ()> {
+            let n = self.len;
+            self.words[n] = code;
+            self.len += 1;
+            Ok(())
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), ()>> {
+            self.closed = true;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl Stream for Queue {
+        type Item = u32;
+
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<u32>> {
+            if self.pos == self.len {
+                return Poll::Ready(None);
+            }
+            self.pos += 1;
+            Poll::Ready(Some(self.words[self.pos - 1]))
+        }
+    }
+
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut sink = GolaySink::new(Queue{ words: [0; 8], len: 0, pos: 0, closed: false });
+    for data in [0x123, 0xABC, 0xFFF].iter() {
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Ok(())));
+        Pin::new(&mut sink).start_send(*data).unwrap();
+    }
+    assert_eq!(Pin::new(&mut sink).poll_close(&mut cx), Poll::Ready(Ok(())));
+    let mut queue = sink.into_inner();
+    assert!(queue.closed);
+    assert_eq!(queue.words[1], crate::encode(0xABC));
+
+    queue.words[0] ^= 0x000401;
+    queue.words[2] ^= 0x00000F;
+    let mut stream = GolayStream::new(queue);
+    let mut next = || Pin::new(&mut stream).poll_next(&mut cx);
+    assert_eq!(next(), Poll::Ready(Some(Ok(0x123))));
+    assert_eq!(next(), Poll::Ready(Some(Ok(0xABC))));
+    assert_eq!(next(), Poll::Ready(Some(Err(Detected))));
+    assert_eq!(next(), Poll::Ready(None));
+    assert_eq!(stream.corrected(), 2);
+
+    // futuresのコンビネータで使う（相手は常に用意ができているので，1回のポーリングで終わる）
+    use core::future::Future;
+    use futures_util::{SinkExt, StreamExt};
+    let mut sink = GolaySink::new(Queue{ words: [0; 8], len: 0, pos: 0, closed: false });
+    let fut = core::pin::pin!(async {
+        sink.send(0x5A5).await.unwrap();
+        sink.send(0x0F0).await.unwrap();
+        let mut stream = GolayStream::new(sink.into_inner()).map(|d| d.unwrap() ^ 0xFFF);
+        (stream.next().await, stream.next().await, stream.next().await)
+    });
+    assert_eq!(fut.poll(&mut cx), Poll::Ready((Some(0xA5A), Some(0xF0F), None)));
+}
@@ -0,0 +1,89 @@
+//! 12bitの値を符号化して持つアトミック変数
+//!
+//! 符号語を`AtomicU32`に入れておき，読み出すたびに訂正する．
+//! ECCの無い共有SRAMを介したコア間のメールボックス向け（RP2040やデュアルコアのSTM32H7など）．
+//!
+//! 読み書きは32bitのload/storeだけなので，CAS命令の無いコア（Cortex-M0+など）でも使える．
+//! 訂正した値の書き戻しはCAS命令のあるターゲットでだけ使える．
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::Decode;
+
+/// 12bitの値を符号化して持つアトミック変数
+#[derive(Debug)]
+pub struct ProtectedAtomicU12 {
+    inner: AtomicU32,
+}
+
+impl ProtectedAtomicU12 {
+    /// 値0の変数を作る．0の符号語は0なので`static`の初期化に使える．
+    pub const fn zero() -> Self {
+        Self{ inner: AtomicU32::new(0) }
+    }
+
+    /// 値（下位12bit）を符号化して持つ変数を作る．
+    pub fn new(value: u16) -> Self {
+        Self{ inner: AtomicU32::new( super::encode(value) ) }
+    }
+
+    /// 値を訂正して読み出す．訂正できなければNone．
+    #[inline]
+    pub fn load(&self, order: Ordering) -> Option<u16> {
+        super::ecc( self.inner.load(order) ).map(super::decode)
+    }
+
+    /// 値を読み出し，誤りの有無も返す．
+    #[inline]
+    pub fn load_status(&self, order: Ordering) -> Decode {
+        super::ecc_status( self.inner.load(order) )
+    }
+
+    /// 値（下位12bit）を符号化して書き込む．
+    #[inline]
+    pub fn store(&self, value: u16, order: Ordering) {
+        self.inner.store(super::encode(value), order);
+    }
+
+    /// 値を訂正して読み出し，誤りがあれば訂正した符号語を書き戻す．
+    ///
+    /// 読み出してから書き戻すまでに他のコアが書き込んでいた場合は書き戻さない．
+    #[cfg(target_has_atomic = "32")]
+    pub fn load_repair(&self, order: Ordering) -> Option<u16> {
+        let r = self.inner.load(order);
+        let code = super::ecc(r)?;
+        if code != r {
+            // 失敗したら他のコアが新しい値を書いたということなので，そのままで良い
+            let _ = self.inner.compare_exchange(r, code, Ordering::AcqRel, Ordering::Relaxed);
+        }
+        Some( super::decode(code) )
+    }
+
+    /// 符号化したままの値（誤りの注入や診断用）
+    #[inline]
+    pub fn raw(&self) -> &AtomicU32 {
+        &self.inner
+    }
+}
+
+impl Default for ProtectedAtomicU12 {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+#[test]
+fn test_atomic() {
+    static MAILBOX: ProtectedAtomicU12 = ProtectedAtomicU12::zero();
+    assert_eq!(MAILBOX.load(Ordering::Acquire), Some(0));
+
+    let cell = ProtectedAtomicU12::new(0xABC);
+    cell.raw().fetch_xor(0x100801, Ordering::Relaxed);
+    assert_eq!(cell.load_status(Ordering::Acquire), Decode::Corrected(super::encode(0xABC), 3));
+    assert_eq!(cell.load_repair(Ordering::Acquire), Some(0xABC));
+    assert_eq!(cell.load_status(Ordering::Acquire), Decode::Clean(super::encode(0xABC)));
+
+    cell.store(0x123, Ordering::Release);
+    cell.raw().fetch_xor(0xF, Ordering::Relaxed);
+    assert_eq!(cell.load(Ordering::Acquire), None);
+}
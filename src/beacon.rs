@@ -0,0 +1,187 @@
+//! 超小型衛星（CubeSat）向けテレメトリビーコン
+//!
+//! コールサインとハウスキーピングデータを12bitずつに分けて符号化し，
+//! インターリーブしたバイト列にする．
+//!
+//! 語の並び
+//! * 0: ヘッダ（上位4bitがフィールド数，下位8bitがシーケンス番号）
+//! * 1〜3: コールサイン（1語に6bit文字を2文字）
+//! * 4〜: ハウスキーピングデータ（1語に12bit）
+
+use super::interleave;
+
+/// コールサインの最大文字数
+pub const CALLSIGN_LEN: usize = 6;
+
+/// ハウスキーピングデータの最大フィールド数
+pub const MAX_FIELDS: usize = 15;
+
+/// ヘッダとコールサインの語数
+const HEADER_WORDS: usize = 1 + CALLSIGN_LEN / 2;
+
+/// ビーコンの最大バイト数
+pub const MAX_BYTES: usize = (HEADER_WORDS + MAX_FIELDS) * 3;
+
+/// ビーコンのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BeaconError {
+    /// コールサインに使えない文字が含まれているか，長すぎる
+    InvalidCallsign,
+    /// フィールド数が`MAX_FIELDS`を超えた
+    TooManyFields,
+    /// 出力先のバッファが足りない
+    BufferTooSmall,
+    /// 長さやヘッダが不正
+    Malformed,
+    /// 訂正できない誤りを検出した
+    Uncorrectable,
+}
+
+/// テレメトリビーコン
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Beacon {
+    callsign: [u8; CALLSIGN_LEN],  // 後ろは空白で埋める
+    sequence: u8,
+    fields: [u16; MAX_FIELDS],
+    n_fields: usize,
+}
+
+/// ASCII文字を6bitの文字コードにする（0x20〜0x5Fのみ．英小文字は大文字にする）．
+fn to_sixbit(c: u8) -> Option<u8> {
+    let c = c.to_ascii_uppercase();
+    if (0x20..0x60).contains(&c) {
+        Some(c - 0x20)
+    } else {
+        None
+    }
+}
+
+impl Beacon {
+    /// * `callsign`: コールサイン（`CALLSIGN_LEN`文字以下）
+    /// * `sequence`: シーケンス番号
+    pub fn new(callsign: &str, sequence: u8) -> Result<Self, BeaconError> {
+        if callsign.len() > CALLSIGN_LEN {
+            return Err(BeaconError::InvalidCallsign);
+        }
+        let mut cs = [b' '; CALLSIGN_LEN];
+        for (dst, c) in cs.iter_mut().zip(callsign.bytes()) {
+            *dst = to_sixbit(c).ok_or(BeaconError::InvalidCallsign)? + 0x20;
+        }
+        Ok( Self{ callsign: cs, sequence, fields: [0; MAX_FIELDS], n_fields: 0 } )
+    }
+
+    /// ハウスキーピングデータを1つ追加する（下位12bit）．
+    pub fn push_field(&mut self, value: u16) -> Result<(), BeaconError> {
+        if self.n_fields >= MAX_FIELDS {
+            return Err(BeaconError::TooManyFields);
+        }
+        self.fields[self.n_fields] = value & 0xFFF;
+        self.n_fields += 1;
+        Ok(())
+    }
+
+    /// コールサイン（後ろの空白は除く）
+    pub fn callsign(&self) -> &str {
+        let len = self.callsign.iter().rposition(|c| *c != b' ').map_or(0, |i| i + 1);
+        // 0x20〜0x5Fしか入っていないのでASCIIとして正しい
+        core::str::from_utf8(&self.callsign[..len]).unwrap()
+    }
+
+    /// シーケンス番号
+    #[inline]
+    pub fn sequence(&self) -> u8 {
+        self.sequence
+    }
+
+    /// ハウスキーピングデータ
+    #[inline]
+    pub fn fields(&self) -> &[u16] {
+        &self.fields[..self.n_fields]
+    }
+
+    /// 符号化後のバイト数
+    #[inline]
+    pub fn encoded_len(&self) -> usize {
+        (HEADER_WORDS + self.n_fields) * 3
+    }
+
+    /// 符号化してインターリーブしたバイト列を書き込む．
+    ///
+    /// * return: 書き込んだバイト数
+    pub fn encode(&self, out: &mut [u8]) -> Result<usize, BeaconError> {
+        let n = HEADER_WORDS + self.n_fields;
+        let mut codes = [0u32; HEADER_WORDS + MAX_FIELDS];
+        codes[0] = super::encode( ((self.n_fields as u16) << 8) | self.sequence as u16 );
+        for (i, pair) in self.callsign.chunks(2).enumerate() {
+            let hi = (pair[0] - 0x20) as u16;
+            let lo = (pair[1] - 0x20) as u16;
+            codes[1 + i] = super::encode( (hi << 6) | lo );
+        }
+        for (code, field) in codes[HEADER_WORDS..n].iter_mut().zip(self.fields()) {
+            *code = super::encode(*field);
+        }
+        interleave::interleave(&codes[..n], out).ok_or(BeaconError::BufferTooSmall)
+    }
+
+    /// 受信したバイト列を訂正してビーコンを取り出す．
+    ///
+    /// * return: ビーコンと訂正したビット数の合計
+    pub fn parse(bytes: &[u8]) -> Result<(Self, u32), BeaconError> {
+        if !bytes.len().is_multiple_of(3) || bytes.len() < HEADER_WORDS * 3 || bytes.len() > MAX_BYTES {
+            return Err(BeaconError::Malformed);
+        }
+        let n = bytes.len() / 3;
+        let mut codes = [0u32; HEADER_WORDS + MAX_FIELDS];
+        interleave::deinterleave(bytes, &mut codes[..n]).ok_or(BeaconError::Malformed)?;
+
+        let mut words = [0u16; HEADER_WORDS + MAX_FIELDS];
+        let mut corrected = 0;
+        for (word, r) in words.iter_mut().zip(codes[..n].iter()) {
+            let c = super::ecc_verbose(*r).ok_or(BeaconError::Uncorrectable)?;
+            corrected += c.errors as u32;
+            *word = super::decode(c.code);
+        }
+
+        let n_fields = (words[0] >> 8) as usize;
+        if n_fields != n - HEADER_WORDS {
+            return Err(BeaconError::Malformed);
+        }
+        let mut callsign = [b' '; CALLSIGN_LEN];
+        for (i, word) in words[1..HEADER_WORDS].iter().enumerate() {
+            callsign[2 * i]     = (word >> 6) as u8 + 0x20;
+            callsign[2 * i + 1] = (word & 0x3F) as u8 + 0x20;
+        }
+        let mut fields = [0u16; MAX_FIELDS];
+        fields[..n_fields].copy_from_slice(&words[HEADER_WORDS..n]);
+
+        let beacon = Self{ callsign, sequence: words[0] as u8, fields, n_fields };
+        Ok( (beacon, corrected) )
+    }
+}
+
+#[test]
+fn test_beacon() {
+    let mut b = Beacon::new("ja1xyz", 42).unwrap();
+    b.push_field(3300).unwrap();  // 電圧 [mV]
+    b.push_field(0x0FE).unwrap();
+    b.push_field(25).unwrap();
+
+    let mut buf = [0u8; MAX_BYTES];
+    let len = b.encode(&mut buf).unwrap();
+    assert_eq!(len, b.encoded_len());
+    assert_eq!(len, 21);
+
+    // 連続した14bitの誤り（符号語7個に2bitずつ）
+    buf[10] ^= 0xFF;
+    buf[11] ^= 0b1111_1100;
+
+    let (rx, corrected) = Beacon::parse(&buf[..len]).unwrap();
+    assert_eq!(rx, b);
+    assert_eq!(rx.callsign(), "JA1XYZ");
+    assert_eq!(rx.sequence(), 42);
+    assert_eq!(rx.fields(), &[3300, 0x0FE, 25]);
+    assert_eq!(corrected, 14);
+
+    assert_eq!(Beacon::new("JA1XYZW", 0), Err(BeaconError::InvalidCallsign));
+    assert_eq!(Beacon::parse(&buf[..len - 1]), Err(BeaconError::Malformed));
+}
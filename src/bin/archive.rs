@@ -0,0 +1,71 @@
+//! ファイルの保護と修復（`file`のコマンドライン版）
+//!
+//! `cargo run --release --features std --bin archive -- protect 元のファイル 保護したファイル`
+//! `cargo run --release --features std --bin archive -- repair 保護したファイル`
+//! `cargo run --release --features std --bin archive -- extract 保護したファイル 元のファイル`
+//!
+//! 修復と取り出しでは，訂正した範囲と失われた範囲（元のファイルでのバイト範囲）を表示する．
+//! 失われた範囲があれば終了コード1で終わる．
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::process;
+
+use golay_code::file::{self, FileReport};
+
+fn usage() -> ! {
+    eprintln!("usage: archive protect <input> <output>");
+    eprintln!("       archive repair <file>");
+    eprintln!("       archive extract <file> <output>");
+    process::exit(2);
+}
+
+fn print_report(report: &FileReport) {
+    println!("length: {} bytes in {} chunks", report.len, report.chunks);
+    println!("corrected: {} bits", report.corrected_bits);
+    for r in report.recovered.iter() {
+        println!("recovered: {}..{}", r.start, r.end);
+    }
+    for r in report.lost.iter() {
+        println!("lost: {}..{}", r.start, r.end);
+    }
+}
+
+fn run(args: &[String]) -> io::Result<bool> {
+    match args {
+        [cmd, input, output] if cmd == "protect" => {
+            let input = BufReader::new(File::open(input)?);
+            let output = BufWriter::new(File::create(output)?);
+            let len = file::protect(input, output)?;
+            println!("protected {} bytes", len);
+            Ok(true)
+        },
+        [cmd, path] if cmd == "repair" => {
+            let mut f = OpenOptions::new().read(true).write(true).open(path)?;
+            let report = file::repair(&mut f)?;
+            print_report(&report);
+            Ok(report.is_ok())
+        },
+        [cmd, input, output] if cmd == "extract" => {
+            let mut input = BufReader::new(File::open(input)?);
+            let mut output = BufWriter::new(File::create(output)?);
+            let report = file::extract(&mut input, &mut output)?;
+            output.flush()?;
+            print_report(&report);
+            Ok(report.is_ok())
+        },
+        _ => usage(),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("archive: {}", e);
+            process::exit(2);
+        },
+    }
+}
@@ -0,0 +1,77 @@
+//! 未検出誤りと誤訂正の割合のシミュレーション
+//!
+//! 重み4以上の誤りパターンを一様に選んで符号語に加え，訂正の結果を数える．
+//! 重みごとの割合を`miscorrection::expected`の解析値と並べ，差を標準誤差で割った値（z）を出す．
+//!
+//! `cargo run --release --features std --bin simulate -- [重みごとの試行回数] [乱数の種]`
+//!
+//! 出力はCSV（重み，試行回数，検出，誤訂正，未検出，誤訂正率，解析値，z）．
+//! 誤訂正には未検出（符号語そのものになったもの）を含む．
+
+use golay_code::miscorrection;
+
+/// xorshift64*
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// 0以上n未満
+    fn below(&mut self, n: u32) -> u32 {
+        (((self.next() >> 32) * n as u64) >> 32) as u32
+    }
+
+    /// 重み`weight`の24bitの誤りパターン（部分的なFisher–Yates）
+    fn pattern(&mut self, weight: u32) -> u32 {
+        let mut pos: [u8; 24] = core::array::from_fn(|i| i as u8);
+        let mut e = 0;
+        for i in 0..weight as usize {
+            let j = i + self.below(24 - i as u32) as usize;
+            pos.swap(i, j);
+            e |= 1 << pos[i];
+        }
+        e
+    }
+}
+
+/// 重み`weight`の誤りパターンの数
+fn patterns(weight: u32) -> f64 {
+    (0..weight).fold(1.0, |c, i| c * (24 - i) as f64 / (i + 1) as f64)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let trials: u64 = args.next().and_then(|a| a.parse().ok()).unwrap_or(1_000_000);
+    let seed: u64 = args.next().and_then(|a| a.parse().ok()).unwrap_or(0x9E37_79B9_7F4A_7C15);
+    let mut rng = Rng(seed | 1);
+
+    println!("weight,trials,detected,miscorrected,undetected,rate,expected,z");
+    for weight in 4..=24 {
+        let (mut detected, mut miscorrected, mut undetected) = (0u64, 0u64, 0u64);
+        for _ in 0..trials {
+            let code = golay_code::encode(rng.below(4096) as u16);
+            let r = code ^ rng.pattern(weight);
+            match golay_code::ecc(r) {
+                None => detected += 1,
+                Some(c) => {
+                    // 4bit以上の誤りなので，送った符号語に戻ることはない
+                    assert_ne!(c, code);
+                    miscorrected += 1;
+                    if c == r {
+                        undetected += 1;
+                    }
+                },
+            }
+        }
+        let rate = miscorrected as f64 / trials as f64;
+        let p = miscorrection::expected(weight) as f64 / patterns(weight);
+        let se = (p * (1.0 - p) / trials as f64).sqrt();
+        let z = if se > 0.0 { (rate - p) / se } else if rate == p { 0.0 } else { f64::INFINITY };
+        println!("{},{},{},{},{},{:.6e},{:.6e},{:.2}", weight, trials, detected, miscorrected, undetected, rate, p, z);
+    }
+}
@@ -0,0 +1,98 @@
+//! 1bitずつ受け取る復号器のモデル
+//!
+//! シフトレジスタで作る復号回路と同じく，受信語を1クロックに1bitずつ（bit 23から）受け取り，
+//! 受信ビットのシフトレジスタとシンドロームのレジスタを更新する．
+//! 24bit目を受け取ったクロックで判定を出し，レジスタを空にして次の語を待つ．
+//!
+//! レジスタは24bitと12bitとカウンタだけなので，RAMの少ないターゲットでも使える．
+//! レジスタの値を`shift_register`と`syndrome`で読めるので，RTLのシミュレーションと
+//! クロックごとに突き合わせる基準モデルにもなる．
+
+use crate::{Decode, H_T};
+
+/// 1bitずつ受け取る復号器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BitSerialDecoder {
+    shift: u32,     // 受け取ったビット（最後に受け取ったものが最下位）
+    syndrome: u32,  // 受け取った分のシンドローム
+    count: u8,      // 受け取ったビット数
+}
+
+impl BitSerialDecoder {
+    /// 空の状態で作る．
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 1bit受け取る．
+    ///
+    /// * return: 24bit目を受け取ったときは判定，それ以外はNone
+    pub fn clock(&mut self, bit: bool) -> Option<Decode> {
+        self.shift = (self.shift << 1) | bit as u32;
+        if bit {
+            self.syndrome ^= H_T[self.count as usize];
+        }
+        self.count += 1;
+        if self.count < 24 {
+            return None;
+        }
+
+        let r = self.shift & 0xFFFFFF;
+        let result = match crate::syndrome_error_pattern(self.syndrome) {
+            Some(0) => Decode::Clean(r),
+            Some(e) => Decode::Corrected(r ^ e, e.count_ones() as u8),
+            None    => Decode::Detected,
+        };
+        self.reset();
+        Some(result)
+    }
+
+    /// 受け取りかけのビットを捨てる（同期が外れたときなど）．
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 今の語で受け取ったビット数（0〜23）
+    #[inline]
+    pub fn count(&self) -> u8 {
+        self.count
+    }
+
+    /// 受信ビットのシフトレジスタ
+    #[inline]
+    pub fn shift_register(&self) -> u32 {
+        self.shift
+    }
+
+    /// シンドロームのレジスタ（下位12bit）
+    #[inline]
+    pub fn syndrome(&self) -> u32 {
+        self.syndrome
+    }
+}
+
+#[test]
+fn test_bit_serial() {
+    let mut d = BitSerialDecoder::new();
+    for (data, e) in [(0x5A3, 0), (0x001, 0x800402), (0xFFF, 0x00000F), (0x7C0, 0x000100)].iter() {
+        let r = crate::encode(*data) ^ e;
+        for i in 0..23 {
+            assert_eq!(d.clock((r >> (23 - i)) & 1 == 1), None);
+        }
+        assert_eq!(d.count(), 23);
+        assert_eq!(d.clock(r & 1 == 1), Some(crate::ecc_status(r)));
+        assert_eq!(d.count(), 0);
+    }
+
+    // 途中のレジスタの値
+    let r = crate::encode(0x800);
+    for i in 0..12 {
+        d.clock((r >> (23 - i)) & 1 == 1);
+    }
+    assert_eq!(d.shift_register(), 0x800);
+    assert_eq!(d.syndrome(), H_T[0]);
+    d.reset();
+    assert_eq!(d, BitSerialDecoder::new());
+}
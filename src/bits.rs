@@ -0,0 +1,103 @@
+//! ビットごとの入出力
+//!
+//! LEDマトリクスやGPIOのバンク，スキャンチェーンのようにビットごとに扱う相手のために，
+//! データを`[bool; 12]`やビットのイテレータで受け渡す．
+//! 添字0がデータの最上位ビット（bit 11）に対応する．
+
+use crate::layout::{BitOrder, Layout, Word};
+
+/// `[bool; 12]`のデータを符号化する．
+pub fn encode_bools(data: &[bool; 12]) -> u32 {
+    crate::encode( data.iter().fold(0, |a, b| (a << 1) | *b as u16) )
+}
+
+/// 符号語からデータを`[bool; 12]`で取り出す．
+pub fn decode_bools(code: u32) -> [bool; 12] {
+    let data = crate::decode(code);
+    let mut out = [false; 12];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = (data >> (11 - i)) & 1 == 1;
+    }
+    out
+}
+
+/// 受信語を訂正して，データを`[bool; 12]`で取り出す．訂正できなければNone．
+#[inline]
+pub fn ecc_bools(r: u32) -> Option<[bool; 12]> {
+    crate::ecc(r).map(decode_bools)
+}
+
+/// 符号語のデータのビットを上位から順に返すイテレータを作る．
+#[inline]
+pub fn data_bits(code: u32) -> Bits {
+    Bits{ word: crate::decode(code) as u32, remaining: 12 }
+}
+
+/// 24bitの語の全ビットを上位から順に返すイテレータを作る．
+#[inline]
+pub fn code_bits(r: u32) -> Bits {
+    Bits{ word: r & 0xFFFFFF, remaining: 24 }
+}
+
+/// 上位ビットから順にビットを返すイテレータ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bits {
+    word: u32,
+    remaining: u32,
+}
+
+impl Iterator for Bits {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<bool> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some( (self.word >> self.remaining) & 1 == 1 )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl ExactSizeIterator for Bits {}
+
+impl<L: Layout, O: BitOrder> Word<L, O> {
+    /// データのビットを上位から順に返す．
+    #[inline]
+    pub fn data_bits(&self) -> Bits {
+        Bits{ word: self.decode() as u32, remaining: 12 }
+    }
+
+    /// データを`[bool; 12]`で取り出す．
+    #[inline]
+    pub fn to_bools(&self) -> [bool; 12] {
+        decode_bools( L::to_native(self.raw()) )
+    }
+}
+
+#[test]
+fn test_bits() {
+    use crate::codeword::Codeword;
+
+    let data = [true, false, true, true, false, false, false, true, true, true, false, true];
+    let code = encode_bools(&data);
+    assert_eq!(code, crate::encode(0b1011_0001_1101));
+    assert_eq!(decode_bools(code), data);
+    assert_eq!(ecc_bools(code ^ 0x400020), Some(data));
+    assert_eq!(ecc_bools(code ^ 0xF), None);
+
+    let bits = data_bits(code);
+    assert_eq!(bits.len(), 12);
+    assert!(bits.eq(data.iter().copied()));
+    assert!(Codeword::from(code).data_bits().eq(Codeword::from(code).to_bools().iter().copied()));
+    let other: crate::layout::Word<crate::layout::ParityHigh, crate::layout::LsbFirst> = Codeword::from(code).convert();
+    assert_eq!(other.to_bools(), data);
+    assert_eq!(code_bits(code).len(), 24);
+    assert_eq!(code_bits(0x800001).filter(|b| *b).count(), 2);
+    assert_eq!(code_bits(0x800000).next(), Some(true));
+}
@@ -0,0 +1,123 @@
+//! 生成行列 G = [I | B] の B 行列
+//!
+//! このクレートの生成行列の右半分（12×12）を B とすると，G = [I | B]，H^T = [B ; I] になる．
+//! B・B^T = I なので符号は自己双対．
+//!
+//! 教科書でよく使われる形は，11を法とする平方剰余から作る11×11の逆巡回行列を1で囲んだもの
+//! （`bordered_reverse_circulant`）．このクレートの B はこれと列の並べ替えの分だけ違う．
+//! 最小距離8の[24,12]2元符号はゴレイ符号しかないので，最小距離を確かめれば同値だと言える．
+
+/// このクレートの B 行列（各行の下位12bit，最上位ビットが1列目）
+pub fn b() -> [u32; 12] {
+    let mut out = [0; 12];
+    for (row, g_line) in out.iter_mut().zip(crate::G.iter()) {
+        *row = g_line & 0xFFF;
+    }
+    out
+}
+
+/// 平方剰余から作った，1で囲んだ逆巡回行列の B
+///
+/// 1行目は {0} ∪ {11の平方剰余} = {0, 1, 3, 4, 5, 9} の列が1で，以降の行は1つずつ左に巡回シフトする．
+/// 最後の列は1，最後の行は最後の列以外が1．
+pub const fn bordered_reverse_circulant() -> [u32; 12] {
+    const FIRST: [u8; 11] = [1, 1, 0, 1, 1, 1, 0, 0, 0, 1, 0];
+    let mut out = [0; 12];
+    let mut i = 0;
+    while i < 11 {
+        let mut row = 1;  // 最後の列
+        let mut j = 0;
+        while j < 11 {
+            row |= (FIRST[(i + j) % 11] as u32) << (11 - j);
+            j += 1;
+        }
+        out[i] = row;
+        i += 1;
+    }
+    out[11] = 0xFFE;
+    out
+}
+
+/// B から生成行列 G = [I | B] を作る．
+pub fn generator(b: &[u32; 12]) -> [u32; 12] {
+    let mut out = [0; 12];
+    for (i, (g_line, b_line)) in out.iter_mut().zip(b.iter()).enumerate() {
+        *g_line = (0x800000 >> i) | (b_line & 0xFFF);
+    }
+    out
+}
+
+/// B から検査行列の転置 H^T = [B ; I] を作る．
+pub fn parity_check(b: &[u32; 12]) -> [u32; 24] {
+    let mut out = [0; 24];
+    for (i, h_t_line) in out.iter_mut().enumerate() {
+        *h_t_line = if i < 12 { b[i] & 0xFFF } else { 0x800 >> (i - 12) };
+    }
+    out
+}
+
+/// 生成行列が作る符号の最小距離（0以外の符号語の最小の重み）
+pub fn min_distance(g: &[u32; 12]) -> u32 {
+    let mut min = 24;
+    for data in 1..4096u32 {
+        min = min.min(crate::gf2::vec_mul(data, g).count_ones());
+    }
+    min
+}
+
+/// B・B^T = I を確かめる．
+fn is_orthogonal(b: &[u32; 12]) -> bool {
+    for i in 0..12 {
+        for j in 0..12 {
+            let dot = (b[i] & b[j]).count_ones() & 1;
+            if dot != (i == j) as u32 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// B から作った符号が，このクレートの符号とビットの並べ替えで一致するか確かめる．
+///
+/// `codec::Codec`で並べ替えを探し，それが24bitの並べ替えになっていて，
+/// 並べ替えた生成行列の各行がこのクレートの符号語になることを確かめる．
+pub fn is_equivalent(b: &[u32; 12]) -> bool {
+    let g = generator(b);
+    let codec = match crate::codec::Codec::new(&g) {
+        Ok(codec) => codec,
+        Err(_) => return false,
+    };
+    let mut image = 0;
+    for i in 0..24 {
+        image |= codec.to_native(1 << i);
+    }
+    image == 0xFFFFFF
+        && g.iter().all(|row| crate::syndrome( codec.to_native(*row) ) == 0)
+}
+
+/// このクレートの生成行列と検査行列が B から作ったものと一致し，
+/// 符号が自己双対で，教科書の B と同値な符号になっていることを確かめる．
+pub fn verify() -> bool {
+    let b = b();
+    generator(&b) == crate::G
+        && parity_check(&b) == crate::H_T
+        && is_orthogonal(&b)
+        && is_orthogonal(&bordered_reverse_circulant())
+        && is_equivalent(&bordered_reverse_circulant())
+}
+
+#[test]
+fn test_bmatrix() {
+    assert!(verify());
+    assert_eq!(min_distance(&crate::G), 8);
+    assert_eq!(bordered_reverse_circulant()[0], 0b110111000101);
+    assert_eq!(bordered_reverse_circulant()[10], 0b011011100011);
+
+    let mut broken = b();
+    broken[3] ^= 0x10;
+    assert!(!is_orthogonal(&broken));
+    assert!(min_distance(&generator(&broken)) < 8);
+    assert!(!is_equivalent(&broken));
+    assert!(is_equivalent(&b()));
+}
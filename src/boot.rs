@@ -0,0 +1,196 @@
+//! ブートローダのイメージ情報の保護
+//!
+//! ブートローダが持つ小さなメタデータ（イメージの長さ，バージョン，エントリアドレス，アプリのCRC）を
+//! 符号化し，同じものを複数コピー書き込む．1bitの反転で起動できなくなるのを防ぐ．
+//!
+//! 読み出しでは各コピーを訂正し，訂正できたコピーの多数決で値を決める．
+//! どのコピーも訂正できない場合は，コピー間でビットごとに多数決をとった語を訂正してみる．
+//!
+//! 1コピーは`META_LEN`バイトで，コピーは続けて並べる．
+
+use crate::protect::{self, GolayProtect, RawReader, RawWriter};
+
+/// 1コピーのバイト数
+pub const META_LEN: usize = protect::protected_len(16);
+
+/// コピー数の上限
+pub const MAX_COPIES: usize = 7;
+
+/// イメージの情報
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImageMeta {
+    /// イメージのバイト数
+    pub length: u32,
+    /// バージョン
+    pub version: u32,
+    /// エントリアドレス
+    pub entry: u32,
+    /// イメージのCRC-32
+    pub crc: u32,
+}
+
+impl GolayProtect for ImageMeta {
+    const RAW_LEN: usize = 16;
+
+    fn write_raw(&self, w: &mut RawWriter) {
+        self.length.write_raw(w);
+        self.version.write_raw(w);
+        self.entry.write_raw(w);
+        self.crc.write_raw(w);
+    }
+
+    fn read_raw(r: &mut RawReader) -> Self {
+        Self{
+            length: u32::read_raw(r),
+            version: u32::read_raw(r),
+            entry: u32::read_raw(r),
+            crc: u32::read_raw(r),
+        }
+    }
+}
+
+impl ImageMeta {
+    /// イメージから情報を作る．
+    pub fn for_image(image: &[u8], version: u32, entry: u32) -> Self {
+        Self{ length: image.len() as u32, version, entry, crc: crc32(image) }
+    }
+
+    /// イメージの長さとCRCが合っているか確かめる．
+    pub fn verify_image(&self, image: &[u8]) -> bool {
+        image.len() == self.length as usize && crc32(image) == self.crc
+    }
+}
+
+/// イメージ情報の読み出しのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetaError {
+    /// コピー数が1〜`MAX_COPIES`でないか，バイト列が短い
+    InvalidLength,
+    /// どのコピーからも値を決められない
+    Unrecoverable,
+    /// 訂正できたコピーの値が食い違い，多数決で決まらない
+    Conflict,
+}
+
+/// 多数決の結果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Voted {
+    /// 決まった値
+    pub meta: ImageMeta,
+    /// 決まった値と一致したコピーの数
+    pub agreeing: usize,
+    /// コピー数
+    pub copies: usize,
+}
+
+impl Voted {
+    /// 全てのコピーが一致していたか（一致していなければ`write_copies`で書き直す）
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.agreeing == self.copies
+    }
+}
+
+/// 情報を`copies`個書き込む．
+///
+/// * return: 書き込んだバイト数．`out`が足りないかコピー数が不正ならNone．
+pub fn write_copies(meta: &ImageMeta, out: &mut [u8], copies: usize) -> Option<usize> {
+    if copies == 0 || copies > MAX_COPIES || out.len() < copies * META_LEN {
+        return None;
+    }
+    for chunk in out.chunks_exact_mut(META_LEN).take(copies) {
+        meta.to_protected_bytes(chunk)?;
+    }
+    Some(copies * META_LEN)
+}
+
+/// 書き込まれた`copies`個のコピーから情報を読み出す．
+pub fn read_copies(bytes: &[u8], copies: usize) -> Result<Voted, MetaError> {
+    if copies == 0 || copies > MAX_COPIES || bytes.len() < copies * META_LEN {
+        return Err(MetaError::InvalidLength);
+    }
+    let mut values = [None; MAX_COPIES];
+    for (v, chunk) in values.iter_mut().zip(bytes.chunks_exact(META_LEN).take(copies)) {
+        *v = ImageMeta::from_protected_bytes(chunk);
+    }
+
+    // 訂正できたコピーの多数決
+    let mut best: Option<(ImageMeta, usize)> = None;
+    let mut tie = false;
+    for v in values.iter().flatten() {
+        let n = values.iter().filter(|x| **x == Some(*v)).count();
+        match best {
+            Some((m, b)) if n < b || (n == b && m == *v) => {},
+            Some((_, b)) if n == b => tie = true,
+            _ => {
+                best = Some((*v, n));
+                tie = false;
+            },
+        }
+    }
+    if let Some((meta, agreeing)) = best {
+        if tie {
+            return Err(MetaError::Conflict);
+        }
+        return Ok( Voted{ meta, agreeing, copies } );
+    }
+
+    // ビットごとの多数決をとってから訂正する
+    let mut voted = [0u8; META_LEN];
+    for (i, v) in voted.iter_mut().enumerate() {
+        for bit in 0..8 {
+            let ones = (0..copies).filter(|k| (bytes[k * META_LEN + i] >> bit) & 1 == 1).count();
+            if 2 * ones > copies {
+                *v |= 1 << bit;
+            }
+        }
+    }
+    match ImageMeta::from_protected_bytes(&voted) {
+        Some(meta) => Ok( Voted{ meta, agreeing: 0, copies } ),
+        None => Err(MetaError::Unrecoverable),
+    }
+}
+
+/// CRC-32（IEEE 802.3）
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[test]
+fn test_boot() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+    let image = [0x5Au8; 100];
+    let meta = ImageMeta::for_image(&image, 3, 0x0800_4000);
+    assert!(meta.verify_image(&image));
+    assert!(!meta.verify_image(&image[1..]));
+
+    let mut flash = [0u8; 3 * META_LEN];
+    assert_eq!(write_copies(&meta, &mut flash, 3), Some(3 * META_LEN));
+    assert_eq!(write_copies(&meta, &mut flash[1..], 3), None);
+    assert!(read_copies(&flash, 3).unwrap().is_clean());
+
+    // 1つ目のコピーは訂正できない，2つ目は訂正できる
+    flash[0] ^= 0x0F;
+    flash[META_LEN + 5] ^= 0x01;
+    let v = read_copies(&flash, 3).unwrap();
+    assert_eq!((v.meta, v.agreeing), (meta, 2));
+
+    // どのコピーも訂正できないが，壊れている語が違う
+    flash[META_LEN + 9] ^= 0xF0;
+    flash[2 * META_LEN + 6] ^= 0x3C;
+    assert_eq!(read_copies(&flash, 3), Ok(Voted{ meta, agreeing: 0, copies: 3 }));
+
+    // 2つのコピーが別の値
+    let mut flash2 = [0u8; 2 * META_LEN];
+    write_copies(&meta, &mut flash2, 1);
+    write_copies(&ImageMeta{ version: 4, ..meta }, &mut flash2[META_LEN..], 1);
+    assert_eq!(read_copies(&flash2, 2), Err(MetaError::Conflict));
+}
@@ -0,0 +1,134 @@
+//! CANフレームのペイロード
+//!
+//! ペイロードに3バイトずつ符号語を詰める．
+//! クラシックCANの8バイトには符号語2つ（データ3バイト）が入り，残りの2バイトは0にする．
+//! CAN FDでは長さに応じて符号語を増やす．
+//!
+//! データは`protect`と同じく上位ビットから12bitずつ区切って符号化する．
+//! データが容量より短い場合は後ろを0で埋めて符号化する．
+
+use super::protect::{RawReader, RawWriter};
+
+/// クラシックCANのペイロード長
+pub const CLASSIC_LEN: usize = 8;
+
+/// CAN FDで使えるペイロード長
+pub const FD_LENGTHS: [usize; 8] = [8, 12, 16, 20, 24, 32, 48, 64];
+
+/// CANのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CanError {
+    /// CAN（FD）のペイロード長として正しくない
+    InvalidLength,
+    /// データがペイロードに入りきらない
+    TooLong,
+    /// 訂正できない誤りを検出した
+    Uncorrectable,
+}
+
+/// ペイロードに入る符号語の数
+#[inline]
+pub fn codewords(frame_len: usize) -> usize {
+    frame_len / 3
+}
+
+/// ペイロードに入るデータのバイト数
+///
+/// * `frame_len`: ペイロード長．`FD_LENGTHS`のどれか．
+#[inline]
+pub fn capacity(frame_len: usize) -> usize {
+    codewords(frame_len) * 12 / 8
+}
+
+/// `data_len`バイトのデータが入る最小のCAN FDのペイロード長
+pub fn fd_len_for(data_len: usize) -> Option<usize> {
+    FD_LENGTHS.iter().copied().find(|len| capacity(*len) >= data_len)
+}
+
+/// データを符号化してペイロードに詰める．
+///
+/// * `data`: データ（`capacity(frame.len())`バイト以下）
+/// * `frame`: ペイロード．長さは`FD_LENGTHS`のどれか．
+pub fn pack(data: &[u8], frame: &mut [u8]) -> Result<(), CanError> {
+    if !FD_LENGTHS.contains(&frame.len()) {
+        return Err(CanError::InvalidLength);
+    }
+    let cap = capacity(frame.len());
+    if data.len() > cap {
+        return Err(CanError::TooLong);
+    }
+    for b in frame.iter_mut() {
+        *b = 0;
+    }
+    let mut w = RawWriter::new(frame);
+    w.write(data);
+    for _ in data.len()..cap {
+        w.write(&[0]);
+    }
+    w.finish().ok_or(CanError::TooLong)?;
+    Ok(())
+}
+
+/// ペイロードを訂正してデータを取り出す．
+///
+/// * `frame`: 受信したペイロード
+/// * `data`: 出力先（`capacity(frame.len())`バイト以下．それより後ろのデータは捨てる）
+/// * return: 訂正したビット数
+pub fn unpack(frame: &[u8], data: &mut [u8]) -> Result<u32, CanError> {
+    if !FD_LENGTHS.contains(&frame.len()) {
+        return Err(CanError::InvalidLength);
+    }
+    if data.len() > capacity(frame.len()) {
+        return Err(CanError::TooLong);
+    }
+    let mut r = RawReader::new(frame);
+    r.read(data);
+    if r.failed {
+        return Err(CanError::Uncorrectable);
+    }
+    Ok(r.corrected)
+}
+
+/// アプリケーションのデータを1フレーム分ずつに分ける．
+///
+/// 受信側は取り出したデータを順に連結すれば元に戻る．
+///
+/// * `frame_len`: ペイロード長．`FD_LENGTHS`のどれか．
+#[inline]
+pub fn split(data: &[u8], frame_len: usize) -> core::slice::Chunks<'_, u8> {
+    data.chunks( capacity(frame_len).max(1) )
+}
+
+#[test]
+fn test_can() {
+    assert_eq!(capacity(CLASSIC_LEN), 3);
+    assert_eq!(capacity(64), 31);
+    assert_eq!(fd_len_for(10), Some(24));
+    assert_eq!(fd_len_for(32), None);
+
+    let mut frame = [0xAAu8; CLASSIC_LEN];
+    pack(&[0x12, 0x34, 0x56], &mut frame).unwrap();
+    assert_eq!(&frame[6..], &[0, 0]);
+    frame[0] ^= 0b1010_0000;
+    frame[4] ^= 0b0000_0111;
+    let mut data = [0u8; 3];
+    assert_eq!(unpack(&frame, &mut data), Ok(5));
+    assert_eq!(data, [0x12, 0x34, 0x56]);
+    frame[1] ^= 0x0F;
+    assert_eq!(unpack(&frame, &mut data), Err(CanError::Uncorrectable));
+
+    // 分けて送って連結する
+    let msg = b"safety relevant payload";
+    let mut rx = [0u8; 23];
+    let mut pos = 0;
+    for chunk in split(msg, 20) {
+        let mut frame = [0u8; 20];
+        pack(chunk, &mut frame).unwrap();
+        unpack(&frame, &mut rx[pos..pos + chunk.len()]).unwrap();
+        pos += chunk.len();
+    }
+    assert_eq!(&rx, msg);
+
+    assert_eq!(pack(&[0; 4], &mut [0u8; 8]), Err(CanError::TooLong));
+    assert_eq!(pack(&[0; 1], &mut [0u8; 9]), Err(CanError::InvalidLength));
+}
@@ -0,0 +1,121 @@
+//! 送信時のビット位置の並べ替え
+//!
+//! 既存の機器と送信時のビットの並びを合わせたり，マルチキャリアの変調で
+//! 符号語の隣り合うビットを離れたキャリアに載せたりするために，24bitの位置を並べ替える．
+//! 並べ替えは送信直前と受信直後にだけ行うので，符号化と復号はそのまま使える．
+//!
+//! 並べ替えは作るときに一度だけ確かめ，逆の並べ替えも作っておく．
+
+/// 並べ替えのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapError {
+    /// 24以上の位置がある
+    OutOfRange,
+    /// 同じ位置が2回ある
+    Duplicate,
+}
+
+/// 24bitの位置の並べ替え
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChannelMap {
+    forward: [u8; 24],  // 符号語のbit iを送る位置
+    inverse: [u8; 24],  // 受信語のbit jが符号語のどのビットか
+}
+
+impl ChannelMap {
+    /// 並べ替えを作る．
+    ///
+    /// * `perm`: `perm[i]`は符号語のbit iを送る位置（0〜23，重複なし）
+    pub fn new(perm: &[u8; 24]) -> Result<Self, MapError> {
+        let mut inverse = [0xFF; 24];
+        for (i, p) in perm.iter().enumerate() {
+            let slot = inverse.get_mut(*p as usize).ok_or(MapError::OutOfRange)?;
+            if *slot != 0xFF {
+                return Err(MapError::Duplicate);
+            }
+            *slot = i as u8;
+        }
+        Ok( Self{ forward: *perm, inverse } )
+    }
+
+    /// 並べ替えない．
+    pub fn identity() -> Self {
+        let mut perm = [0; 24];
+        for (i, p) in perm.iter_mut().enumerate() {
+            *p = i as u8;
+        }
+        Self{ forward: perm, inverse: perm }
+    }
+
+    /// 符号語のbit iを位置 (i × `step`) mod 24 に送る．
+    ///
+    /// 隣り合うビットが`step`だけ離れる．`step`が24と互いに素でなければ`MapError::Duplicate`．
+    pub fn stride(step: u8) -> Result<Self, MapError> {
+        let mut perm = [0; 24];
+        for (i, p) in perm.iter_mut().enumerate() {
+            *p = ((i * step as usize) % 24) as u8;
+        }
+        Self::new(&perm)
+    }
+
+    /// 符号語のbit iを送る位置
+    #[inline]
+    pub fn positions(&self) -> &[u8; 24] {
+        &self.forward
+    }
+
+    /// 送信するときの並べ替え
+    pub fn apply(&self, code: u32) -> u32 {
+        permute(code, &self.forward)
+    }
+
+    /// 受信したときの逆の並べ替え．誤りの位置や消失の位置のマスクにも使える．
+    pub fn invert(&self, r: u32) -> u32 {
+        permute(r, &self.inverse)
+    }
+
+    /// データを符号化して並べ替える．
+    #[inline]
+    pub fn encode(&self, data: u16) -> u32 {
+        self.apply( crate::encode(data) )
+    }
+
+    /// 受信語を戻して訂正し，データを取り出す．訂正できなければNone．
+    #[inline]
+    pub fn decode(&self, r: u32) -> Option<u16> {
+        crate::ecc( self.invert(r) ).map(crate::decode)
+    }
+}
+
+impl Default for ChannelMap {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// bit iをbit `to[i]`に移す．
+fn permute(word: u32, to: &[u8; 24]) -> u32 {
+    to.iter().enumerate().fold(0, |out, (i, p)| out | (((word >> i) & 1) << p))
+}
+
+#[test]
+fn test_channel_map() {
+    let map = ChannelMap::stride(5).unwrap();
+    assert_eq!(map.positions()[1], 5);
+    assert_eq!(ChannelMap::stride(6), Err(MapError::Duplicate));
+    let mut perm = *ChannelMap::identity().positions();
+    perm[3] = 24;
+    assert_eq!(ChannelMap::new(&perm), Err(MapError::OutOfRange));
+    assert_eq!(ChannelMap::default().apply(0xABCDEF), 0xABCDEF);
+
+    for data in [0x000, 0x5A5, 0xFFF, 0x123].iter() {
+        let code = crate::encode(*data);
+        let tx = map.encode(*data);
+        assert_eq!(map.invert(tx), code);
+        assert_eq!(tx.count_ones(), code.count_ones());
+        // 送信時に隣り合う3bitの誤り
+        assert_eq!(map.decode(tx ^ 0x000700), Some(*data));
+    }
+    assert_eq!(map.apply(1 << 2), 1 << 10);
+    assert_eq!(map.invert(1 << 10), 1 << 2);
+}
@@ -0,0 +1,216 @@
+//! 少しずつ進める符号化と復号
+//!
+//! 大きなバッファを`protect`と同じ形式（12bitずつ符号化して3バイトにする）で符号化・復号する．
+//! 1回の`poll`で処理する符号語は`budget`個までで，続きは次の呼び出しで行う．
+//! スーパーループや協調的なスケジューラから呼んでも，他のタスクを長く待たせない．
+
+use crate::protect::protected_len;
+
+/// `poll`の結果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// まだ残りがある
+    Pending,
+    /// 全て処理した
+    Done,
+}
+
+/// データの`k`番目の12bit（足りないビットは0）
+fn get_12(data: &[u8], k: usize) -> u16 {
+    let j = 3 * k / 2;
+    let b0 = *data.get(j).unwrap_or(&0) as u16;
+    let b1 = *data.get(j + 1).unwrap_or(&0) as u16;
+    if k.is_multiple_of(2) {
+        (b0 << 4) | (b1 >> 4)
+    } else {
+        ((b0 & 0xF) << 8) | b1
+    }
+}
+
+/// データの`k`番目の12bitを書き込む（範囲外のビットは捨てる）．
+fn set_12(out: &mut [u8], k: usize, a: u16) {
+    let j = 3 * k / 2;
+    if k.is_multiple_of(2) {
+        if let Some(b) = out.get_mut(j) {
+            *b = (a >> 4) as u8;
+        }
+        if let Some(b) = out.get_mut(j + 1) {
+            *b = (*b & 0x0F) | ((a as u8) << 4);
+        }
+    } else {
+        if let Some(b) = out.get_mut(j) {
+            *b = (*b & 0xF0) | (a >> 8) as u8;
+        }
+        if let Some(b) = out.get_mut(j + 1) {
+            *b = a as u8;
+        }
+    }
+}
+
+/// 再開できる符号化
+pub struct ChunkedEncoder<'a> {
+    data: &'a [u8],
+    out: &'a mut [u8],
+    budget: usize,
+    next: usize,    // 次に符号化する符号語
+    words: usize,   // 符号語の数
+}
+
+impl<'a> ChunkedEncoder<'a> {
+    /// * `data`: 符号化するデータ
+    /// * `out`: 出力先（`protected_len(data.len())`バイト以上）
+    /// * `budget`: 1回の`poll`で符号化する符号語の数（1以上）
+    pub fn new(data: &'a [u8], out: &'a mut [u8], budget: usize) -> Option<Self> {
+        if budget == 0 || out.len() < protected_len(data.len()) {
+            return None;
+        }
+        Some( Self{ data, out, budget, next: 0, words: protected_len(data.len()) / 3 } )
+    }
+
+    /// 符号語を`budget`個まで符号化する．
+    pub fn poll(&mut self) -> Status {
+        let end = self.words.min(self.next + self.budget);
+        for k in self.next..end {
+            let code = crate::encode( get_12(self.data, k) );
+            self.out[3 * k..3 * k + 3].copy_from_slice(&code.to_be_bytes()[1..]);
+        }
+        self.next = end;
+        self.status()
+    }
+
+    /// 今の状態
+    #[inline]
+    pub fn status(&self) -> Status {
+        if self.next < self.words { Status::Pending } else { Status::Done }
+    }
+
+    /// （処理した符号語の数，全体の符号語の数）
+    #[inline]
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.words)
+    }
+
+    /// 書き込んだ（書き込む）バイト数
+    #[inline]
+    pub fn output_len(&self) -> usize {
+        3 * self.words
+    }
+}
+
+/// 再開できる復号
+pub struct ChunkedDecoder<'a> {
+    protected: &'a [u8],
+    out: &'a mut [u8],
+    budget: usize,
+    next: usize,
+    words: usize,
+    corrected: u32,
+    uncorrectable: u32,
+    first_uncorrectable: Option<usize>,
+}
+
+impl<'a> ChunkedDecoder<'a> {
+    /// * `protected`: 符号化したバイト列（`protected_len(out.len())`バイト以上）
+    /// * `out`: 復号したデータの書き込み先．長さが元のデータのバイト数．
+    /// * `budget`: 1回の`poll`で復号する符号語の数（1以上）
+    pub fn new(protected: &'a [u8], out: &'a mut [u8], budget: usize) -> Option<Self> {
+        if budget == 0 || protected.len() < protected_len(out.len()) {
+            return None;
+        }
+        let words = protected_len(out.len()) / 3;
+        Some( Self{ protected, out, budget, next: 0, words, corrected: 0, uncorrectable: 0, first_uncorrectable: None } )
+    }
+
+    /// 符号語を`budget`個まで訂正して復号する．
+    ///
+    /// 訂正できない符号語は受信語のデータ部分をそのまま書き込み，数を記録して続ける．
+    pub fn poll(&mut self) -> Status {
+        let end = self.words.min(self.next + self.budget);
+        for k in self.next..end {
+            let c = &self.protected[3 * k..3 * k + 3];
+            let r = u32::from_be_bytes([0, c[0], c[1], c[2]]);
+            let a = match crate::ecc_verbose(r) {
+                Some(c) => {
+                    self.corrected += c.errors as u32;
+                    crate::decode(c.code)
+                },
+                None => {
+                    self.uncorrectable += 1;
+                    self.first_uncorrectable.get_or_insert(k);
+                    crate::decode(r)
+                },
+            };
+            set_12(self.out, k, a);
+        }
+        self.next = end;
+        self.status()
+    }
+
+    /// 今の状態
+    #[inline]
+    pub fn status(&self) -> Status {
+        if self.next < self.words { Status::Pending } else { Status::Done }
+    }
+
+    /// （処理した符号語の数，全体の符号語の数）
+    #[inline]
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.words)
+    }
+
+    /// これまでに訂正したビット数
+    #[inline]
+    pub fn corrected(&self) -> u32 {
+        self.corrected
+    }
+
+    /// これまでに訂正できなかった符号語の数
+    #[inline]
+    pub fn uncorrectable(&self) -> u32 {
+        self.uncorrectable
+    }
+
+    /// 最初に訂正できなかった符号語の番号
+    #[inline]
+    pub fn first_uncorrectable(&self) -> Option<usize> {
+        self.first_uncorrectable
+    }
+}
+
+#[test]
+fn test_chunked() {
+    let mut data = [0u8; 37];
+    for (i, d) in data.iter_mut().enumerate() {
+        *d = (i as u8).wrapping_mul(73) ^ 0x5C;
+    }
+    let mut out = [0u8; protected_len(37)];
+    let mut enc = ChunkedEncoder::new(&data, &mut out, 4).unwrap();
+    let mut polls = 1;
+    while enc.poll() == Status::Pending {
+        polls += 1;
+    }
+    assert_eq!(enc.progress(), (25, 25));
+    assert_eq!(polls, 7);
+
+    // `protect`のRawWriterと同じ形式になる
+    let mut expected = [0u8; protected_len(37)];
+    let mut w = crate::protect::RawWriter::new(&mut expected);
+    w.write(&data);
+    assert_eq!(w.finish(), Some(expected.len()));
+    assert_eq!(out, expected);
+
+    out[4] ^= 0x81;
+    out[30] ^= 0x10;
+    out[60] ^= 0xF0;
+    let mut back = [0u8; 37];
+    let mut dec = ChunkedDecoder::new(&out, &mut back, 10).unwrap();
+    assert_eq!(dec.poll(), Status::Pending);
+    assert_eq!(dec.progress(), (10, 25));
+    while dec.poll() == Status::Pending {}
+    assert_eq!((dec.corrected(), dec.uncorrectable(), dec.first_uncorrectable()), (3, 1, Some(20)));
+    assert_eq!(back[..30], data[..30]);
+    assert_eq!(back[31..], data[31..]);
+
+    assert!(ChunkedEncoder::new(&data, &mut [0u8; 10], 1).is_none());
+    assert!(ChunkedDecoder::new(&expected, &mut back, 0).is_none());
+}
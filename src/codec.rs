@@ -0,0 +1,258 @@
+//! 利用者が与えた生成行列による符号化と復号
+//!
+//! 列の並べ方が違うゴレイ符号を使う相手と通信するためのもの．
+//! 与えられた12×24の生成行列の階数と最小距離を確かめ，
+//! 重み8の符号語（オクタッド）が対応するように，このクレートの符号語とのビットの並べ替えを探す．
+//! 最小距離8の[24,12]2元符号はゴレイ符号しかないので，並べ替えは必ず見つかる．
+//!
+//! 訂正は並べ替えてからこのクレートの復号器で行うので，`decoder`のどの復号器も使える．
+//! フレーム化などには`to_native`で並べ替えた符号語を渡せばよい．
+//! `to_word`と`from_word`は，配置とビット順を型引数で指定した`layout::Word`と相互に変換する．
+
+use crate::decoder::Decoder;
+use crate::decoder::restricted::Accept;
+use crate::layout::{BitOrder, Layout, Word};
+use crate::metrics::MetricsSink;
+
+/// 生成行列のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatrixError {
+    /// 階数が12でない
+    Rank,
+    /// 最小距離が8でない
+    Distance,
+}
+
+/// オクタッドの数
+const N_OCTADS: usize = 759;
+
+/// 利用者が与えた生成行列の符号
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Codec {
+    g: [u32; 12],
+    perm: [u8; 24],    // 利用者の符号語のbit iが，このクレートの符号語のbit perm[i]に対応する
+    pivots: [u8; 12],  // 簡約した生成行列の各行の先頭の列
+    t: [u16; 12],      // 簡約した生成行列の各行が，元の生成行列のどの行の和か
+}
+
+impl Codec {
+    /// 生成行列から作る．
+    ///
+    /// * `g`: 生成行列．i行目の下位24bitで，データのbit (11 - i) に対応する．
+    pub fn new(g: &[u32; 12]) -> Result<Self, MatrixError> {
+        let mut g = *g;
+        for row in g.iter_mut() {
+            *row &= 0xFFFFFF;
+        }
+        let (pivots, t) = reduce(&g).ok_or(MatrixError::Rank)?;
+        if crate::bmatrix::min_distance(&g) != 8 {
+            return Err(MatrixError::Distance);
+        }
+
+        let mut octads = [0u32; N_OCTADS];
+        let mut n = 0;
+        for data in 1..4096 {
+            let code = multiply(&g, data);
+            if code.count_ones() == 8 {
+                octads[n] = code;
+                n += 1;
+            }
+        }
+        let mut perm = [0u8; 24];
+        if !search(&octads, &mut perm, 0, 0) {
+            return Err(MatrixError::Distance);  // ゴレイ符号なら起こらない
+        }
+        Ok( Self{ g, perm, pivots, t } )
+    }
+
+    /// 12bitのデータを符号化する．
+    #[inline]
+    pub fn encode(&self, data: u16) -> u32 {
+        multiply(&self.g, data)
+    }
+
+    /// 符号語からデータを取り出す．
+    pub fn decode(&self, code: u32) -> u16 {
+        let mut data = 0;
+        for (p, t_line) in self.pivots.iter().zip(self.t.iter()) {
+            if (code >> p) & 1 == 1 {
+                data ^= t_line;
+            }
+        }
+        data
+    }
+
+    /// 受信語の誤り訂正を行う．訂正できない場合はNone．
+    #[inline]
+    pub fn ecc(&self, r: u32) -> Option<u32> {
+        self.ecc_with(&crate::decoder::Arithmetic, r)
+    }
+
+    /// 復号器を指定して受信語の誤り訂正を行う．
+    #[inline]
+    pub fn ecc_with<D: Decoder>(&self, decoder: &D, r: u32) -> Option<u32> {
+        decoder.ecc( self.to_native(r) ).map(|code| self.from_native(code))
+    }
+
+    /// 受信語の誤り訂正を行い，結果を`sink`に渡す．訂正できない場合はNone．
+    #[inline]
+    pub fn ecc_metered<M: MetricsSink + ?Sized>(&self, r: u32, sink: &mut M) -> Option<u32> {
+        crate::metrics::ecc( self.to_native(r), sink ).map(|code| self.from_native(code))
+    }
+
+    /// 受信語の誤り訂正を行い，訂正結果のデータが`accept`で有効でなければ誤訂正として捨てる．
+    ///
+    /// 判定はこの符号のデータ（`decode`の結果）で行う．訂正できない場合はNone．
+    #[inline]
+    pub fn ecc_restricted<A: Accept + ?Sized>(&self, r: u32, accept: &A) -> Option<u32> {
+        self.ecc(r).filter(|code| accept.accept( self.decode(*code) ))
+    }
+
+    /// 利用者の符号語（受信語）をこのクレートのビット順にする．
+    pub fn to_native(&self, r: u32) -> u32 {
+        let mut out = 0;
+        for (i, p) in self.perm.iter().enumerate() {
+            out |= ((r >> i) & 1) << p;
+        }
+        out
+    }
+
+    /// このクレートの符号語を利用者のビット順にする．
+    pub fn from_native(&self, code: u32) -> u32 {
+        let mut out = 0;
+        for (i, p) in self.perm.iter().enumerate() {
+            out |= ((code >> p) & 1) << i;
+        }
+        out
+    }
+
+    /// 利用者の符号語（受信語）を，配置`L`，ビット順`O`の語にする．
+    #[inline]
+    pub fn to_word<L: Layout, O: BitOrder>(&self, r: u32) -> Word<L, O> {
+        Word::from_raw( L::from_native(self.to_native(r)) )
+    }
+
+    /// 配置`L`，ビット順`O`の語を利用者の符号語（受信語）にする．
+    #[inline]
+    pub fn from_word<L: Layout, O: BitOrder>(&self, word: Word<L, O>) -> u32 {
+        self.from_native( L::to_native(word.raw()) )
+    }
+}
+
+/// データと生成行列の積
+#[inline]
+fn multiply(g: &[u32; 12], data: u16) -> u32 {
+    crate::gf2::vec_mul(data as u32, g)
+}
+
+/// 生成行列を簡約して，各行の先頭の列と，元の行との対応を求める．階数が12でなければNone．
+fn reduce(g: &[u32; 12]) -> Option<([u8; 12], [u16; 12])> {
+    let mut rows = *g;
+    let mut t = [0u16; 12];
+    for (i, t_line) in t.iter_mut().enumerate() {
+        *t_line = 0x800 >> i;
+    }
+    let mut pivots = [0u8; 12];
+    let mut n = 0;
+    for p in 0..24u8 {
+        if n == 12 {
+            break;
+        }
+        if let Some(k) = (n..12).find(|&k| (rows[k] >> p) & 1 == 1) {
+            rows.swap(n, k);
+            t.swap(n, k);
+            for k in 0..12 {
+                if k != n && (rows[k] >> p) & 1 == 1 {
+                    rows[k] ^= rows[n];
+                    t[k] ^= t[n];
+                }
+            }
+            pivots[n] = p;
+            n += 1;
+        }
+    }
+    if n < 12 {
+        return None;
+    }
+    Some((pivots, t))
+}
+
+/// 利用者の符号のbit 0〜n-1 の対応先が，オクタッドの対応と矛盾しないか確かめる．
+fn consistent(octads: &[u32; N_OCTADS], perm: &[u8; 24], n: usize) -> bool {
+    let assigned = (1u32 << n) - 1;
+    for octad in octads.iter() {
+        let s = octad & assigned;
+        if s.count_ones() < 5 {
+            continue;
+        }
+        // 5点を含むオクタッドはただ1つで，5点の語から3bit訂正すれば求まる
+        let mut five = 0;
+        let mut k = 0;
+        for (i, p) in perm.iter().enumerate().take(n) {
+            if k < 5 && (s >> i) & 1 == 1 {
+                five |= 1 << p;
+                k += 1;
+            }
+        }
+        let target = match crate::ecc(five) {
+            Some(target) => target,
+            None => return false,
+        };
+        for (i, p) in perm.iter().enumerate().take(n) {
+            if (octad >> i) & 1 != (target >> p) & 1 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// ビットの対応をバックトラックで探す．
+fn search(octads: &[u32; N_OCTADS], perm: &mut [u8; 24], n: usize, used: u32) -> bool {
+    if n == 24 {
+        return true;
+    }
+    for c in 0..24u8 {
+        if (used >> c) & 1 == 1 {
+            continue;
+        }
+        perm[n] = c;
+        if consistent(octads, perm, n + 1) && search(octads, perm, n + 1, used | (1 << c)) {
+            return true;
+        }
+    }
+    false
+}
+
+#[test]
+fn test_codec() {
+    // ビットの順番を逆にし，行を足し合わせた生成行列
+    let mut g = [0u32; 12];
+    for (i, g_line) in g.iter_mut().enumerate() {
+        let row = if i < 11 { crate::G[i] ^ crate::G[i + 1] } else { crate::G[i] };
+        *g_line = row.reverse_bits() >> 8;
+    }
+    let codec = Codec::new(&g).unwrap();
+
+    for data in [0x000, 0x123, 0xFFF, 0xA5A].iter() {
+        let code = codec.encode(*data);
+        assert_eq!(codec.decode(code), *data);
+        assert_eq!(crate::syndrome(codec.to_native(code)), 0);
+        assert_eq!(codec.ecc(code ^ 0x800201), Some(code));
+        assert_eq!(codec.ecc_with(&crate::decoder::Kasami, code ^ 0x000111), Some(code));
+        assert_eq!(codec.ecc(code ^ 0x00000F), None);
+        assert_eq!(codec.ecc_restricted(code ^ 0x000300, &|d| d == *data), Some(code));
+        assert_eq!(codec.ecc_restricted(code, &|d| d != *data), None);
+
+        let word = codec.to_word::<crate::layout::ParityHigh, crate::layout::LsbFirst>(code ^ 0x000401);
+        assert_eq!(word.ecc().map(|w| codec.from_word(w)), Some(code));
+        assert_eq!(codec.to_word::<crate::layout::DataHigh, crate::layout::MsbFirst>(code).raw(), codec.to_native(code));
+    }
+
+    let mut g_rank = crate::G;
+    g_rank[4] = g_rank[7];
+    assert_eq!(Codec::new(&g_rank), Err(MatrixError::Rank));
+    let mut g_dist = crate::G;
+    g_dist[4] ^= 0x10;
+    assert_eq!(Codec::new(&g_dist), Err(MatrixError::Distance));
+}
@@ -0,0 +1,140 @@
+//! 符号語の型と文字列との変換
+//!
+//! `Codeword`は24bitの符号語（または受信語）を包む型で，このクレートの形式
+//! （データが上位12bit，上位ビットから送る）の`layout::Word`．
+//! 文字列との変換は他の約束事の`Word<L, O>`にも使え，配置`L`の24bitの値をそのまま表す．
+//! ログやATコマンド風のインターフェース，テストの固定値でやり取りできるように，
+//! `core::fmt`を使わずに16進文字列と相互に変換する．
+//!
+//! 16進表記は6桁の大文字（例: `8A3F01`）．読み込みでは`0x`の接頭辞と小文字も受け付ける．
+//! キャラクタLCDなどの診断表示向けに，データと検査ビットを区切った2進表記
+//! （`xxxxxxxxxxxx|xxxxxxxxxxxx`）も書き出せる．
+
+use crate::layout::{BitOrder, DataHigh, Layout, MsbFirst, Word};
+
+/// このクレートの形式の符号語（下位24bit）
+pub type Codeword = Word<DataHigh, MsbFirst>;
+
+/// 16進文字列のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexError {
+    /// 数字が無い
+    Empty,
+    /// 16進数でない文字がある
+    InvalidDigit,
+    /// 24bitに収まらない
+    TooLong,
+}
+
+/// 16進表記の桁数
+pub const HEX_LEN: usize = 6;
+
+/// 区切り付きの2進表記の文字数
+pub const BINARY_LEN: usize = 25;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+impl<L: Layout, O: BitOrder> Word<L, O> {
+    /// 16進文字列から読み込む．
+    ///
+    /// 1〜6桁の16進数（`0x`の接頭辞は省略可）を受け付ける．
+    pub fn from_hex_str(s: &str) -> Result<Self, HexError> {
+        let s = s.as_bytes();
+        let digits = match s {
+            [b'0', b'x', rest @ ..] | [b'0', b'X', rest @ ..] => rest,
+            _ => s,
+        };
+        if digits.is_empty() {
+            return Err(HexError::Empty);
+        }
+        if digits.len() > HEX_LEN {
+            return Err(HexError::TooLong);
+        }
+        let mut r = 0;
+        for c in digits {
+            let d = match c {
+                b'0'..=b'9' => c - b'0',
+                b'a'..=b'f' => c - b'a' + 10,
+                b'A'..=b'F' => c - b'A' + 10,
+                _ => return Err(HexError::InvalidDigit),
+            };
+            r = (r << 4) | d as u32;
+        }
+        Ok( Self::from_raw(r) )
+    }
+
+    /// 6桁の16進文字列を`buf`に書き込む．
+    ///
+    /// * return: 書き込んだ文字列．`buf`が6バイト未満ならNone．
+    pub fn to_hex<'a>(&self, buf: &'a mut [u8]) -> Option<&'a str> {
+        let out = buf.get_mut(..HEX_LEN)?;
+        for (i, o) in out.iter_mut().enumerate() {
+            *o = HEX_DIGITS[((self.raw() >> (20 - 4 * i)) & 0xF) as usize];
+        }
+        core::str::from_utf8(out).ok()
+    }
+
+    /// データと検査ビットを`|`で区切った2進文字列（25文字）を`buf`に書き込む．
+    ///
+    /// * return: 書き込んだ文字列．`buf`が25バイト未満ならNone．
+    #[inline]
+    pub fn to_binary<'a>(&self, buf: &'a mut [u8]) -> Option<&'a str> {
+        self.to_binary_grouped(buf, 12, b'|')
+    }
+
+    /// `group`bitごとに`sep`で区切った2進文字列を上位ビットから`buf`に書き込む．
+    ///
+    /// * `group`: 区切る間隔（0なら区切らない）
+    /// * `sep`: 区切り文字（ASCII）
+    /// * return: 書き込んだ文字列．`buf`が足りないか`sep`がASCIIでなければNone．
+    pub fn to_binary_grouped<'a>(&self, buf: &'a mut [u8], group: usize, sep: u8) -> Option<&'a str> {
+        if !sep.is_ascii() {
+            return None;
+        }
+        let seps = 23usize.checked_div(group).unwrap_or(0);
+        let out = buf.get_mut(..24 + seps)?;
+        let mut n = 0;
+        for i in 0..24usize {
+            if group > 0 && i > 0 && i.is_multiple_of(group) {
+                out[n] = sep;
+                n += 1;
+            }
+            out[n] = if (self.raw() >> (23 - i)) & 1 == 1 { b'1' } else { b'0' };
+            n += 1;
+        }
+        core::str::from_utf8(out).ok()
+    }
+}
+
+#[test]
+fn test_codeword() {
+    let c = Codeword::encode(0x8A3);
+    let mut buf = [0u8; 8];
+    let s = c.to_hex(&mut buf).unwrap();
+    assert_eq!(s.len(), HEX_LEN);
+    assert_eq!(Codeword::from_hex_str(s), Ok(c));
+    assert_eq!(Codeword::from(0x00AB0F).to_hex(&mut buf), Some("00AB0F"));
+    assert_eq!(Codeword::from_hex_str("0xab0f"), Ok(Codeword::from(0xAB0F)));
+    assert_eq!(Codeword::from(0).to_hex(&mut buf[..5]), None);
+
+    assert_eq!(Codeword::from_hex_str(""), Err(HexError::Empty));
+    assert_eq!(Codeword::from_hex_str("0x"), Err(HexError::Empty));
+    assert_eq!(Codeword::from_hex_str("1234567"), Err(HexError::TooLong));
+    assert_eq!(Codeword::from_hex_str("12G4"), Err(HexError::InvalidDigit));
+
+    let mut buf = [0u8; 32];
+    assert_eq!(Codeword::from(0x800001).to_binary(&mut buf), Some("100000000000|000000000001"));
+    assert_eq!(Codeword::from(0xF0F0F0).to_binary_grouped(&mut buf, 4, b' '), Some("1111 0000 1111 0000 1111 0000"));
+    assert_eq!(Codeword::from(5).to_binary_grouped(&mut buf, 0, b' ').map(|s| s.len()), Some(24));
+    assert_eq!(Codeword::from(0).to_binary(&mut buf[..BINARY_LEN - 1]), None);
+
+    let r = Codeword::from(c.raw() ^ 0x101);
+    assert_eq!(r.ecc().map(|c| c.decode()), Some(0x8A3));
+
+    // 他の約束事の語は，その配置の値をそのまま表す
+    let other: Word<crate::layout::ParityHigh, crate::layout::LsbFirst> = c.convert();
+    let mut hex = [0u8; HEX_LEN];
+    let s = other.to_hex(&mut hex).unwrap();
+    assert_eq!(u32::from_str_radix(s, 16), Ok(other.raw()));
+    assert_eq!(Word::from_hex_str(s), Ok(other));
+}
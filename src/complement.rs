@@ -0,0 +1,86 @@
+//! 補元に関するユーティリティ
+//!
+//! 拡張ゴレイ符号は全1の語を符号語に含むので，任意の符号語の補元（全ビット反転）も符号語になる．
+//! 補元の符号語はデータも全ビット反転したものになる．
+//!
+//! 符号語一つだけでは極性を判別できないので，判別には既知の同期語などを使う．
+
+/// 全ビットが1の符号語
+pub const ALL_ONES: u32 = 0xFFFFFF;
+
+/// 受信語の極性
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Polarity {
+    /// そのまま
+    Normal,
+    /// 全ビット反転している
+    Inverted,
+}
+
+/// 符号語の補元を返す．
+#[inline]
+pub fn complement(code: u32) -> u32 {
+    code ^ ALL_ONES
+}
+
+/// 極性を考慮して受信語の誤り訂正を行う．
+///
+/// 反転した受信語は反転を戻してから訂正する．
+/// 全1の語のシンドロームは0なので，訂正する誤りパターンは極性によらず同じになる．
+#[inline]
+pub fn ecc_polarity(r: u32, polarity: Polarity) -> Option<u32> {
+    match polarity {
+        Polarity::Normal   => super::ecc(r),
+        Polarity::Inverted => super::ecc(complement(r)),
+    }
+}
+
+/// 既知のデータ（同期語など）を符号化した受信語から極性を判別する．
+///
+/// * `r`: 受信語（下位24bit）
+/// * `sync`: 送信側で符号化したデータ（下位12bit）
+/// * return: 訂正後のデータが`sync`ならNormal，その反転ならInverted．どちらでもなければNone．
+pub fn resolve_polarity(r: u32, sync: u16) -> Option<Polarity> {
+    let data = super::decode( super::ecc(r)? );
+    let sync = sync & 0xFFF;
+    if data == sync {
+        Some(Polarity::Normal)
+    } else if data == !sync & 0xFFF {
+        Some(Polarity::Inverted)
+    } else {
+        None
+    }
+}
+
+/// 極性が不明な受信語を訂正して，正しいデータと極性を推定する．
+///
+/// 訂正後のデータとその反転の両方を`is_valid`で確かめ，有効なほうを返す．
+/// 両方とも有効な場合は判別できないのでNoneを返す．
+///
+/// * `r`: 受信語（下位24bit）
+/// * `is_valid`: データ（下位12bit）が有効かどうかを判定する関数
+pub fn decode_either<F>(r: u32, is_valid: F) -> Option<(u16, Polarity)>
+where F: Fn(u16) -> bool {
+    let data = super::decode( super::ecc(r)? );
+    let inverted = !data & 0xFFF;
+    match ( is_valid(data), is_valid(inverted) ) {
+        (true, false) => Some( (data, Polarity::Normal) ),
+        (false, true) => Some( (inverted, Polarity::Inverted) ),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_complement() {
+    let data = 0b000111010110;
+    let code = super::encode(data);
+    assert_eq!(super::encode(0xFFF), ALL_ONES);
+    assert_eq!(complement(code), super::encode(!data & 0xFFF));
+
+    let e = 0b0100_0000_0000_0010_0000_0001;
+    let rx = complement(code) ^ e;
+    assert_eq!(resolve_polarity(rx, data), Some(Polarity::Inverted));
+    assert_eq!(resolve_polarity(code ^ e, data), Some(Polarity::Normal));
+    assert_eq!(ecc_polarity(rx, Polarity::Inverted), Some(code));
+    assert_eq!(decode_either(rx, |d| d >> 11 == 0), Some((data, Polarity::Inverted)));
+}
@@ -0,0 +1,159 @@
+//! 復号器の回帰試験用の入力集
+//!
+//! 実装を間違えやすい受信語を集めたもの．どの復号器（`decoder::Decoder`）も全て通るはずなので，
+//! 新しい復号器や他の言語・HDLでの実装の確認に使える．
+//!
+//! * `Extreme`: 全0・全1の符号語と，その近くの受信語
+//! * `SecondSyndrome`: データ部分に2bit以上の誤りがあり，2つめのシンドロームでしか見つからない誤り
+//! * `Boundary`: データと検査ビットの境目（bit 12とbit 11）をまたぐ誤り
+//! * `CosetBoundary`: 重み4の剰余類（6つの誤りパターンが同じシンドロームになる）の全ての代表．検出されるはず．
+//! * `Complement`: 他の項目の受信語を全ビット反転したもの．補元の符号語に訂正されるはず．
+
+use crate::decoder::Decoder;
+
+/// 入力の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// 全0・全1
+    Extreme,
+    /// 2つめのシンドロームの経路
+    SecondSyndrome,
+    /// データと検査ビットの境目
+    Boundary,
+    /// 重み4の剰余類
+    CosetBoundary,
+    /// 補元
+    Complement,
+}
+
+/// 1つの入力
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Case {
+    /// 受信語（24bit）
+    pub received: u32,
+    /// 訂正後の符号語．検出されるべき場合はNone．
+    pub expected: Option<u32>,
+    /// 種類
+    pub kind: Kind,
+}
+
+/// （送ったデータ，誤りパターン，種類）
+static SEEDS: [(u16, u32, Kind); 31] = [
+    (0x000, 0x000000, Kind::Extreme),
+    (0xFFF, 0x000000, Kind::Extreme),
+    (0x000, 0xE00000, Kind::Extreme),
+    (0xFFF, 0x000007, Kind::Extreme),
+    (0x000, 0x00000F, Kind::Extreme),
+    (0xFFF, 0xF00000, Kind::Extreme),
+    (0x123, 0xC00000, Kind::SecondSyndrome),
+    (0x123, 0x700000, Kind::SecondSyndrome),
+    (0x9C3, 0x810001, Kind::SecondSyndrome),
+    (0xA5C, 0x0A0800, Kind::SecondSyndrome),
+    (0x555, 0x005000, Kind::SecondSyndrome),
+    (0x3C5, 0x001800, Kind::Boundary),
+    (0x3C5, 0x003800, Kind::Boundary),
+    (0x3C5, 0x001C00, Kind::Boundary),
+    (0x7FF, 0x001000, Kind::Boundary),
+    (0x800, 0x000800, Kind::Boundary),
+    // 0x00000Fの剰余類
+    (0x0F0, 0x00000F, Kind::CosetBoundary),
+    (0x0F0, 0x441010, Kind::CosetBoundary),
+    (0x0F0, 0x00C420, Kind::CosetBoundary),
+    (0x0F0, 0x9000C0, Kind::CosetBoundary),
+    (0x0F0, 0x090300, Kind::CosetBoundary),
+    (0x0F0, 0x222800, Kind::CosetBoundary),
+    // データ部分だけの0xF00000の剰余類
+    (0xAAA, 0xF00000, Kind::CosetBoundary),
+    (0xAAA, 0x008181, Kind::CosetBoundary),
+    (0xAAA, 0x040806, Kind::CosetBoundary),
+    (0xAAA, 0x000648, Kind::CosetBoundary),
+    (0xAAA, 0x092010, Kind::CosetBoundary),
+    (0xAAA, 0x025020, Kind::CosetBoundary),
+    // 境目をまたぐ0x801801の剰余類（一部）
+    (0x9C3, 0x801801, Kind::CosetBoundary),
+    (0x9C3, 0x300018, Kind::CosetBoundary),
+    (0x9C3, 0x018300, Kind::CosetBoundary),
+];
+
+/// 全ての入力を順に返すイテレータ
+///
+/// 各種類の入力の後に，それを反転した`Complement`の入力が続く．
+#[inline]
+pub fn cases() -> Cases {
+    Cases{ index: 0 }
+}
+
+/// `cases`のイテレータ
+#[derive(Clone, Copy, Debug)]
+pub struct Cases {
+    index: usize,
+}
+
+impl Iterator for Cases {
+    type Item = Case;
+
+    fn next(&mut self) -> Option<Case> {
+        let (data, e, kind) = *SEEDS.get(self.index / 2)?;
+        let code = crate::encode(data);
+        let expected = if e.count_ones() <= 3 { Some(code) } else { None };
+        let case = if self.index.is_multiple_of(2) {
+            Case{ received: code ^ e, expected, kind }
+        } else {
+            let inv = |c: u32| !c & 0xFFFFFF;
+            Case{ received: inv(code ^ e), expected: expected.map(inv), kind: Kind::Complement }
+        };
+        self.index += 1;
+        Some(case)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = 2 * SEEDS.len() - self.index;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for Cases {}
+
+/// 全ての入力を復号器で訂正して確かめる．
+///
+/// * return: 確かめた入力の数．合わない入力があれば最初のもの．
+pub fn check<D: Decoder>(decoder: &D) -> Result<usize, Case> {
+    let mut n = 0;
+    for case in cases() {
+        if decoder.ecc(case.received) != case.expected {
+            return Err(case);
+        }
+        n += 1;
+    }
+    Ok(n)
+}
+
+#[test]
+fn test_corpus() {
+    use crate::decoder::*;
+
+    assert_eq!(cases().len(), 62);
+    for case in cases() {
+        assert_eq!(crate::ecc(case.received), case.expected, "{:?}", case);
+        if case.kind == Kind::CosetBoundary {
+            assert_eq!(crate::coset::candidates(case.received).map(|c| c.len()), Some(6));
+        }
+    }
+    assert_eq!(check(&Arithmetic), Ok(62));
+    assert_eq!(check(&Kasami), Ok(62));
+    assert_eq!(check(&StepByStep), Ok(62));
+    assert_eq!(check(&Permutation), Ok(62));
+    assert_eq!(check(&Majority), Ok(62));
+    assert_eq!(check(&FixedTime), Ok(62));
+    #[cfg(feature = "accel")]
+    assert_eq!(check(&crate::accel::Table), Ok(62));
+
+    // 間違った復号器
+    struct Weak;
+    impl Decoder for Weak {
+        fn error_pattern(&self, r: u32) -> Option<u32> {
+            crate::ecc(r).filter(|c| (c ^ r) & 0xFFF000 == 0).map(|c| c ^ r)
+        }
+    }
+    assert_eq!(check(&Weak).map_err(|c| c.kind), Err(Kind::Extreme));
+}
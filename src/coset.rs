@@ -0,0 +1,83 @@
+//! 重み4の剰余類代表（訂正できずに検出した誤り）の列挙
+//!
+//! シンドロームは4096通りで，そのうち2325通りは重み3以下の誤りパターンに対応して訂正できる．
+//! 残りの1771通りでは，重み4の誤りパターンが6つずつあり，互いに重ならずに24bitを覆う（セクステット）．
+//! 検出したフレームの解析で，実際に起きた誤りの候補を絞るのに使う．
+
+/// 受信語の誤りの候補となる重み4の誤りパターン6つを返す．
+///
+/// 受信語が3bit以下の誤りで訂正できる場合はNone．
+pub fn candidates(r: u32) -> Option<[u32; 6]> {
+    let r = r & 0xFFFFFF;
+    if super::error_pattern(r).is_some() {
+        return None;
+    }
+
+    // 1bit反転すると重み3の剰余類になるので，訂正した3bitと合わせれば重み4のパターンになる
+    let mut out = [0u32; 6];
+    let mut covered = 0u32;
+    for slot in out.iter_mut() {
+        let i = (!covered & 0xFFFFFF).trailing_zeros();
+        let e = super::error_pattern(r ^ (1 << i))? | (1 << i);
+        *slot = e;
+        covered |= e;
+    }
+    Some(out)
+}
+
+/// シンドローム（下位12bit）から重み4の誤りパターンの候補を返す．
+#[inline]
+pub fn syndrome_candidates(s: u16) -> Option<[u32; 6]> {
+    // 検査行列の下半分は単位行列なので，検査ビットにsを置いた語のシンドロームがsになる
+    candidates((s & 0xFFF) as u32)
+}
+
+/// 重み4の剰余類を，シンドロームの小さい順に列挙する．
+///
+/// 要素は（シンドローム，重み4の誤りパターン6つ）．
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Leaders {
+    s: u16,
+}
+
+impl Leaders {
+    /// シンドローム0から列挙を始める．
+    pub fn new() -> Self {
+        Self{ s: 0 }
+    }
+}
+
+impl Iterator for Leaders {
+    type Item = (u16, [u32; 6]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.s < 4096 {
+            let s = self.s;
+            self.s += 1;
+            if let Some(c) = syndrome_candidates(s) {
+                return Some((s, c));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn test_coset() {
+    assert_eq!(Leaders::new().count(), 1771);
+    for (s, sextet) in Leaders::new().step_by(50) {
+        let mut union = 0;
+        for e in sextet.iter() {
+            assert_eq!(e.count_ones(), 4);
+            assert_eq!(super::syndrome(*e), s as u32);
+            assert_eq!(union & e, 0);
+            union |= e;
+        }
+        assert_eq!(union, 0xFFFFFF);
+    }
+
+    let code = super::encode(0x6B2);
+    let e = 0x100C01;
+    assert!(candidates(code ^ e).unwrap().contains(&e));
+    assert_eq!(candidates(code ^ 0x000C01), None);
+}
@@ -0,0 +1,136 @@
+//! 被覆半径と深い穴
+//!
+//! 任意の24bitの語から最も近い符号語までの距離は4以下で，この最大値4が被覆半径．
+//! 距離がちょうど4の語（深い穴）は，シンドロームが重み4の剰余類（1771通り）に入る語で，
+//! 1771 × 4096 = 7254016 個ある．深い穴から距離4の符号語は6つある（`coset`のセクステット）．
+//!
+//! 訂正半径（3）を超えた誤りでの復号器の振る舞いを調べるためのもの．
+
+use crate::coset::{self, Leaders};
+
+/// 深い穴の数
+pub const DEEP_HOLES: usize = 1771 * 4096;
+
+/// 語（下位24bit）から最も近い符号語までの距離（0〜4）
+pub fn distance(r: u32) -> u32 {
+    match crate::error_pattern(r & 0xFFFFFF) {
+        Some(e) => crate::weight(e),
+        None => 4,
+    }
+}
+
+/// 深い穴（符号からの距離が被覆半径に等しい語）か．
+#[inline]
+pub fn is_deep_hole(r: u32) -> bool {
+    distance(r) == 4
+}
+
+/// 最も近い符号語を全て求める．
+///
+/// * return: （符号語，個数）．距離3以下なら1つ，深い穴なら6つ．
+pub fn nearest(r: u32) -> ([u32; 6], usize) {
+    let r = r & 0xFFFFFF;
+    match coset::candidates(r) {
+        Some(sextet) => {
+            let mut out = [0; 6];
+            for (o, e) in out.iter_mut().zip(sextet.iter()) {
+                *o = r ^ e;
+            }
+            (out, 6)
+        },
+        None => {
+            let code = crate::ecc(r).unwrap_or(r);  // candidatesがNoneなら訂正できる
+            ([code, 0, 0, 0, 0, 0], 1)
+        },
+    }
+}
+
+/// 剰余類の最小の重みごとのシンドロームの数（`[w]`が重みwの剰余類の数）
+///
+/// `[1, 24, 276, 2024, 1771]`になる．
+pub fn coset_weight_distribution() -> [u32; 5] {
+    let mut out = [0u32; 5];
+    for s in 0..4096u16 {
+        // 検査ビットにsを置いた語のシンドロームがsになる
+        out[distance(s as u32) as usize] += 1;
+    }
+    out
+}
+
+/// 被覆半径（全ての剰余類の最小の重みの最大値）を求める．4になる．
+pub fn covering_radius() -> u32 {
+    let dist = coset_weight_distribution();
+    dist.iter().rposition(|n| *n > 0).unwrap_or(0) as u32
+}
+
+/// 深い穴を列挙する．
+///
+/// シンドロームの小さい順に，剰余類の代表に全ての符号語を足したものを返す．
+#[derive(Clone, Debug)]
+pub struct DeepHoles {
+    leaders: Leaders,
+    leader: u32,
+    data: u16,
+    remaining: usize,
+}
+
+impl DeepHoles {
+    /// 列挙を始める．
+    pub fn new() -> Self {
+        Self{ leaders: Leaders::new(), leader: 0, data: 4096, remaining: DEEP_HOLES }
+    }
+}
+
+impl Default for DeepHoles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for DeepHoles {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.data == 4096 {
+            let (_, sextet) = self.leaders.next()?;
+            self.leader = sextet[0];
+            self.data = 0;
+        }
+        let hole = self.leader ^ crate::encode(self.data);
+        self.data += 1;
+        self.remaining -= 1;
+        Some(hole)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for DeepHoles {}
+
+#[test]
+fn test_covering() {
+    assert_eq!(coset_weight_distribution(), [1, 24, 276, 2024, 1771]);
+    assert_eq!(covering_radius(), 4);
+
+    let code = crate::encode(0x3D9);
+    assert_eq!(distance(code), 0);
+    assert_eq!(distance(code ^ 0x000100), 1);
+    assert_eq!(distance(code ^ 0x810001), 3);
+    assert_eq!(distance(code ^ 0x0000F0), 4);
+    assert_eq!(nearest(code ^ 0x810001), ([code, 0, 0, 0, 0, 0], 1));
+
+    let hole = code ^ 0x0000F0;
+    let (near, n) = nearest(hole);
+    assert_eq!(n, 6);
+    assert!(near.contains(&code));
+    assert!(near.iter().all(|c| crate::syndrome(*c) == 0 && crate::weight(c ^ hole) == 4));
+
+    let holes = DeepHoles::new();
+    assert_eq!(holes.len(), DEEP_HOLES);
+    assert!(holes.take(5000).all(is_deep_hole));
+    let mut holes = DeepHoles::new().skip(4095);
+    let (a, b) = (holes.next().unwrap(), holes.next().unwrap());
+    assert_ne!(crate::syndrome(a), crate::syndrome(b));
+}
@@ -0,0 +1,66 @@
+//! CRC-4付きペイロード
+//!
+//! 12bitのデータを8bitのペイロードとCRC-4に分け，訂正後にCRCを確かめる．
+//! 5bit以上の誤りで別の符号語に誤訂正された場合も，CRCで大半を検出できる．
+//! 誤訂正が致命的になるコマンド回線向け．
+//!
+//! データの上位8bitがペイロード，下位4bitがCRC．
+//! CRCの生成多項式は x^4 + x + 1（CRC-4-ITU，初期値0）．
+
+/// CRC-4付きペイロードのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Crc4Error {
+    /// 訂正できない誤りを検出した
+    Detected,
+    /// 訂正後のCRCが合わない（誤訂正）
+    Crc,
+}
+
+/// 8bitのペイロードのCRC-4を計算する．
+pub fn crc4(payload: u8) -> u8 {
+    let mut crc: u8 = 0;
+    for i in (0..8).rev() {
+        let bit = (payload >> i) & 1;
+        let msb = (crc >> 3) & 1;
+        crc = (crc << 1) & 0xF;
+        if (bit ^ msb) == 1 {
+            crc ^= 0b0011;
+        }
+    }
+    crc
+}
+
+/// ペイロードにCRCを付けて符号化する．
+#[inline]
+pub fn encode(payload: u8) -> u32 {
+    super::encode( ((payload as u16) << 4) | crc4(payload) as u16 )
+}
+
+/// 受信語を訂正してCRCを確かめ，ペイロードを取り出す．
+pub fn decode(r: u32) -> Result<u8, Crc4Error> {
+    let code = super::ecc(r).ok_or(Crc4Error::Detected)?;
+    let data = super::decode(code);
+    let payload = (data >> 4) as u8;
+    if crc4(payload) as u16 != data & 0xF {
+        return Err(Crc4Error::Crc);
+    }
+    Ok(payload)
+}
+
+#[test]
+fn test_crc4() {
+    // 1bitの誤りは必ず検出できる
+    for payload in 0..=255u8 {
+        for i in 0..8 {
+            assert_ne!(crc4(payload), crc4(payload ^ (1 << i)));
+        }
+    }
+
+    let code = encode(0xA7);
+    assert_eq!(decode(code ^ 0x820001), Ok(0xA7));
+    assert_eq!(decode(code ^ 0xF), Err(Crc4Error::Detected));
+
+    // 別の符号語に誤訂正される誤り（データを1bit変えた符号語に1bit足す）
+    let wrong = super::encode( ((0xA7 << 4) | crc4(0xA7) as u16) ^ 0x100 );
+    assert_eq!(decode(wrong ^ 1), Err(Crc4Error::Crc));
+}
@@ -0,0 +1,99 @@
+//! 巡回符号としての表現
+//!
+//! このクレートの符号語のビット順は巡回符号になっていないが，ビットを並べ替えると
+//! 生成多項式 g(x) = x^11 + x^10 + x^6 + x^5 + x^4 + x^2 + 1 の(23,12)巡回ゴレイ符号に
+//! 全体パリティを付けたものになる．
+//!
+//! 巡回表現では bit 0〜22 が多項式の係数（bit iが x^i），bit 23 が全体パリティ．
+//! シフトレジスタで符号化・復号するハードウェアと突き合わせるときや，
+//! 巡回シフトを使う復号法で使う．
+
+/// 生成多項式 g(x)（bit iが x^i の係数）
+pub const GENERATOR: u32 = 0b1100_0111_0101;
+
+/// `PERM[i]`は符号語のbit iの巡回表現での位置．
+///
+/// 重み8の符号語が作るシュタイナー系 S(5,8,24) の同型写像を探索して求めた．
+/// 全体パリティのbit 0が巡回表現のbit 23に移る．
+const PERM: [u8; 24] = [
+    23, 22, 21, 20, 19, 18, 12,  6,
+     2,  0, 16,  5,  1,  8,  4,  3,
+    14, 15, 13, 11,  7,  9, 10, 17,
+];
+
+/// 符号語（または受信語，誤りパターン）を巡回表現に並べ替える．
+pub const fn to_cyclic(r: u32) -> u32 {
+    let mut c = 0;
+    let mut i = 0;
+    while i < 24 {
+        c |= ((r >> i) & 1) << PERM[i];
+        i += 1;
+    }
+    c
+}
+
+/// 巡回表現を符号語のビット順に戻す．
+pub const fn from_cyclic(c: u32) -> u32 {
+    let mut r = 0;
+    let mut i = 0;
+    while i < 24 {
+        r |= ((c >> PERM[i]) & 1) << i;
+        i += 1;
+    }
+    r
+}
+
+/// m(x)・g(x) に全体パリティを付けた巡回表現の符号語（非組織符号）
+///
+/// * `m`: 11次以下の多項式（下位12bit）
+pub const fn codeword(m: u16) -> u32 {
+    let mut c = 0;
+    let mut i = 0;
+    while i < 12 {
+        if (m >> i) & 1 == 1 {
+            c ^= GENERATOR << i;
+        }
+        i += 1;
+    }
+    c | ((c.count_ones() & 1) << 23)
+}
+
+/// 23bitの多項式を g(x) で割った余り（下位11bit）
+pub const fn remainder(c: u32) -> u32 {
+    let mut c = c & 0x7FFFFF;
+    let mut i = 22;
+    while i >= 11 {
+        if (c >> i) & 1 == 1 {
+            c ^= GENERATOR << (i - 11);
+        }
+        i -= 1;
+    }
+    c
+}
+
+/// 23bitの多項式に x^i を掛ける（x^23 - 1 を法とした巡回シフト）．
+#[inline]
+pub fn rotate(c: u32, i: u32) -> u32 {
+    let c = c & 0x7FFFFF;
+    let i = i % 23;
+    if i == 0 {
+        return c;
+    }
+    ((c << i) | (c >> (23 - i))) & 0x7FFFFF
+}
+
+#[test]
+fn test_cyclic() {
+    for data in 0..4096 {
+        let code = super::encode(data);
+        let c = to_cyclic(code);
+        assert_eq!(from_cyclic(c), code);
+        // g(x)で割り切れ，bit 23が全体パリティ
+        assert_eq!(remainder(c), 0);
+        assert_eq!(c.count_ones() % 2, 0);
+        // 巡回シフトしても符号語
+        let shifted = rotate(c, 5);
+        let p = shifted.count_ones() & 1;
+        assert_eq!(super::ecc_status(from_cyclic(shifted | (p << 23))), super::Decode::Clean(from_cyclic(shifted | (p << 23))));
+    }
+}
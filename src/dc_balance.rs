@@ -0,0 +1,71 @@
+//! DCバランス符号化
+//!
+//! 符号語とその補元のうち，ランニングディスパリティ（送信した1の数 - 0の数）を
+//! 0に近づけるほうを送信する．トランス結合やAC結合の伝送路向け．
+//!
+//! データの最上位ビットを反転フラグとして使うので，ペイロードは11bitになる．
+//! 補元の符号語はデータも全ビット反転したものになるので，
+//! 受信側はフラグが1ならデータを反転して元に戻す．
+
+/// 符号語のディスパリティ（1の数 - 0の数）
+#[inline]
+pub fn disparity(code: u32) -> i32 {
+    2 * (code & 0xFFFFFF).count_ones() as i32 - 24
+}
+
+/// DCバランス符号化器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DcBalancer {
+    rd: i32,  // ランニングディスパリティ
+}
+
+impl DcBalancer {
+    pub fn new() -> Self {
+        Self{ rd: 0 }
+    }
+
+    /// 現在のランニングディスパリティ
+    #[inline]
+    pub fn running_disparity(&self) -> i32 {
+        self.rd
+    }
+
+    /// 11bitのペイロードを符号化する．
+    ///
+    /// ペイロードは下位11bitに入れておく．上位5bitは見ない．
+    pub fn encode(&mut self, payload: u16) -> u32 {
+        let code = super::encode(payload & 0x7FF);
+        let d = disparity(code);
+        // 符号語の重みは偶数なので，補元のディスパリティは符号が逆になるだけ
+        let code = if (self.rd + d).abs() > (self.rd - d).abs() {
+            super::complement::complement(code)
+        } else {
+            code
+        };
+        self.rd += disparity(code);
+        code
+    }
+}
+
+/// 訂正後の符号語から11bitのペイロードを取り出す．
+#[inline]
+pub fn decode(code: u32) -> u16 {
+    let data = super::decode(code);
+    if data & 0x800 != 0 {
+        !data & 0x7FF
+    } else {
+        data
+    }
+}
+
+#[test]
+fn test_dc_balance() {
+    let mut enc = DcBalancer::new();
+    for payload in 0..0x800 {
+        let code = enc.encode(payload);
+        // 符号語の重みは0, 8, 12, 16, 24のどれかなので，ディスパリティの変化は最大24
+        assert!(enc.running_disparity().abs() <= 24);
+        let rx = code ^ 0b1000_0000_0001_0000_0000_0100;
+        assert_eq!(decode(super::ecc(rx).unwrap()), payload);
+    }
+}
@@ -0,0 +1,48 @@
+//! 復号器の切り替え
+//!
+//! `ecc`などのクレート直下の関数はシンドロームの計算による復号（`Arithmetic`）を使う．
+//! 処理速度やコードサイズ，ハードウェアとの突き合わせの都合で別の復号法を使いたい場合は，
+//! `Decoder`を実装した型を選んで使う．
+//!
+//! どの復号器も3bit以下の誤りは訂正し，4bitの誤りは検出する．
+
+pub mod auto;
+pub mod fixed;
+#[cfg(feature = "isd")]
+pub mod isd;
+pub mod kasami;
+pub mod majority;
+pub mod pd_set;
+pub mod restricted;
+pub mod step;
+
+pub use auto::Auto;
+pub use fixed::FixedTime;
+pub use kasami::Kasami;
+pub use majority::Majority;
+pub use pd_set::Permutation;
+pub use restricted::{Codebook, Restricted};
+pub use step::StepByStep;
+
+/// 復号器
+pub trait Decoder {
+    /// 受信語（下位24bit）の誤りパターンを推定する．訂正できない場合はNone．
+    fn error_pattern(&self, r: u32) -> Option<u32>;
+
+    /// 受信語の誤り訂正を行う．訂正できない場合はNone．
+    #[inline]
+    fn ecc(&self, r: u32) -> Option<u32> {
+        self.error_pattern(r).map(|e| (r & 0xFFFFFF) ^ e)
+    }
+}
+
+/// シンドロームの計算による復号（`ecc`と同じ）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Arithmetic;
+
+impl Decoder for Arithmetic {
+    #[inline]
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        super::error_pattern(r & 0xFFFFFF)
+    }
+}
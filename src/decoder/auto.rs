@@ -0,0 +1,176 @@
+//! 実行時の復号器の自動選択
+//!
+//! 速い復号器はCPU（キャッシュの大きさ，分岐予測，乗算器の有無など）によって変わる．
+//! 初期化のときに各復号器で同じ受信語の列を復号する時間を測り，最も速いものを選ぶ．
+//! 1つのバイナリを性質の違う機器に配っても，それぞれで速い復号器が使われる．
+//!
+//! 時間の測り方は呼び出し側が渡す（サイクルカウンタやタイマの値を返す関数）．
+//! `std`では`Auto::new`が`std::time::Instant`で測る．
+
+use super::{Arithmetic, Decoder, FixedTime, Kasami, Majority, Permutation, StepByStep};
+
+/// 選べる復号器
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// `Arithmetic`
+    Arithmetic,
+    /// `Kasami`
+    Kasami,
+    /// `StepByStep`
+    StepByStep,
+    /// `Permutation`
+    Permutation,
+    /// `Majority`
+    Majority,
+    /// `FixedTime`
+    FixedTime,
+    /// `accel::Table`（`accel`フィーチャ）
+    #[cfg(feature = "accel")]
+    Table,
+}
+
+/// 計測する復号器（`Arithmetic`を先に置き，同じ時間ならこれを選ぶ）
+#[cfg(not(feature = "accel"))]
+pub const BACKENDS: [Backend; 6] = [
+    Backend::Arithmetic,
+    Backend::Kasami,
+    Backend::StepByStep,
+    Backend::Permutation,
+    Backend::Majority,
+    Backend::FixedTime,
+];
+
+/// 計測する復号器（`Arithmetic`を先に置き，同じ時間ならこれを選ぶ）
+#[cfg(feature = "accel")]
+pub const BACKENDS: [Backend; 7] = [
+    Backend::Arithmetic,
+    Backend::Kasami,
+    Backend::StepByStep,
+    Backend::Permutation,
+    Backend::Majority,
+    Backend::FixedTime,
+    Backend::Table,
+];
+
+/// 計測に使う受信語の数
+const N_SAMPLES: u32 = 64;
+
+impl Backend {
+    /// この復号器で誤りパターンを推定する．
+    #[inline]
+    pub fn error_pattern(&self, r: u32) -> Option<u32> {
+        match self {
+            Backend::Arithmetic  => Arithmetic.error_pattern(r),
+            Backend::Kasami      => Kasami.error_pattern(r),
+            Backend::StepByStep  => StepByStep.error_pattern(r),
+            Backend::Permutation => Permutation.error_pattern(r),
+            Backend::Majority    => Majority.error_pattern(r),
+            Backend::FixedTime   => FixedTime.error_pattern(r),
+            #[cfg(feature = "accel")]
+            Backend::Table       => crate::accel::Table.error_pattern(r),
+        }
+    }
+}
+
+/// 計測で選んだ復号器
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Auto {
+    backend: Backend,
+}
+
+impl Auto {
+    /// 復号器を指定して作る（計測しない）．
+    #[inline]
+    pub fn with_backend(backend: Backend) -> Self {
+        Self{ backend }
+    }
+
+    /// 各復号器の時間を測り，最も速いものを選ぶ．
+    ///
+    /// * `rounds`: 受信語の列を復号する回数（1以上）．多いほど測定のばらつきが減る．
+    /// * `now`: 単調増加する時刻（単位は問わない）を返す関数
+    pub fn select<T: FnMut() -> u64>(rounds: u32, mut now: T) -> Self {
+        let mut best = (Backend::Arithmetic, u64::MAX);
+        for backend in BACKENDS.iter() {
+            let t = measure(*backend, rounds.max(1), &mut now);
+            if t < best.1 {
+                best = (*backend, t);
+            }
+        }
+        Self{ backend: best.0 }
+    }
+
+    /// `std::time::Instant`で測って選ぶ．
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        let start = std::time::Instant::now();
+        Self::select(16, || start.elapsed().as_nanos() as u64)
+    }
+
+    /// 選んだ復号器
+    #[inline]
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for Auto {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for Auto {
+    #[inline]
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        self.backend.error_pattern(r)
+    }
+}
+
+/// 誤りの重みが0〜4の受信語の列を`rounds`回復号する時間
+fn measure<T: FnMut() -> u64>(backend: Backend, rounds: u32, now: &mut T) -> u64 {
+    let start = now();
+    for _ in 0..rounds {
+        let mut x = 0x2545F491u32;
+        for i in 0..N_SAMPLES {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            let mut e = 0;
+            for _ in 0..(i % 5) {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                e |= 1 << (x % 24);
+            }
+            let r = crate::encode(x as u16 & 0xFFF) ^ e;
+            core::hint::black_box( backend.error_pattern(core::hint::black_box(r)) );
+        }
+    }
+    now().wrapping_sub(start)
+}
+
+#[test]
+fn test_auto() {
+    // 復号器ごとに決まった時間がかかる時計で，選び方を確かめる
+    let mut t = 0u64;
+    let mut calls = 0;
+    let auto = Auto::select(1, || {
+        // 開始と終了で1回ずつ呼ばれる．3番目の復号器（StepByStep）だけ短くする．
+        calls += 1;
+        if calls % 2 == 0 {
+            t += if calls == 6 { 1 } else { 10 };
+        }
+        t
+    });
+    assert_eq!(calls, 2 * BACKENDS.len());
+    assert_eq!(auto.backend(), Backend::StepByStep);
+
+    for backend in BACKENDS.iter() {
+        let d = Auto::with_backend(*backend);
+        let code = crate::encode(0x9C3);
+        assert_eq!(d.ecc(code ^ 0x100082), Some(code));
+        assert_eq!(d.ecc(code ^ 0x00000F), None);
+    }
+}
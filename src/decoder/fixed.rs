@@ -0,0 +1,96 @@
+//! 処理量が一定の復号（WCETの解析向け）
+//!
+//! `Arithmetic`と同じ誤りパターンを求めるが，途中で返らず，受信語によらず全ての候補を調べる．
+//! 候補の選択は分岐ではなくマスクで行うので，ループの回数と演算の数は入力によらない．
+//! 分岐は最後の`Option`を作るところだけ（`error_pattern_masked`ならそれもない）．
+//!
+//! 1語あたりの演算はおよそ次の通り．
+//! * シンドローム（24行）と2つめのシンドローム（12行）: 各行 シフト・AND・乗算・AND・XOR
+//! * 候補26個: 各 XOR・ビット数・比較・マスクでの選択（5演算程度）
+//!
+//! 最悪実行時間の目安（命令数から見積もった値で，実測ではない．キャッシュやフラッシュの待ちは含まない）
+//!
+//! | アーキテクチャ | popcnt | 目安 |
+//! |---|---|---|
+//! | Cortex-M0+（1サイクル乗算器） | なし（ソフトウェアで約12命令） | 約1000サイクル |
+//! | Cortex-M3/M4 | なし（約12命令） | 約700サイクル |
+//! | RISC-V RV32IMC | なし（約12命令） | 約800サイクル |
+//! | RV32 + Zbb | `cpop` | 約400サイクル |
+//! | x86-64（`popcnt`有効） | `popcnt` | 約250サイクル |
+//!
+//! 対象の機器では`profile`フィーチャの`Profiler::ecc_with`で実測して確かめること．
+
+use super::Decoder;
+use crate::H_T;
+
+/// 処理量が一定の復号器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FixedTime;
+
+/// `ok`が1で，まだ候補が決まっていなければ`candidate`を選ぶ．
+#[inline(always)]
+fn select(result: &mut u32, found: &mut u32, ok: u32, candidate: u32) {
+    let take = ok & !*found;
+    *result |= 0u32.wrapping_sub(take) & candidate;
+    *found |= ok;
+}
+
+impl FixedTime {
+    /// 誤りパターンと，訂正できたか（1なら訂正できた）を分岐なしで求める．
+    ///
+    /// 訂正できない場合の誤りパターンは0．
+    #[inline]
+    pub fn error_pattern_masked(&self, r: u32) -> (u32, u32) {
+        let r = r & 0xFFFFFF;
+        let mut s = 0;
+        for (i, h_t_line) in H_T.iter().enumerate() {
+            s ^= (((r >> (23 - i)) & 1) * 0xFFF) & h_t_line;
+        }
+        let mut sh = 0;
+        for (i, h_t_line) in H_T.iter().take(12).enumerate() {
+            sh ^= (((s >> (11 - i)) & 1) * 0xFFF) & h_t_line;
+        }
+
+        // `error_pattern`と同じ順に候補を並べ，最初に条件を満たすものを選ぶ
+        let mut result = 0;
+        let mut found = 0;
+        select(&mut result, &mut found, (s.count_ones() <= 3) as u32, s);
+        for (i, h_t_line) in H_T.iter().take(12).enumerate() {
+            let tmp = s ^ h_t_line;
+            select(&mut result, &mut found, (tmp.count_ones() <= 2) as u32, (0x800000 >> i) | tmp);
+        }
+        select(&mut result, &mut found, (sh.count_ones() <= 3) as u32, sh << 12);
+        for (i, h_t_line) in H_T.iter().take(12).enumerate() {
+            let tmp = sh ^ h_t_line;
+            select(&mut result, &mut found, (tmp.count_ones() <= 2) as u32, (tmp << 12) | (0x800 >> i));
+        }
+        (result, found)
+    }
+}
+
+impl Decoder for FixedTime {
+    #[inline]
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        match self.error_pattern_masked(r) {
+            (e, 1) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_fixed_time() {
+    let code = super::super::encode(0xB62);
+    assert_eq!(FixedTime.ecc(code), Some(code));
+    for i in 0..24 {
+        for j in i..24 {
+            for k in j..24 {
+                let e = (1 << i) | (1 << j) | (1 << k);
+                assert_eq!(FixedTime.ecc(code ^ e), Some(code));
+                assert_eq!(FixedTime.ecc(code ^ e ^ 0x400002), super::Arithmetic.ecc(code ^ e ^ 0x400002));
+                assert_eq!(FixedTime.ecc(code ^ e ^ 0x000001), super::Arithmetic.ecc(code ^ e ^ 0x000001));
+            }
+        }
+    }
+    assert_eq!(FixedTime.error_pattern_masked(code ^ 0xF), (0, 0));
+}
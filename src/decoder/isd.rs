@@ -0,0 +1,90 @@
+//! 情報集合復号（information set decoding）
+//!
+//! ランダムに選んだ情報集合（生成行列の列のうち線形独立な12列）の受信ビットから符号化し直し，
+//! 受信語との距離が最も小さい符号語を探す．
+//! 保証された訂正能力（3bit）を超える誤りに対しても，試行回数を増やせば最尤復号に近づく．
+//!
+//! 研究での比較用の参照実装で，速度は考えていない．乱数は呼び出し側が渡す．
+
+use crate::Correction;
+
+/// 情報集合復号を行う．
+///
+/// * `r`: 受信語（下位24bit）
+/// * `trials`: 試す情報集合の数（1以上）
+/// * `rng`: 32bitの一様乱数を返す関数
+/// * return: 見つかった中で受信語に最も近い符号語
+pub fn isd<R: FnMut() -> u32>(r: u32, trials: usize, rng: &mut R) -> Correction {
+    let r = r & 0xFFFFFF;
+    let mut best = Correction{ code: 0, errors: 25, error_mask: 0 };
+    for _ in 0..trials.max(1) {
+        // 列の順番をランダムに並べ替える
+        let mut order = [0u8; 24];
+        for (i, o) in order.iter_mut().enumerate() {
+            *o = i as u8;
+        }
+        for i in (1..24).rev() {
+            let j = (rng() % (i as u32 + 1)) as usize;
+            order.swap(i, j);
+        }
+
+        let code = reencode(r, &order);
+        let errors = (code ^ r).count_ones() as u8;
+        if errors < best.errors {
+            best = Correction{ code, errors, error_mask: code ^ r };
+        }
+    }
+    best
+}
+
+/// `order`の順に列を見て情報集合を選び，その位置の受信ビットから符号化し直す．
+fn reencode(r: u32, order: &[u8; 24]) -> u32 {
+    let mut rows = crate::G;
+    let mut pivots = [0u8; 12];
+    let mut n = 0;
+    for &p in order.iter() {
+        if n == 12 {
+            break;
+        }
+        let found = (n..12).find(|&k| (rows[k] >> p) & 1 == 1);
+        if let Some(k) = found {
+            rows.swap(n, k);
+            // 他の行からこの列を消す
+            for k in 0..12 {
+                if k != n && (rows[k] >> p) & 1 == 1 {
+                    rows[k] ^= rows[n];
+                }
+            }
+            pivots[n] = p;
+            n += 1;
+        }
+    }
+
+    let mut code = 0;
+    for (row, p) in rows.iter().zip(pivots.iter()) {
+        code ^= ((r >> p) & 1) * row;
+    }
+    code
+}
+
+#[test]
+fn test_isd() {
+    let mut seed: u32 = 0x1234_5678;
+    let mut rng = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed
+    };
+
+    let code = crate::encode(0x3C5);
+    let c = isd(code ^ 0x000421, 100, &mut rng);
+    assert_eq!((c.code, c.errors), (code, 3));
+
+    // 5〜6bitの誤りでも，全ての符号語を調べた最短距離と一致する
+    for t in 0..20u32 {
+        let r = code ^ (0x108421 << (t % 3)) ^ (1 << t);
+        let nearest = (0..4096).map(|d| (crate::encode(d) ^ r).count_ones()).min().unwrap();
+        assert_eq!(isd(r, 2000, &mut rng).errors as u32, nearest);
+    }
+}
@@ -0,0 +1,79 @@
+//! 誤り捕捉復号（Kasami法）
+//!
+//! 巡回表現（`cyclic`）の23bit部分を1bitずつ巡回シフトしながら，シンドロームの重みを見る．
+//! 誤りが全て検査ビット（x^0〜x^10）に入ったシフトではシンドロームがそのまま誤りパターンになる．
+//! 3bitの誤りが検査ビットに収まらない場合は，情報ビットの x^16 か x^17 に誤りが1つあると仮定し，
+//! その分のシンドロームを足してから重みを見る（被覆多項式）．
+//!
+//! シンドロームの更新は s ← x・s mod g(x) だけなので，シフトレジスタで作る回路と1ステップずつ突き合わせられる．
+//! 最後に全体パリティを見て4bitの誤りを検出する．
+
+use super::Decoder;
+use crate::cyclic;
+
+/// 被覆多項式の次数
+const COVERS: [u32; 2] = [16, 17];
+
+/// 被覆多項式のシンドローム
+const COVER_SYNDROMES: [u32; 2] = [
+    cyclic::remainder(1 << COVERS[0]),
+    cyclic::remainder(1 << COVERS[1]),
+];
+
+/// 誤り捕捉復号器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Kasami;
+
+impl Kasami {
+    /// 23bitの巡回符号の誤りパターンを捕捉する．
+    fn trap(r23: u32) -> Option<u32> {
+        let mut s = cyclic::remainder(r23);
+        for i in 0..23 {
+            // sは受信語を i bit巡回シフトしたもののシンドローム
+            if s.count_ones() <= 3 {
+                return Some( cyclic::rotate(s, 23 - i) );
+            }
+            for (j, cover) in COVERS.iter().zip(COVER_SYNDROMES.iter()) {
+                let t = s ^ cover;
+                if t.count_ones() <= 2 {
+                    return Some( cyclic::rotate(t | (1 << j), 23 - i) );
+                }
+            }
+            s <<= 1;
+            if s & 0x800 != 0 {
+                s ^= cyclic::GENERATOR;
+            }
+        }
+        None
+    }
+}
+
+impl Decoder for Kasami {
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        let c = cyclic::to_cyclic(r & 0xFFFFFF);
+        let e23 = Self::trap(c & 0x7FFFFF)?;
+
+        // 訂正後の全体パリティが合わなければ，パリティビットにも誤りがある
+        let parity = ((c & 0x7FFFFF) ^ e23).count_ones() & 1;
+        let e = e23 | ((parity ^ (c >> 23)) << 23);
+        if e.count_ones() > 3 {
+            return None;  // 4bitエラー
+        }
+        Some( cyclic::from_cyclic(e) )
+    }
+}
+
+#[test]
+fn test_kasami() {
+    let code = super::super::encode(0x9C3);
+    assert_eq!(Kasami.ecc(code), Some(code));
+    for i in 0..24 {
+        for j in i..24 {
+            for k in j..24 {
+                let e = (1 << i) | (1 << j) | (1 << k);
+                assert_eq!(Kasami.ecc(code ^ e), Some(code));
+                assert_eq!(Kasami.ecc(code ^ e ^ (1 << 23) ^ 1), super::Arithmetic.ecc(code ^ e ^ (1 << 23) ^ 1));
+            }
+        }
+    }
+}
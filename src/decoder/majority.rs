@@ -0,0 +1,79 @@
+//! 多数決論理復号（majority-logic decoding）
+//!
+//! 符号は自己双対なので，重み8の符号語（オクタッド，759個）はそのまま検査式になる．
+//! 各ビットについて，そのビットを含む253個のオクタッドのうち検査式が成り立たないものを数え，
+//! 過半数（127個以上）なら誤りとする．
+//!
+//! 3bit以下の誤りでは，誤ったビットは141個以上，正しいビットは125個以下の検査式が成り立たなくなるので，
+//! 1段の多数決で全ての誤りが決まる．
+//! 受信語によらず同じ計算をするので，分岐が少なく処理時間が一定になる．
+//! その分遅いので，主に他の復号器との突き合わせに使う．
+
+use super::Decoder;
+use crate::cyclic;
+
+/// オクタッドの数
+const N_OCTADS: usize = 759;
+
+/// 全てのオクタッドを列挙する．
+const fn octads() -> [u32; N_OCTADS] {
+    let mut out = [0; N_OCTADS];
+    let mut n = 0;
+    let mut m = 0;
+    while m < 4096 {
+        let c = cyclic::codeword(m);
+        if c.count_ones() == 8 {
+            out[n] = cyclic::from_cyclic(c);
+            n += 1;
+        }
+        m += 1;
+    }
+    out
+}
+
+/// オクタッド（符号語のビット順）
+static OCTADS: [u32; N_OCTADS] = octads();
+
+/// 多数決論理復号器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Majority;
+
+impl Decoder for Majority {
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        let r = r & 0xFFFFFF;
+        let mut votes = [0u16; 24];
+        for octad in OCTADS.iter() {
+            let fail = ((octad & r).count_ones() & 1) as u16;
+            for (i, v) in votes.iter_mut().enumerate() {
+                *v += ((octad >> i) & 1) as u16 * fail;
+            }
+        }
+        let mut e = 0;
+        for (i, v) in votes.iter().enumerate() {
+            e |= ((*v >= 127) as u32) << i;
+        }
+
+        // 4bitの誤りでは訂正結果が符号語にならないか，3bitより多く反転している
+        if e.count_ones() <= 3 && crate::syndrome(r ^ e) == 0 {
+            Some(e)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_majority() {
+    assert!(OCTADS.iter().all(|o| o.count_ones() == 8 && crate::syndrome(*o) == 0));
+
+    let code = super::super::encode(0xE01);
+    for i in 0..24 {
+        for j in i..24 {
+            for k in j..24 {
+                let e = (1 << i) | (1 << j) | (1 << k);
+                assert_eq!(Majority.ecc(code ^ e), Some(code));
+            }
+        }
+        assert_eq!(Majority.ecc(code ^ (0xF << (i % 21))), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! PD集合による置換復号（permutation decoding）
+//!
+//! 符号の自己同型（ビットの並べ替えで符号語を符号語に移すもの）を順に受信語にかけ，
+//! 誤りが全て検査ビット（bit 11〜0）に移ったものを探す．
+//! そのときはデータ部分から符号化し直した符号語との差が3bit以下になるので，それを誤りとして元に戻す．
+//!
+//! `PD_SET`は3bit以下のどの誤りパターンも，どれか1つの置換で検査ビットに移るように選んだ自己同型の集合．
+//! ランダムに作った自己同型から貪欲法で選んだ．
+
+use super::Decoder;
+
+/// PD集合の大きさ
+pub const PD_SET_LEN: usize = 23;
+
+/// PD集合．`PD_SET[k][i]`は置換kでbit iが移る位置．先頭は恒等置換．
+pub const PD_SET: [[u8; 24]; PD_SET_LEN] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23],
+    [22, 12, 14, 23, 18, 17, 16, 19, 13, 4, 20, 10, 3, 1, 5, 21, 0, 6, 9, 8, 7, 2, 11, 15],
+    [23, 12, 16, 8, 1, 3, 5, 14, 11, 15, 22, 21, 10, 4, 20, 2, 0, 17, 13, 9, 18, 7, 19, 6],
+    [11, 2, 6, 22, 13, 20, 19, 1, 12, 18, 10, 21, 23, 17, 8, 7, 15, 14, 4, 16, 0, 9, 3, 5],
+    [8, 0, 2, 3, 17, 5, 21, 10, 19, 16, 7, 12, 4, 6, 13, 22, 9, 11, 15, 1, 20, 14, 23, 18],
+    [19, 21, 7, 15, 4, 8, 0, 18, 5, 16, 12, 2, 23, 22, 9, 1, 20, 11, 3, 17, 10, 13, 6, 14],
+    [1, 0, 12, 10, 9, 16, 22, 13, 19, 3, 20, 7, 18, 23, 11, 2, 21, 4, 14, 6, 17, 15, 8, 5],
+    [3, 6, 16, 17, 15, 18, 1, 21, 0, 2, 11, 23, 12, 4, 14, 13, 10, 5, 8, 22, 7, 20, 19, 9],
+    [15, 17, 13, 8, 6, 2, 12, 4, 20, 23, 5, 3, 0, 18, 1, 19, 16, 21, 11, 14, 9, 7, 22, 10],
+    [2, 12, 18, 17, 15, 14, 8, 0, 9, 10, 5, 21, 4, 3, 1, 11, 20, 23, 19, 22, 16, 6, 7, 13],
+    [17, 12, 7, 21, 11, 10, 16, 4, 13, 2, 15, 0, 14, 1, 22, 6, 5, 20, 3, 9, 8, 18, 19, 23],
+    [16, 9, 2, 21, 10, 3, 23, 20, 19, 4, 22, 15, 8, 11, 18, 13, 7, 5, 12, 14, 17, 1, 6, 0],
+    [20, 10, 18, 9, 12, 21, 8, 6, 7, 23, 16, 3, 22, 15, 1, 19, 11, 14, 17, 0, 4, 13, 2, 5],
+    [7, 6, 10, 5, 18, 14, 8, 16, 1, 3, 23, 19, 0, 21, 20, 15, 22, 9, 2, 4, 13, 11, 12, 17],
+    [14, 22, 23, 18, 10, 12, 13, 6, 16, 17, 3, 0, 19, 5, 9, 11, 2, 1, 20, 4, 21, 7, 8, 15],
+    [7, 10, 8, 22, 9, 14, 0, 17, 3, 19, 15, 2, 5, 6, 13, 1, 21, 20, 12, 16, 4, 11, 23, 18],
+    [15, 16, 4, 7, 19, 6, 23, 20, 17, 3, 9, 22, 13, 1, 5, 12, 14, 18, 0, 10, 11, 21, 8, 2],
+    [3, 2, 12, 1, 13, 8, 15, 19, 22, 0, 17, 9, 16, 14, 10, 7, 4, 21, 6, 18, 11, 20, 5, 23],
+    [18, 23, 7, 20, 14, 15, 11, 9, 6, 22, 19, 5, 16, 2, 1, 13, 4, 8, 3, 12, 10, 17, 21, 0],
+    [1, 19, 20, 2, 13, 9, 18, 8, 15, 0, 7, 16, 3, 23, 17, 11, 4, 14, 22, 21, 12, 5, 10, 6],
+    [13, 2, 11, 7, 6, 23, 21, 22, 18, 20, 5, 14, 9, 8, 1, 10, 3, 4, 0, 12, 19, 17, 16, 15],
+    [9, 0, 22, 11, 3, 14, 23, 13, 4, 12, 7, 17, 20, 16, 8, 19, 6, 1, 2, 10, 5, 18, 15, 21],
+    [22, 14, 12, 7, 16, 2, 10, 0, 17, 4, 8, 6, 15, 11, 21, 18, 9, 5, 19, 1, 20, 13, 23, 3],
+];
+
+/// bit iを`perm[i]`に移す．
+#[inline]
+pub fn permute(r: u32, perm: &[u8; 24]) -> u32 {
+    let mut out = 0;
+    for (i, p) in perm.iter().enumerate() {
+        out |= ((r >> i) & 1) << p;
+    }
+    out
+}
+
+/// `permute`の逆
+#[inline]
+pub fn unpermute(r: u32, perm: &[u8; 24]) -> u32 {
+    let mut out = 0;
+    for (i, p) in perm.iter().enumerate() {
+        out |= ((r >> p) & 1) << i;
+    }
+    out
+}
+
+/// 置換が符号の自己同型になっているかを確かめる（生成行列の各行が符号語に移ればよい）．
+pub fn is_automorphism(perm: &[u8; 24]) -> bool {
+    crate::G.iter().all(|g_line| crate::syndrome( permute(*g_line, perm) ) == 0)
+}
+
+/// PD集合による置換復号器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Permutation;
+
+impl Decoder for Permutation {
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        let r = r & 0xFFFFFF;
+        for perm in PD_SET.iter() {
+            let rp = permute(r, perm);
+            let e = rp ^ crate::encode( crate::decode(rp) );
+            if e.count_ones() <= 3 {
+                return Some( unpermute(e, perm) );
+            }
+        }
+        None  // 4bitエラー
+    }
+}
+
+#[test]
+fn test_pd_set() {
+    for perm in PD_SET.iter() {
+        assert!(is_automorphism(perm));
+    }
+    assert!(!is_automorphism(&[1, 0, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23]));
+
+    let code = super::super::encode(0x5A5);
+    for i in 0..24 {
+        for j in i..24 {
+            for k in j..24 {
+                let e = (1 << i) | (1 << j) | (1 << k);
+                assert_eq!(Permutation.ecc(code ^ e), Some(code));
+                assert_eq!(Permutation.ecc(code ^ e ^ 0x800100), super::Arithmetic.ecc(code ^ e ^ 0x800100));
+            }
+        }
+    }
+}
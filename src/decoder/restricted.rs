@@ -0,0 +1,185 @@
+//! 使うメッセージを限った復号
+//!
+//! 定義済みのコマンドコードが200個だけ，のように有効な12bitのメッセージが限られている場合，
+//! 訂正した結果が有効なメッセージでなければ誤訂正とみなして捨てる．
+//!
+//! 4bit以上の誤りで誤訂正が起きたとき，行き先の符号語は有効なメッセージとはほぼ無関係なので，
+//! 有効なメッセージが`k`個なら見逃す誤訂正はおよそ`k / 4096`倍に減る（200個なら約1/20）．
+//! 3bit以下の誤りの訂正には影響しない．
+
+use super::{Arithmetic, Decoder};
+
+/// 有効なメッセージの判定
+pub trait Accept {
+    /// 12bitのデータが有効なメッセージか．
+    fn accept(&self, data: u16) -> bool;
+}
+
+impl<F: Fn(u16) -> bool> Accept for F {
+    #[inline]
+    fn accept(&self, data: u16) -> bool {
+        self(data)
+    }
+}
+
+/// 有効なメッセージの集合（4096bitのビットマップ，512バイト）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Codebook {
+    bits: [u64; 64],
+}
+
+impl Codebook {
+    /// 空の集合
+    pub const fn new() -> Self {
+        Self{ bits: [0; 64] }
+    }
+
+    /// メッセージの一覧から作る．`static`の初期化にも使える．
+    pub const fn from_slice(messages: &[u16]) -> Self {
+        let mut bits = [0u64; 64];
+        let mut i = 0;
+        while i < messages.len() {
+            let m = messages[i] & 0xFFF;
+            bits[(m >> 6) as usize] |= 1 << (m & 0x3F);
+            i += 1;
+        }
+        Self{ bits }
+    }
+
+    /// メッセージ（下位12bit）を加える．
+    #[inline]
+    pub fn insert(&mut self, data: u16) {
+        let m = data & 0xFFF;
+        self.bits[(m >> 6) as usize] |= 1 << (m & 0x3F);
+    }
+
+    /// メッセージ（下位12bit）を除く．
+    #[inline]
+    pub fn remove(&mut self, data: u16) {
+        let m = data & 0xFFF;
+        self.bits[(m >> 6) as usize] &= !(1 << (m & 0x3F));
+    }
+
+    /// メッセージ（下位12bit）を含むか．
+    #[inline]
+    pub fn contains(&self, data: u16) -> bool {
+        let m = data & 0xFFF;
+        (self.bits[(m >> 6) as usize] >> (m & 0x3F)) & 1 == 1
+    }
+
+    /// メッセージの数
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    /// 空か．
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|b| *b == 0)
+    }
+}
+
+impl Default for Codebook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Accept for Codebook {
+    #[inline]
+    fn accept(&self, data: u16) -> bool {
+        self.contains(data)
+    }
+}
+
+/// 訂正結果が有効なメッセージでなければ訂正できないとする復号器
+///
+/// `D`で訂正してから`A`で判定する．
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Restricted<A, D = Arithmetic> {
+    accept: A,
+    decoder: D,
+}
+
+impl<A: Accept> Restricted<A> {
+    /// `Arithmetic`で訂正する．
+    pub fn new(accept: A) -> Self {
+        Self{ accept, decoder: Arithmetic }
+    }
+}
+
+impl<A: Accept, D: Decoder> Restricted<A, D> {
+    /// 復号器を指定する．
+    pub fn with_decoder(accept: A, decoder: D) -> Self {
+        Self{ accept, decoder }
+    }
+
+    /// 有効なメッセージの判定
+    #[inline]
+    pub fn accepted(&self) -> &A {
+        &self.accept
+    }
+}
+
+impl<A: Accept, D: Decoder> Decoder for Restricted<A, D> {
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        let e = self.decoder.error_pattern(r)?;
+        if self.accept.accept( crate::decode((r & 0xFFFFFF) ^ e) ) {
+            Some(e)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_restricted() {
+    static COMMANDS: Codebook = {
+        let mut list = [0u16; 200];
+        let mut i = 0;
+        while i < 200 {
+            list[i] = (i as u16) * 20 + 7;
+            i += 1;
+        }
+        Codebook::from_slice(&list)
+    };
+    assert_eq!(COMMANDS.len(), 200);
+    assert!(COMMANDS.contains(27) && !COMMANDS.contains(28));
+
+    let decoder = Restricted::new(COMMANDS);
+    let code = crate::encode(7);
+    assert_eq!(decoder.ecc(code ^ 0x100402), Some(code));
+    assert_eq!(decoder.ecc(crate::encode(8)), None);
+
+    // 5bitの誤りによる誤訂正の多くを捨てられる
+    let mut plain = 0;
+    let mut restricted = 0;
+    for i in 0..24 {
+        for j in i + 1..24 {
+            let e: u32 = 0x1F ^ (1 << i) ^ (1 << j);
+            if e.count_ones() != 5 {
+                continue;
+            }
+            if Arithmetic.ecc(code ^ e).is_some() {
+                plain += 1;
+            }
+            if let Some(c) = decoder.ecc(code ^ e) {
+                assert!(COMMANDS.contains(crate::decode(c)));
+                restricted += 1;
+            }
+        }
+    }
+    assert!(plain > 0);
+    assert!(restricted * 4 < plain);
+
+    // 関数でも判定できる
+    let even = Restricted::with_decoder(|d: u16| d.is_multiple_of(2), super::Kasami);
+    assert_eq!(even.ecc(crate::encode(0x10) ^ 1), Some(crate::encode(0x10)));
+    assert_eq!(even.ecc(crate::encode(0x11)), None);
+
+    let mut book = Codebook::default();
+    assert!(book.is_empty());
+    book.insert(0x1234);
+    assert!(book.contains(0x234));
+    book.remove(0x234);
+    assert!(book.is_empty());
+}
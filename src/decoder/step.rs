@@ -0,0 +1,83 @@
+//! 逐次復号（step-by-step decoding）
+//!
+//! 受信語のビットを1つずつ反転してみて，剰余類の重み（その剰余類で最も軽い誤りパターンの重み）が
+//! 減るなら反転を確定する．誤りのあるビットを反転すると重みは1減り，それ以外では1増えるので，
+//! 24bitを1回ずつ見れば訂正が終わる．
+//!
+//! 作業領域はシンドロームと重みだけなので，RAMの少ないターゲット向け．
+//! 24回の重みの判定が必要なので，`Arithmetic`より遅い．
+
+use super::Decoder;
+
+/// 逐次復号器
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepByStep;
+
+/// シンドロームsの剰余類の重み（4以上は4）
+fn coset_weight(s: u32) -> u32 {
+    let w = s.count_ones();
+    if w <= 3 {
+        return w;
+    }
+    for h_t_line in crate::H_T.iter().take(12) {
+        if (s ^ h_t_line).count_ones() <= 2 {
+            return (s ^ h_t_line).count_ones() + 1;
+        }
+    }
+
+    // データ部分だけに誤りがあるとみたときの誤りパターン
+    let mut sh = 0;
+    for (i, h_t_line) in crate::H_T.iter().take(12).enumerate() {
+        sh ^= ((s >> (11 - i)) & 1) * h_t_line;
+    }
+    let w = sh.count_ones();
+    if w <= 3 {
+        return w;
+    }
+    for h_t_line in crate::H_T.iter().take(12) {
+        if (sh ^ h_t_line).count_ones() <= 2 {
+            return (sh ^ h_t_line).count_ones() + 1;
+        }
+    }
+    4
+}
+
+impl Decoder for StepByStep {
+    fn error_pattern(&self, r: u32) -> Option<u32> {
+        let mut s = crate::syndrome(r & 0xFFFFFF);
+        let mut w = coset_weight(s);
+        if w > 3 {
+            return None;  // 4bitエラー
+        }
+
+        let mut e = 0;
+        for (i, h_t_line) in crate::H_T.iter().enumerate() {
+            if w == 0 {
+                break;
+            }
+            let t = s ^ h_t_line;
+            let w_t = coset_weight(t);
+            if w_t < w {
+                e |= 0x800000 >> i;
+                s = t;
+                w = w_t;
+            }
+        }
+        Some(e)
+    }
+}
+
+#[test]
+fn test_step_by_step() {
+    let code = super::super::encode(0x123);
+    assert_eq!(StepByStep.ecc(code), Some(code));
+    for i in 0..24 {
+        for j in i..24 {
+            for k in j..24 {
+                let e = (1 << i) | (1 << j) | (1 << k);
+                assert_eq!(StepByStep.ecc(code ^ e), Some(code));
+                assert_eq!(StepByStep.ecc(code ^ e ^ 0x400002), super::Arithmetic.ecc(code ^ e ^ 0x400002));
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! 訂正と検出の記録
+//!
+//! 最近の訂正・検出の出来事を固定長のリングに残す（古いものから上書きする）．
+//! ヒープを使わないので，ロガーの無い現場の機器でも後から故障を調べられる．
+//! 時刻は利用者が渡す時計（ティックカウンタなど）から読む．
+//!
+//! `dump`は1件を`RECORD_LEN`バイトにして，古い順にデバッグ用の回線へ書き出す．
+//! * 時刻（u32，リトルエンディアン）
+//! * 種類（1: 訂正，2: 検出）
+//! * 誤りの位置（24bit，リトルエンディアン．検出では0）
+//! * 訂正したビット数
+
+/// 出来事の種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// 訂正した
+    Corrected,
+    /// 訂正できない誤りを検出した
+    Detected,
+}
+
+/// 1件の記録
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// 時刻
+    pub time: u32,
+    /// 種類
+    pub kind: EventKind,
+    /// 訂正したビット位置（検出では0）
+    pub mask: u32,
+    /// 訂正したビット数（検出では0）
+    pub count: u8,
+}
+
+/// `dump`での1件のバイト数
+pub const RECORD_LEN: usize = 9;
+
+impl Event {
+    /// `RECORD_LEN`バイトにする．
+    pub fn to_bytes(&self) -> [u8; RECORD_LEN] {
+        let t = self.time.to_le_bytes();
+        let m = self.mask.to_le_bytes();
+        let kind = match self.kind {
+            EventKind::Corrected => 1,
+            EventKind::Detected => 2,
+        };
+        [t[0], t[1], t[2], t[3], kind, m[0], m[1], m[2], self.count]
+    }
+}
+
+/// 訂正と検出の記録
+pub struct EventLog<C: FnMut() -> u32, const N: usize> {
+    clock: C,
+    events: [Option<Event>; N],
+    next: usize,    // 次に書く位置
+    total: u32,     // 記録した件数（上書きしたものを含む）
+}
+
+impl<C: FnMut() -> u32, const N: usize> EventLog<C, N> {
+    /// * `clock`: 時刻を返す関数
+    pub fn new(clock: C) -> Self {
+        Self{ clock, events: [None; N], next: 0, total: 0 }
+    }
+
+    fn push(&mut self, kind: EventKind, mask: u32, count: u8) {
+        if N == 0 {
+            return;
+        }
+        let time = (self.clock)();
+        self.events[self.next] = Some( Event{ time, kind, mask, count } );
+        self.next = (self.next + 1) % N;
+        self.total = self.total.wrapping_add(1);
+    }
+
+    /// 受信語を訂正し，誤りがあれば記録する．訂正できない場合はNone．
+    pub fn ecc(&mut self, r: u32) -> Option<u32> {
+        match crate::ecc_verbose(r) {
+            Some(c) => {
+                if c.errors > 0 {
+                    self.push(EventKind::Corrected, c.error_mask, c.errors);
+                }
+                Some(c.code)
+            },
+            None => {
+                self.push(EventKind::Detected, 0, 0);
+                None
+            },
+        }
+    }
+
+    /// 他で訂正した結果を記録する．誤りなしなら記録しない．
+    pub fn record(&mut self, result: Option<crate::Correction>) {
+        match result {
+            Some(c) if c.errors == 0 => {},
+            Some(c) => self.push(EventKind::Corrected, c.error_mask, c.errors),
+            None => self.push(EventKind::Detected, 0, 0),
+        }
+    }
+
+    /// 残っている記録を古い順に返す．
+    pub fn iter(&self) -> impl Iterator<Item = &Event> {
+        let (new, old) = self.events.split_at(self.next);
+        old.iter().chain(new.iter()).filter_map(|e| e.as_ref())
+    }
+
+    /// 残っている記録の件数
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// 記録が無ければtrue．
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.total == 0 || N == 0
+    }
+
+    /// これまでに記録した件数（上書きして消えたものを含む）
+    #[inline]
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// 残っている記録を古い順に`out`に書き出す．
+    ///
+    /// * return: 書き出したバイト数．`out`に入りきらない分は書かない．
+    pub fn dump(&self, out: &mut [u8]) -> usize {
+        let mut n = 0;
+        for (e, dst) in self.iter().zip(out.chunks_exact_mut(RECORD_LEN)) {
+            dst.copy_from_slice(&e.to_bytes());
+            n += RECORD_LEN;
+        }
+        n
+    }
+
+    /// 記録を全て消す．
+    pub fn clear(&mut self) {
+        self.events = [None; N];
+        self.next = 0;
+        self.total = 0;
+    }
+}
+
+#[test]
+fn test_events() {
+    let mut t = 100;
+    let mut log: EventLog<_, 3> = EventLog::new(|| {
+        t += 10;
+        t
+    });
+    assert!(log.is_empty());
+    let code = crate::encode(0x2B4);
+    assert_eq!(log.ecc(code), Some(code));
+    assert_eq!(log.len(), 0);
+    assert_eq!(log.ecc(code ^ 0x800001), Some(code));
+    assert_eq!(log.ecc(code ^ 0x00000F), None);
+    log.record(crate::ecc_verbose(code ^ 0x000100));
+    log.record(crate::ecc_verbose(code ^ 0x070000));
+    assert_eq!(log.len(), 3);
+    assert_eq!(log.total(), 4);
+
+    let events: [Event; 3] = {
+        let mut it = log.iter().copied();
+        core::array::from_fn(|_| it.next().unwrap())
+    };
+    assert_eq!(events[0], Event{ time: 120, kind: EventKind::Detected, mask: 0, count: 0 });
+    assert_eq!(events[1].mask, 0x000100);
+    assert_eq!(events[2], Event{ time: 140, kind: EventKind::Corrected, mask: 0x070000, count: 3 });
+
+    let mut buf = [0u8; 32];
+    assert_eq!(log.dump(&mut buf), 27);
+    assert_eq!(buf[..RECORD_LEN], [120, 0, 0, 0, 2, 0, 0, 0, 0]);
+    assert_eq!(buf[18..27], [140, 0, 0, 0, 1, 0, 0, 7, 3]);
+    assert_eq!(log.dump(&mut buf[..20]), 18);
+
+    log.clear();
+    assert!(log.is_empty());
+}
@@ -0,0 +1,340 @@
+//! ファイルの保護と修復（`std`フィーチャ）
+//!
+//! ファイルを`CHUNK_LEN`バイトずつのチャンクに分けて符号化し，チャンクごとのCRC-32を索引として付ける．
+//! 後で保護したファイルを調べて，訂正できた誤りをその場で書き戻す．
+//!
+//! 保護したファイルの構成
+//! * チャンク: `protect`と同じく12bitずつ符号化したもの．最後のチャンクは0で埋めて同じ大きさにする．
+//! * 索引: 各チャンクの元のデータ（埋めた0を含む）のCRC-32を符号化したもの
+//! * フッタ: マジック`GOLY`，元のファイルの長さ（u64），チャンクの長さ（u32）を符号化したもの
+//!
+//! フッタは固定長でファイルの最後にあるので，末尾から読めば全体の構成がわかる．
+//! 修復の結果は元のファイルでのバイト範囲で返す．
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::vec::Vec;
+
+use super::boot::crc32;
+use super::protect::{protected_len, RawReader, RawWriter};
+
+/// 1チャンクのデータのバイト数
+pub const CHUNK_LEN: usize = 3072;
+
+/// フッタのマジック
+const MAGIC: [u8; 4] = *b"GOLY";
+
+/// フッタのバイト数（マジック4，長さ8，チャンク長4を符号化したもの）
+const FOOTER_LEN: usize = protected_len(16);
+
+/// 索引の1要素のバイト数
+const INDEX_ENTRY_LEN: usize = protected_len(4);
+
+/// 調べた結果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileReport {
+    /// 元のファイルの長さ
+    pub len: u64,
+    /// チャンクの数
+    pub chunks: u64,
+    /// 訂正したビット数
+    pub corrected_bits: u64,
+    /// 誤りを訂正した範囲（元のファイルでのバイト範囲）
+    pub recovered: Vec<Range<u64>>,
+    /// チャンクが訂正できないかCRCが合わなかった範囲（チャンク単位）
+    pub lost: Vec<Range<u64>>,
+}
+
+impl FileReport {
+    /// 全て正しく読めたか
+    #[inline]
+    pub fn is_ok(&self) -> bool {
+        self.lost.is_empty()
+    }
+}
+
+/// ファイルを保護して書き出す．
+///
+/// * return: 元のファイルの長さ
+pub fn protect<R: Read, W: Write>(mut input: R, mut output: W) -> io::Result<u64> {
+    let mut chunk = [0u8; CHUNK_LEN];
+    let mut coded = [0u8; protected_len(CHUNK_LEN)];
+    let mut crcs = Vec::new();
+    let mut len = 0u64;
+    loop {
+        let n = read_full(&mut input, &mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        for b in chunk[n..].iter_mut() {
+            *b = 0;
+        }
+        encode_into(&chunk, &mut coded);
+        output.write_all(&coded)?;
+        crcs.push(crc32(&chunk));
+        len += n as u64;
+        if n < CHUNK_LEN {
+            break;
+        }
+    }
+
+    let mut entry = [0u8; INDEX_ENTRY_LEN];
+    for crc in crcs.iter() {
+        encode_into(&crc.to_le_bytes(), &mut entry);
+        output.write_all(&entry)?;
+    }
+    output.write_all(&footer(len))?;
+    output.flush()?;
+    Ok(len)
+}
+
+/// 保護したファイルを調べて，訂正できた誤りをその場で書き戻す．
+///
+/// CRCが合わないチャンクは書き戻さない．訂正できない索引は，チャンクが訂正できれば作り直して書き戻す．
+pub fn repair<F: Read + Write + Seek>(file: &mut F) -> io::Result<FileReport> {
+    scan(file, |file, pos, coded| {
+        file.seek(SeekFrom::Start(pos))?;
+        file.write_all(coded)
+    }, |_, _| Ok(()))
+}
+
+/// 保護したファイルを訂正して元のファイルを書き出す．
+///
+/// 訂正できないチャンクもそのまま（誤ったまま）書き出すので，結果の`lost`を確かめること．
+pub fn extract<R: Read + Seek, W: Write>(input: &mut R, mut output: W) -> io::Result<FileReport> {
+    scan(input, |_, _, _| Ok(()), |data, _| output.write_all(data))
+}
+
+/// 全てのチャンクを調べる．
+///
+/// * `write_back`: 訂正した符号語の列を（ファイル内の位置，バイト列）で受け取る
+/// * `emit`: 復号したデータを（データ，チャンクが正しいか）で受け取る
+fn scan<F, B, E>(file: &mut F, mut write_back: B, mut emit: E) -> io::Result<FileReport>
+where F: Read + Seek, B: FnMut(&mut F, u64, &[u8]) -> io::Result<()>, E: FnMut(&[u8], bool) -> io::Result<()> {
+    let total = file.seek(SeekFrom::End(0))?;
+    if total < FOOTER_LEN as u64 {
+        return Err( invalid("file too short") );
+    }
+
+    // フッタ
+    let footer_pos = total - FOOTER_LEN as u64;
+    let mut coded = [0u8; FOOTER_LEN];
+    file.seek(SeekFrom::Start(footer_pos))?;
+    file.read_exact(&mut coded)?;
+    let mut raw = [0u8; 16];
+    let footer_bits = correct(&mut coded, |_| ()).ok_or_else(|| invalid("footer is uncorrectable"))?;
+    RawReader::new(&coded).read(&mut raw);
+    if raw[..4] != MAGIC || u32::from_le_bytes([raw[12], raw[13], raw[14], raw[15]]) as usize != CHUNK_LEN {
+        return Err( invalid("not a protected file") );
+    }
+    if footer_bits > 0 {
+        write_back(file, footer_pos, &coded)?;
+    }
+    let mut len_bytes = [0u8; 8];
+    len_bytes.copy_from_slice(&raw[4..12]);
+    let len = u64::from_le_bytes(len_bytes);
+    let chunks = len.div_ceil(CHUNK_LEN as u64);
+    let chunk_coded = protected_len(CHUNK_LEN) as u64;
+    // 長さはファイルから読んだものなので，溢れないか確かめる
+    let mismatch = || invalid("file length does not match the footer");
+    let index_pos = chunks.checked_mul(chunk_coded).ok_or_else(mismatch)?;
+    let expected = chunks.checked_mul(INDEX_ENTRY_LEN as u64)
+        .and_then(|n| n.checked_add(index_pos))
+        .and_then(|n| n.checked_add(FOOTER_LEN as u64))
+        .ok_or_else(mismatch)?;
+    if expected != total {
+        return Err( mismatch() );
+    }
+
+    let mut report = FileReport{ len, chunks, corrected_bits: footer_bits as u64, ..FileReport::default() };
+    let mut coded = [0u8; protected_len(CHUNK_LEN)];
+    let mut chunk = [0u8; CHUNK_LEN];
+    for k in 0..chunks {
+        let start = k * CHUNK_LEN as u64;
+        let end = len.min(start + CHUNK_LEN as u64);
+
+        // チャンク
+        let chunk_pos = k * chunk_coded;
+        file.seek(SeekFrom::Start(chunk_pos))?;
+        file.read_exact(&mut coded)?;
+        let mut ranges = Vec::new();
+        let bits = correct(&mut coded, |j| {
+            // j番目の符号語は元のデータの 12j〜12j+11 bit
+            let lo = start + (12 * j as u64) / 8;
+            let hi = end.min(start + (12 * j as u64 + 12).div_ceil(8));
+            if lo < hi {
+                push_range(&mut ranges, lo..hi);
+            }
+        });
+        RawReader::new(&coded).read(&mut chunk);
+
+        // 索引
+        let entry_pos = index_pos + k * INDEX_ENTRY_LEN as u64;
+        let mut entry = [0u8; INDEX_ENTRY_LEN];
+        file.seek(SeekFrom::Start(entry_pos))?;
+        file.read_exact(&mut entry)?;
+        let crc = match correct(&mut entry, |_| ()) {
+            Some(bits) => {
+                if bits > 0 {
+                    write_back(file, entry_pos, &entry)?;
+                    report.corrected_bits += bits as u64;
+                }
+                let mut b = [0u8; 4];
+                RawReader::new(&entry).read(&mut b);
+                Some( u32::from_le_bytes(b) )
+            },
+            None => None,
+        };
+
+        // 索引が壊れていても，チャンクの符号語が全て訂正できればチャンクは正しいとして索引を作り直す．
+        // 索引が読めればCRCで誤訂正を確かめる．
+        let ok = match (bits, crc) {
+            (None, _) => false,
+            (Some(_), None) => {
+                encode_into(&crc32(&chunk).to_le_bytes(), &mut entry);
+                write_back(file, entry_pos, &entry)?;
+                true
+            },
+            (Some(_), Some(crc)) => crc == crc32(&chunk),
+        };
+        if ok {
+            let bits = bits.unwrap();
+            if bits > 0 {
+                write_back(file, chunk_pos, &coded)?;
+                report.corrected_bits += bits as u64;
+                for r in ranges {
+                    push_range(&mut report.recovered, r);
+                }
+            }
+        } else {
+            push_range(&mut report.lost, start..end);
+        }
+        emit(&chunk[..(end - start) as usize], ok)?;
+    }
+    Ok(report)
+}
+
+/// バイト列の符号語をその場で訂正する．
+///
+/// * `on_corrected`: 誤りを訂正した符号語の番号を受け取る
+/// * return: 訂正したビット数．訂正できない符号語があればNone（訂正できたものは訂正する）．
+fn correct<C: FnMut(usize)>(coded: &mut [u8], mut on_corrected: C) -> Option<usize> {
+    let mut bits = 0;
+    let mut failed = false;
+    for (j, c) in coded.chunks_exact_mut(3).enumerate() {
+        let r = u32::from_be_bytes([0, c[0], c[1], c[2]]);
+        match super::ecc_verbose(r) {
+            Some(x) => if x.errors > 0 {
+                bits += x.errors as usize;
+                c.copy_from_slice(&x.code.to_be_bytes()[1..]);
+                on_corrected(j);
+            },
+            None => failed = true,
+        }
+    }
+    if failed { None } else { Some(bits) }
+}
+
+/// 範囲を追加する．直前の範囲と重なるか隣り合う場合はつなげる．
+fn push_range(ranges: &mut Vec<Range<u64>>, r: Range<u64>) {
+    if let Some(last) = ranges.last_mut() {
+        if r.start <= last.end {
+            last.end = last.end.max(r.end);
+            return;
+        }
+    }
+    ranges.push(r);
+}
+
+fn encode_into(data: &[u8], out: &mut [u8]) {
+    let mut w = RawWriter::new(out);
+    w.write(data);
+    w.finish();
+}
+
+fn footer(len: u64) -> [u8; FOOTER_LEN] {
+    let mut raw = [0u8; 16];
+    raw[..4].copy_from_slice(&MAGIC);
+    raw[4..12].copy_from_slice(&len.to_le_bytes());
+    raw[12..].copy_from_slice(&(CHUNK_LEN as u32).to_le_bytes());
+    let mut out = [0u8; FOOTER_LEN];
+    encode_into(&raw, &mut out);
+    out
+}
+
+/// `buf`が埋まるか終端まで読む．
+fn read_full<R: Read>(input: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match input.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(m) => n += m,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[test]
+fn test_file() {
+    use std::io::Cursor;
+
+    let data: Vec<u8> = (0..(CHUNK_LEN * 2 + 100)).map(|i| (i * 7 + i / 256) as u8).collect();
+    let mut file = Cursor::new(Vec::new());
+    assert_eq!(protect(&data[..], &mut file).unwrap(), data.len() as u64);
+
+    // 1つ目のチャンクの先頭の符号語に3bit，3つ目のチャンクの符号語に4bitの誤り
+    let chunk_coded = protected_len(CHUNK_LEN);
+    {
+        let v = file.get_mut();
+        v[0] ^= 0x80;
+        v[2] ^= 0x03;
+        v[2 * chunk_coded + 30] ^= 0x0F;
+        let n = v.len();
+        v[n - 1] ^= 0x01;  // フッタ
+    }
+
+    let report = repair(&mut file).unwrap();
+    assert_eq!(report.chunks, 3);
+    assert_eq!(report.corrected_bits, 4);
+    assert_eq!(report.recovered.as_slice(), &[Range{ start: 0, end: 2 }]);
+    let start = 2 * CHUNK_LEN as u64;
+    assert_eq!(report.lost.as_slice(), &[Range{ start, end: data.len() as u64 }]);
+
+    // 書き戻したので，2回目は訂正するものが無い
+    let report = repair(&mut file).unwrap();
+    assert_eq!((report.corrected_bits, report.recovered.len(), report.lost.len()), (0, 0, 1));
+
+    file.get_mut()[2 * chunk_coded + 30] ^= 0x0F;
+    let mut out = Vec::new();
+    let report = extract(&mut file, &mut out).unwrap();
+    assert!(report.is_ok());
+    assert_eq!(out, data);
+
+    // 索引が訂正できなくても，チャンクが正しければ失われたことにせず，索引を作り直す
+    let index_pos = 3 * chunk_coded;
+    file.get_mut()[index_pos + INDEX_ENTRY_LEN] ^= 0x0F;
+    let report = repair(&mut file).unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.corrected_bits, 0);
+    file.get_mut()[chunk_coded + 30] ^= 0xF0;  // 2つ目のチャンクにも4bit誤り
+    file.get_mut()[index_pos + INDEX_ENTRY_LEN] ^= 0x0F;
+    let report = repair(&mut file).unwrap();
+    assert_eq!(report.lost.as_slice(), &[Range{ start: CHUNK_LEN as u64, end: 2 * CHUNK_LEN as u64 }]);
+    file.get_mut()[chunk_coded + 30] ^= 0xF0;
+    let mut out = Vec::new();
+    let report = extract(&mut file, &mut out).unwrap();
+    assert!(report.is_ok());
+    assert_eq!(out, data);
+
+    assert!(repair(&mut Cursor::new(std::vec![0u8; 40])).is_err());
+
+    // フッタの長さが大きすぎても溢れない
+    let mut file = Cursor::new(footer(u64::MAX).to_vec());
+    assert_eq!(repair(&mut file).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
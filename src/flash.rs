@@ -0,0 +1,272 @@
+//! 符号化して読み書きするNORフラッシュ
+//!
+//! `ProtectedFlash`はNORフラッシュのドライバを包み，書き込むときに符号化して，
+//! 読み出すときに訂正する．SPIフラッシュを，重要なデータ用のソフトウェアECC付きの記憶域にする．
+//!
+//! * 3バイト（12bit × 2）を2つの符号語（6バイト）にして書き込むので，容量は半分より少し小さくなる．
+//! * 符号語が消去の単位をまたがないように，消去の単位ごとに6で割り切れない余りは使わない．
+//! * 消去したセル（0xFF）は全1の符号語なので，消去した領域は0xFFとして読める．
+//! * 訂正は1→0の誤りを優先する（`nor::correct`）．
+//! * 書き込みは消去後に3バイト単位で一度だけ行うこと（符号語の上では1→0の書き込みにならない）．
+//!
+//! ドライバは`embedded-storage`の`NorFlash`を実装したもの（`storage`フィーチャ）で，
+//! `ProtectedFlash`自身も`NorFlash`を実装するので，その上にそのままファイルシステムなどを載せられる．
+
+use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash};
+
+/// 3バイトを符号化した6バイト
+const GROUP: usize = 6;
+/// 符号化する前の3バイト
+const UNIT: usize = 3;
+
+/// `ProtectedFlash`のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlashError<E> {
+    /// ドライバのエラー
+    Inner(E),
+    /// 位置や長さが単位の倍数でない
+    NotAligned,
+    /// 範囲の外
+    OutOfBounds,
+    /// 訂正できない符号語がある（符号化する前の位置）
+    Uncorrectable(u32),
+}
+
+impl<E: NorFlashError> NorFlashError for FlashError<E> {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Self::Inner(e) => e.kind(),
+            Self::NotAligned => NorFlashErrorKind::NotAligned,
+            Self::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            Self::Uncorrectable(_) => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+/// 符号化して読み書きするNORフラッシュ
+#[derive(Debug)]
+pub struct ProtectedFlash<F> {
+    inner: F,
+    corrected: u32,
+}
+
+impl<F: NorFlash> ProtectedFlash<F> {
+    /// 符号化する前の，消去の単位あたりのバイト数
+    pub const SECTOR_SIZE: usize = F::ERASE_SIZE / GROUP * UNIT;
+
+    /// ドライバを包む．
+    ///
+    /// ドライバの読み書きの単位が6の約数でないか，消去の単位が6未満ならpanicする．
+    pub fn new(inner: F) -> Self {
+        assert!(GROUP.is_multiple_of(F::READ_SIZE) && GROUP.is_multiple_of(F::WRITE_SIZE));
+        assert!(F::ERASE_SIZE >= GROUP);
+        Self{ inner, corrected: 0 }
+    }
+
+    /// ドライバを返す．
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+
+    /// これまでの読み出しで訂正したビット数
+    #[inline]
+    pub fn corrected(&self) -> u32 {
+        self.corrected
+    }
+
+    /// 符号化する前の位置（3の倍数）を，ドライバの位置にする．
+    fn physical(offset: usize) -> u32 {
+        let sector = offset / Self::SECTOR_SIZE;
+        let within = offset % Self::SECTOR_SIZE;
+        (sector * F::ERASE_SIZE + within * 2) as u32
+    }
+
+    fn check(&self, offset: u32, len: usize) -> Result<usize, FlashError<F::Error>> {
+        let offset = offset as usize;
+        if !offset.is_multiple_of(UNIT) || !len.is_multiple_of(UNIT) {
+            return Err(FlashError::NotAligned);
+        }
+        match offset.checked_add(len) {
+            Some(end) if end <= self.capacity() => Ok(offset),
+            _ => Err(FlashError::OutOfBounds),
+        }
+    }
+
+    /// 消去の単位を1つ読み直し，訂正した符号語があれば消去して書き直す．
+    ///
+    /// * `sector`: 消去の単位の番号
+    /// * `buf`: 作業用（`SECTOR_SIZE`バイト以上）
+    /// * return: 訂正したビット数（0なら書き直していない）
+    pub fn scrub(&mut self, sector: usize, buf: &mut [u8]) -> Result<u32, FlashError<F::Error>> {
+        let buf = buf.get_mut(..Self::SECTOR_SIZE).ok_or(FlashError::OutOfBounds)?;
+        let offset = (sector * Self::SECTOR_SIZE) as u32;
+        let before = self.corrected;
+        self.read(offset, buf)?;
+        let errors = self.corrected - before;
+        if errors > 0 {
+            let from = (sector * F::ERASE_SIZE) as u32;
+            self.inner.erase(from, from + F::ERASE_SIZE as u32).map_err(FlashError::Inner)?;
+            // 消去したままの値は書かなくても同じ
+            for (i, unit) in buf.chunks(UNIT).enumerate() {
+                if unit != [0xFF; UNIT] {
+                    self.write(offset + (i * UNIT) as u32, unit)?;
+                }
+            }
+        }
+        Ok(errors)
+    }
+}
+
+impl<F: NorFlash> ErrorType for ProtectedFlash<F> {
+    type Error = FlashError<F::Error>;
+}
+
+impl<F: NorFlash> ReadNorFlash for ProtectedFlash<F> {
+    const READ_SIZE: usize = UNIT;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let offset = self.check(offset, bytes.len())?;
+        for (i, unit) in bytes.chunks_mut(UNIT).enumerate() {
+            let pos = offset + i * UNIT;
+            let mut raw = [0u8; GROUP];
+            self.inner.read(Self::physical(pos), &mut raw).map_err(FlashError::Inner)?;
+            let mut bits = 0u32;
+            for half in raw.chunks(3) {
+                let r = u32::from_be_bytes([0, half[0], half[1], half[2]]);
+                let (code, errors) = crate::nor::correct(r).ok_or(FlashError::Uncorrectable(pos as u32))?;
+                self.corrected += errors as u32;
+                bits = (bits << 12) | crate::decode(code) as u32;
+            }
+            unit.copy_from_slice(&bits.to_be_bytes()[1..]);
+        }
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity() / F::ERASE_SIZE * Self::SECTOR_SIZE
+    }
+}
+
+impl<F: NorFlash> NorFlash for ProtectedFlash<F> {
+    const WRITE_SIZE: usize = UNIT;
+    const ERASE_SIZE: usize = Self::SECTOR_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let (from, to) = (from as usize, to as usize);
+        if !from.is_multiple_of(Self::SECTOR_SIZE) || !to.is_multiple_of(Self::SECTOR_SIZE) {
+            return Err(FlashError::NotAligned);
+        }
+        if from > to || to > self.capacity() {
+            return Err(FlashError::OutOfBounds);
+        }
+        let scale = |o: usize| (o / Self::SECTOR_SIZE * F::ERASE_SIZE) as u32;
+        self.inner.erase(scale(from), scale(to)).map_err(FlashError::Inner)
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let offset = self.check(offset, bytes.len())?;
+        for (i, unit) in bytes.chunks(UNIT).enumerate() {
+            let bits = u32::from_be_bytes([0, unit[0], unit[1], unit[2]]);
+            let hi = crate::encode((bits >> 12) as u16).to_be_bytes();
+            let lo = crate::encode(bits as u16 & 0xFFF).to_be_bytes();
+            let raw = [hi[1], hi[2], hi[3], lo[1], lo[2], lo[3]];
+            self.inner.write(Self::physical(offset + i * UNIT), &raw).map_err(FlashError::Inner)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_flash() {
+    /// RAM上のNORフラッシュ（書き込みは1→0だけ）
+    struct Ram {
+        cells: [u8; 128],
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct RamError;
+
+    impl NorFlashError for RamError {
+        fn kind(&self) -> NorFlashErrorKind {
+            NorFlashErrorKind::Other
+        }
+    }
+
+    impl ErrorType for Ram {
+        type Error = RamError;
+    }
+
+    impl ReadNorFlash for Ram {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), RamError> {
+            let o = offset as usize;
+            bytes.copy_from_slice(self.cells.get(o..o + bytes.len()).ok_or(RamError)?);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.cells.len()
+        }
+    }
+
+    impl NorFlash for Ram {
+        const WRITE_SIZE: usize = 1;
+        const ERASE_SIZE: usize = 64;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), RamError> {
+            self.cells[from as usize..to as usize].fill(0xFF);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), RamError> {
+            for (c, b) in self.cells[offset as usize..].iter_mut().zip(bytes.iter()) {
+                *c &= b;
+            }
+            Ok(())
+        }
+    }
+
+    let mut flash = ProtectedFlash::new(Ram{ cells: [0xFF; 128] });
+    assert_eq!(ProtectedFlash::<Ram>::SECTOR_SIZE, 30);
+    assert_eq!(flash.capacity(), 60);
+
+    let data = *b"critical config data!!";
+    let mut rec = [0u8; 24];
+    rec[..22].copy_from_slice(&data);
+    flash.write(27, &rec).unwrap();  // 消去の単位をまたぐ
+    let mut back = [0u8; 24];
+    flash.read(27, &mut back).unwrap();
+    assert_eq!(back, rec);
+    let mut erased = [0u8; 3];
+    flash.read(57, &mut erased).unwrap();
+    assert_eq!(erased, [0xFF; 3]);
+
+    assert_eq!(flash.write(1, &rec[..3]), Err(FlashError::NotAligned));
+    assert_eq!(flash.read(57, &mut back[..6]), Err(FlashError::OutOfBounds));
+    assert_eq!(flash.erase(0, 31), Err(FlashError::NotAligned));
+    assert_eq!(flash.read(60, &mut back[..3]).unwrap_err().kind(), NorFlashErrorKind::OutOfBounds);
+
+    // 1→0の誤りを訂正し，スクラブで書き直す
+    let mut ram = flash.into_inner();
+    let errors = (ram.cells[64] & 0x81).count_ones() + (ram.cells[70] & 0x03).count_ones();
+    assert!(errors > 0);
+    ram.cells[64] &= 0x7E;
+    ram.cells[70] &= 0xFC;
+    let mut flash = ProtectedFlash::new(ram);
+    flash.read(27, &mut back).unwrap();
+    assert_eq!(back, rec);
+    assert_eq!(flash.corrected(), errors);
+    let mut buf = [0u8; 30];
+    assert_eq!(flash.scrub(1, &mut buf), Ok(errors));
+    assert_eq!(flash.scrub(1, &mut buf), Ok(0));
+    flash.read(27, &mut back).unwrap();
+    assert_eq!(back, rec);
+
+    // 借用したドライバも包める
+    let mut ram = flash.into_inner();
+    let mut flash = ProtectedFlash::new(&mut ram);
+    flash.erase(30, 60).unwrap();
+    flash.read(30, &mut back[..3]).unwrap();
+    assert_eq!(back[..3], [0xFF; 3]);
+}
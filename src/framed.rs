@@ -0,0 +1,131 @@
+//! 長さ付きフレームの符号器・復号器（`tokio`フィーチャ）
+//!
+//! `tokio_util::codec`の`Encoder`と`Decoder`を実装するので，`FramedRead`や`FramedWrite`で
+//! バイトストリームから訂正したフレームを読み書きできる．
+//! フレームの構成は`pipeline`と同じ（ペイロード長の符号語と，12bitずつ符号化したペイロード）．
+
+use std::io;
+use std::vec::Vec;
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::pipeline::{decode_frame, encode_frame, PipelineError};
+use crate::protect::protected_len;
+
+/// 長さ付きフレームの符号器・復号器
+///
+/// 訂正できないフレームや長すぎるペイロードは`io::ErrorKind::InvalidData`のエラーにする．
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameCodec {
+    frames: u64,
+}
+
+impl FrameCodec {
+    /// 符号器・復号器を作る．
+    pub fn new() -> Self {
+        Self{ frames: 0 }
+    }
+
+    /// これまでに取り出したフレームの数
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+}
+
+fn invalid(e: PipelineError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+impl Encoder<&[u8]> for FrameCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, payload: &[u8], dst: &mut BytesMut) -> io::Result<()> {
+        dst.extend_from_slice(&encode_frame(payload).map_err(invalid)?);
+        Ok(())
+    }
+}
+
+impl Encoder<Vec<u8>> for FrameCodec {
+    type Error = io::Error;
+
+    #[inline]
+    fn encode(&mut self, payload: Vec<u8>, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode(&payload[..], dst)
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Vec<u8>>> {
+        if src.len() < 3 {
+            return Ok(None);
+        }
+        let header = crate::uart::from_bytes([src[0], src[1], src[2]]);
+        let len = crate::ecc(header).ok_or_else(|| invalid(PipelineError::Uncorrectable))?;
+        let frame_len = 3 + protected_len(crate::decode(len) as usize);
+        if src.len() < frame_len {
+            return Ok(None);
+        }
+        let payload = decode_frame(&src[..frame_len]).map_err(invalid)?;
+        src.advance(frame_len);
+        self.frames += 1;
+        Ok(Some(payload))
+    }
+}
+
+#[test]
+fn test_framed() {
+    use core::pin::Pin;
+    use core::task::{Context, Poll, Waker};
+    use futures_core::Stream;
+    use tokio_util::codec::FramedRead;
+
+    let mut codec = FrameCodec::new();
+    let mut wire = BytesMut::new();
+    codec.encode(&b"tokio"[..], &mut wire).unwrap();
+    codec.encode(b"framed".to_vec(), &mut wire).unwrap();
+    codec.encode(&[][..], &mut wire).unwrap();
+    wire[1] ^= 0x40;
+    wire[6] ^= 0x03;
+
+    // 少しずつ届いても，揃うまで待って取り出す
+    let mut rx = BytesMut::new();
+    let mut out = Vec::new();
+    for b in wire.iter() {
+        rx.extend_from_slice(&[*b]);
+        if let Some(payload) = codec.decode(&mut rx).unwrap() {
+            out.push(payload);
+        }
+    }
+    assert_eq!(out, [b"tokio".to_vec(), b"framed".to_vec(), Vec::new()]);
+    assert!(rx.is_empty());
+    assert_eq!(codec.frames(), 3);
+
+    // tokioのFramedReadで読む（&[u8]は常に読み出せるので，実行器なしでポーリングできる）
+    let mut wire = BytesMut::new();
+    codec.encode(&b"tokio"[..], &mut wire).unwrap();
+    codec.encode(&b"util"[..], &mut wire).unwrap();
+    wire[4] ^= 0x20;
+    let mut framed = FramedRead::new(&wire[..], FrameCodec::new());
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut next = || match Pin::new(&mut framed).poll_next(&mut cx) {
+        Poll::Ready(item) => item.map(|r| r.unwrap()),
+        Poll::Pending => panic!("pending"),
+    };
+    assert_eq!(next(), Some(b"tokio".to_vec()));
+    assert_eq!(next(), Some(b"util".to_vec()));
+    assert_eq!(next(), None);
+    assert_eq!(framed.decoder().frames(), 2);
+
+    let mut bad = BytesMut::new();
+    codec.encode(&b"x"[..], &mut bad).unwrap();
+    bad[0] ^= 0xF0;
+    bad[2] ^= 0x0F;
+    assert_eq!(codec.decode(&mut bad).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    let long = std::vec![0u8; crate::pipeline::MAX_PAYLOAD_LEN + 1];
+    assert!(codec.encode(long, &mut bad).is_err());
+}
@@ -0,0 +1,118 @@
+//! 4値FSKのシンボル変換
+//!
+//! 符号語のビット列を上位ビットから2bit（ダイビット）ずつ区切り，4値のシンボルに対応させる．
+//! 1符号語は12シンボルになる．
+//!
+//! シンボルは周波数偏移の小さい順に0〜3で表す（M17やDMRの -3, -1, +1, +3 に対応）．
+//! グレイ符号の対応にすると，隣のシンボルと取り違えても1bitの誤りで済む．
+
+/// ダイビットとシンボルの対応
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    to_symbol: [u8; 4],  // ダイビット → シンボル
+    to_dibit: [u8; 4],   // シンボル → ダイビット
+}
+
+impl Mapping {
+    /// 自然2進の対応（00 → 0, 01 → 1, 10 → 2, 11 → 3）
+    pub const BINARY: Mapping = Mapping{ to_symbol: [0, 1, 2, 3], to_dibit: [0, 1, 2, 3] };
+
+    /// グレイ符号の対応（00 → 2, 01 → 3, 10 → 1, 11 → 0）
+    ///
+    /// M17やDMRと同じく，+1が00，+3が01，-1が10，-3が11になる並び．
+    pub const GRAY: Mapping = Mapping{ to_symbol: [2, 3, 1, 0], to_dibit: [3, 2, 0, 1] };
+
+    /// 任意の対応を作る．
+    ///
+    /// * `to_symbol`: ダイビット（0〜3）をシンボルに対応させる表
+    /// * return: 0〜3の並べ替えになっていなければNone．
+    pub fn new(to_symbol: [u8; 4]) -> Option<Self> {
+        let mut to_dibit = [0xFF; 4];
+        for (dibit, symbol) in to_symbol.iter().enumerate() {
+            let slot = to_dibit.get_mut(*symbol as usize)?;
+            if *slot != 0xFF {
+                return None;
+            }
+            *slot = dibit as u8;
+        }
+        Some( Self{ to_symbol, to_dibit } )
+    }
+
+    /// ダイビットをシンボルにする．
+    #[inline]
+    pub fn symbol(&self, dibit: u8) -> u8 {
+        self.to_symbol[(dibit & 3) as usize]
+    }
+
+    /// シンボルをダイビットにする．
+    #[inline]
+    pub fn dibit(&self, symbol: u8) -> u8 {
+        self.to_dibit[(symbol & 3) as usize]
+    }
+
+    /// 符号語（下位24bit）を12個のシンボルにする．
+    pub fn to_symbols(&self, code: u32) -> [u8; 12] {
+        let mut out = [0; 12];
+        for (i, s) in out.iter_mut().enumerate() {
+            *s = self.symbol( (code >> (22 - 2 * i)) as u8 );
+        }
+        out
+    }
+
+    /// 12個のシンボルを受信語にする．
+    pub fn from_symbols(&self, symbols: &[u8; 12]) -> u32 {
+        symbols.iter().fold(0, |r, s| (r << 2) | self.dibit(*s) as u32)
+    }
+
+    /// 符号語の列をシンボル列にする．
+    ///
+    /// * return: 書き込んだシンボル数．`out`が足りなければNone．
+    pub fn map(&self, codes: &[u32], out: &mut [u8]) -> Option<usize> {
+        let len = codes.len() * 12;
+        let out = out.get_mut(..len)?;
+        for (code, dst) in codes.iter().zip(out.chunks_exact_mut(12)) {
+            dst.copy_from_slice( &self.to_symbols(*code) );
+        }
+        Some(len)
+    }
+
+    /// シンボル列を受信語の列にする．端数のシンボルは無視する．
+    ///
+    /// * return: 書き込んだ受信語の数．`out`が足りなければNone．
+    pub fn demap(&self, symbols: &[u8], out: &mut [u32]) -> Option<usize> {
+        let n = symbols.len() / 12;
+        let out = out.get_mut(..n)?;
+        for (dst, s) in out.iter_mut().zip(symbols.chunks_exact(12)) {
+            let mut block = [0; 12];
+            block.copy_from_slice(s);
+            *dst = self.from_symbols(&block);
+        }
+        Some(n)
+    }
+}
+
+#[test]
+fn test_fsk4() {
+    assert_eq!(Mapping::new([2, 3, 1, 0]), Some(Mapping::GRAY));
+    assert_eq!(Mapping::new([0, 1, 1, 3]), None);
+    assert_eq!(Mapping::new([0, 1, 2, 4]), None);
+
+    // グレイ符号なら隣のシンボルとの取り違えは1bitの誤り
+    for s in 0..3 {
+        assert_eq!((Mapping::GRAY.dibit(s) ^ Mapping::GRAY.dibit(s + 1)).count_ones(), 1);
+    }
+
+    let codes = [super::encode(0x3C5), super::encode(0xA0F)];
+    let mut symbols = [0u8; 24];
+    assert_eq!(Mapping::GRAY.map(&codes, &mut symbols), Some(24));
+
+    // 3シンボルを隣のシンボルと取り違える
+    symbols[0] ^= 1;
+    symbols[5] = if symbols[5] == 3 { 2 } else { symbols[5] + 1 };
+    symbols[11] = if symbols[11] == 0 { 1 } else { symbols[11] - 1 };
+
+    let mut rx = [0u32; 2];
+    assert_eq!(Mapping::GRAY.demap(&symbols, &mut rx), Some(2));
+    assert_eq!(super::ecc(rx[0]), Some(codes[0]));
+    assert_eq!(rx[1], codes[1]);
+}
@@ -0,0 +1,137 @@
+//! GF(2)上の行列の計算
+//!
+//! 行列は各行を`u32`の下位ビットに入れた行の列で表す（24列まで）．
+//! `linear`や`codec`，`bmatrix`の検証で使っているものを，利用者の行列にも使えるように公開する．
+//!
+//! ベクトルと行列の積は2通りある．
+//! * `vec_mul`: 行ベクトル×行列（符号化．i行目がベクトルのbit (k-1-i) に対応する）
+//! * `mul_vec`: 行列×列ベクトル（シンドローム．i行目の結果が出力のbit (m-1-i) に入る）
+
+/// 列数の上限
+pub const MAX_COLS: usize = 24;
+
+/// 行ベクトル`v`と行列の積．`v`のbit (k-1-i) が1の行の和（kは行数，32行まで）．
+pub fn vec_mul(v: u32, rows: &[u32]) -> u32 {
+    let k = rows.len();
+    rows.iter().enumerate().fold(0, |c, (i, row)| c ^ (((v >> (k - 1 - i)) & 1) * row))
+}
+
+/// 行列と列ベクトル`v`の積．i行目と`v`の内積が出力のbit (m-1-i) に入る（mは行数，32行まで）．
+pub fn mul_vec(rows: &[u32], v: u32) -> u32 {
+    rows.iter().fold(0, |s, row| (s << 1) | ((row & v).count_ones() & 1))
+}
+
+/// 既約な行の集まりに行を加える．線形独立なら加えてtrue．
+///
+/// `basis[b]`は先頭（最上位の1）がbit bの行．
+fn insert(basis: &mut [u32; MAX_COLS], mut row: u32) -> bool {
+    for b in (0..MAX_COLS).rev() {
+        if (row >> b) & 1 == 0 {
+            continue;
+        }
+        if basis[b] == 0 {
+            basis[b] = row;
+            return true;
+        }
+        row ^= basis[b];
+    }
+    false
+}
+
+/// 行列の階数．行の数は問わない．
+pub fn rank(rows: &[u32]) -> usize {
+    let mut basis = [0u32; MAX_COLS];
+    rows.iter().filter(|row| insert(&mut basis, *row & 0xFFFFFF)).count()
+}
+
+/// 行列を被約階段形（RREF）にする．
+///
+/// 先頭の1が上位ビットにある行から順に並べ，先頭の列の他の行は0にする．
+/// 残りの行は0になる．
+///
+/// * return: 階数
+pub fn rref(rows: &mut [u32]) -> usize {
+    let mut r = 0;
+    for b in (0..MAX_COLS).rev() {
+        if let Some(p) = (r..rows.len()).find(|&i| (rows[i] >> b) & 1 == 1) {
+            rows.swap(r, p);
+            for i in 0..rows.len() {
+                if i != r && (rows[i] >> b) & 1 == 1 {
+                    rows[i] ^= rows[r];
+                }
+            }
+            r += 1;
+        }
+    }
+    r
+}
+
+/// 行列の零空間（`mul_vec(rows, x) == 0`となるx）の基底を求める．
+///
+/// * `cols`: 列数（1〜24）．行の`cols`ビットより上は見ない．
+/// * return: （基底，次元）．基底は先頭の「次元」個．
+pub fn null_space(rows: &[u32], cols: usize) -> ([u32; MAX_COLS], usize) {
+    assert!(cols > 0 && cols <= MAX_COLS);
+    let mask = (1u32 << cols) - 1;
+    let mut basis = [0u32; MAX_COLS];
+    for row in rows.iter() {
+        insert(&mut basis, row & mask);
+    }
+    // 先頭の列を他の行から消して被約にする
+    for b in (0..cols).rev() {
+        if basis[b] == 0 {
+            continue;
+        }
+        for upper in b + 1..cols {
+            if (basis[upper] >> b) & 1 == 1 {
+                basis[upper] ^= basis[b];
+            }
+        }
+    }
+
+    // 先頭でない列ごとに，その列を1にして先頭の列で釣り合わせる
+    let mut out = [0u32; MAX_COLS];
+    let mut n = 0;
+    for free in (0..cols).filter(|&f| basis[f] == 0) {
+        let mut x = 1 << free;
+        for (p, row) in basis.iter().enumerate().take(cols) {
+            if (row >> free) & 1 == 1 {
+                x |= 1 << p;
+            }
+        }
+        out[n] = x;
+        n += 1;
+    }
+    (out, n)
+}
+
+#[test]
+fn test_gf2() {
+    let data = 0xA53;
+    assert_eq!(vec_mul(data, &crate::G), crate::encode(data as u16));
+    assert_eq!(mul_vec(&[0b110, 0b011], 0b010), 0b11);
+    assert_eq!(mul_vec(&[0b110, 0b011], 0b101), 0b11);
+
+    assert_eq!(rank(&crate::G), 12);
+    assert_eq!(rank(&crate::H_T), 12);
+    assert_eq!(rank(&[0b101, 0b011, 0b110, 0]), 2);
+
+    let mut g = crate::G;
+    g.reverse();
+    assert_eq!(rref(&mut g), 12);
+    assert_eq!(g, crate::G);
+    let mut m = [0b110, 0b011, 0b101];
+    assert_eq!(rref(&mut m), 2);
+    assert_eq!(m, [0b101, 0b011, 0]);
+
+    // 自己双対なので，生成行列の零空間は符号そのもの
+    let (basis, dim) = null_space(&crate::G, 24);
+    assert_eq!(dim, 12);
+    assert_eq!(rank(&basis[..dim]), 12);
+    assert!(basis[..dim].iter().all(|v| crate::syndrome(*v) == 0));
+
+    let (basis, dim) = null_space(&[0b1100, 0b0110], 4);
+    assert_eq!(dim, 2);
+    assert!(basis[..dim].iter().all(|v| mul_vec(&[0b1100, 0b0110], *v) == 0));
+    assert_eq!(null_space(&[], 3).1, 3);
+}
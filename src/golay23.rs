@@ -0,0 +1,91 @@
+//! (23,12)２元ゴレイ符号との相互変換
+//!
+//! 拡張ゴレイ符号の符号語は全て重みが偶数なので，最下位ビットは他の23bitの全体パリティになっている．
+//! これを取り除くと(23,12)完全符号の符号語になる．
+
+/// 23bitの符号語に全体パリティを付加して24bitの符号語にする．
+///
+/// * `code`: (23,12)符号の符号語（下位23bit）
+#[inline]
+pub fn extend(code: u32) -> u32 {
+    let code = code & 0x7FFFFF;
+    (code << 1) | (code.count_ones() & 1)
+}
+
+/// 24bitの符号語から全体パリティを取り除いて23bitの符号語にする．
+///
+/// * `code`: 拡張ゴレイ符号の符号語（下位24bit）
+#[inline]
+pub fn puncture(code: u32) -> u32 {
+    (code >> 1) & 0x7FFFFF
+}
+
+/// 12bitのデータを23bitの符号語に変換する．
+#[inline]
+pub fn encode(a: u16) -> u32 {
+    puncture( super::encode(a) )
+}
+
+/// 23bitの受信語の誤り訂正を行う．
+///
+/// 完全符号なので3bit以下の誤りは必ず訂正でき，常に何らかの符号語を返す．
+/// 4bit以上の誤りでは正しく訂正できているわけではない．
+///
+/// * `r`: 受信語（下位23bit）
+/// * return: 訂正した23bitの符号語．
+#[inline]
+pub fn ecc(r: u32) -> u32 {
+    // 全体パリティを消失として扱えば 2×3 + 1 < 8 なので3bitまで訂正できる．
+    // 23bitの任意の語から距離3以内に必ず符号語があるのでunwrapしても良い．
+    let code = super::ecc_erasure((r & 0x7FFFFF) << 1, 1).unwrap();
+    puncture(code)
+}
+
+/// 23bitの符号語からデータを取り出す．
+#[inline]
+pub fn decode(code: u32) -> u16 {
+    ((code >> 11) & 0xFFF) as u16
+}
+
+/// 符号語の形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Form {
+    /// (23,12)完全符号
+    Perfect,
+    /// (24,12)拡張ゴレイ符号
+    Extended,
+}
+
+/// どちらの形式の受信語でも訂正して24bitの符号語を返す．
+///
+/// * `r`: 受信語
+/// * `form`: 受信語の形式
+/// * return: 訂正した24bitの符号語．拡張ゴレイ符号で4bit誤りの場合はNone．
+#[inline]
+pub fn ecc_any(r: u32, form: Form) -> Option<u32> {
+    match form {
+        Form::Perfect  => Some( extend(ecc(r)) ),
+        Form::Extended => super::ecc(r & 0xFFFFFF),
+    }
+}
+
+#[test]
+fn test_golay23() {
+    let data = 0b011100101001;
+    let code24 = super::encode(data);
+    let code23 = encode(data);
+    assert_eq!(extend(code23), code24);
+    assert_eq!(puncture(code24), code23);
+    assert_eq!(decode(code23), data);
+
+    // 3bitまでの誤りは全て訂正できる
+    for i in 0..23 {
+        for j in 0..23 {
+            for k in 0..23 {
+                let e = (1 << i) | (1 << j) | (1 << k);
+                assert_eq!(ecc(code23 ^ e), code23);
+                assert_eq!(ecc_any(code23 ^ e, Form::Perfect), Some(code24));
+            }
+        }
+    }
+}
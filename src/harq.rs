@@ -0,0 +1,192 @@
+//! ハイブリッドARQ（インクリメンタル冗長）
+//!
+//! 送信側はまず符号語から一部のパリティビットを除いたもの（データ12bitだけでも良い）を送り，
+//! NACKが返ってきたら残りのパリティビットを送る．
+//! 受信側は2回分を合わせて24bitの受信語にしてから訂正する．
+//!
+//! 1回目に除くビットが7bit以下なら，受信側は除かれたビットを消失として1回目だけで訂正を試みる．
+//! 8bit以上除いた場合は1回目では誤りを確かめられないので，常にNACKを返す．
+
+use super::puncture::{compress, expand, MAX_PUNCTURED};
+
+/// 1回目と2回目の送信の分け方
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scheme {
+    mask: u32,  // 1回目に除くビット位置（符号語の下位12bit）
+}
+
+impl Scheme {
+    /// 1回目はデータ12bitだけを送る．
+    pub const DATA_ONLY: Scheme = Scheme{ mask: 0xFFF };
+
+    /// * `mask`: 1回目に除くパリティビットの位置（下位12bit）
+    /// * return: 上位4bitが立っていればNone．
+    pub fn new(mask: u16) -> Option<Self> {
+        if mask > 0xFFF {
+            return None;
+        }
+        Some( Self{ mask: mask as u32 } )
+    }
+
+    /// 1回目の送信のビット数
+    #[inline]
+    pub fn first_bits(&self) -> u32 {
+        24 - self.mask.count_ones()
+    }
+
+    /// 2回目の送信のビット数
+    #[inline]
+    pub fn second_bits(&self) -> u32 {
+        self.mask.count_ones()
+    }
+
+    #[inline]
+    fn keep(&self) -> u32 {
+        !self.mask & 0xFFFFFF
+    }
+}
+
+/// 送信側の状態
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxState {
+    /// 送るものがない
+    Idle,
+    /// 1回目の応答待ち
+    WaitFirst,
+    /// 2回目の応答待ち
+    WaitSecond,
+}
+
+/// 送信側
+#[derive(Clone, Copy, Debug)]
+pub struct HarqTx {
+    scheme: Scheme,
+    code: u32,
+    state: TxState,
+}
+
+impl HarqTx {
+    pub fn new(scheme: Scheme) -> Self {
+        Self{ scheme, code: 0, state: TxState::Idle }
+    }
+
+    #[inline]
+    pub fn state(&self) -> TxState {
+        self.state
+    }
+
+    /// 12bitのデータの送信を始める．
+    ///
+    /// * return: 1回目に送るビット（下位`first_bits()`bit）
+    pub fn start(&mut self, data: u16) -> u32 {
+        self.code = super::encode(data);
+        self.state = TxState::WaitFirst;
+        compress(self.code, self.scheme.keep())
+    }
+
+    /// ACKを受け取った．
+    #[inline]
+    pub fn on_ack(&mut self) {
+        self.state = TxState::Idle;
+    }
+
+    /// NACKを受け取った．
+    ///
+    /// * return: 1回目の後なら2回目に送るビット（下位`second_bits()`bit）．
+    ///   2回目の後なら送るものが残っていないのでNone（諦めてIdleに戻る）．
+    pub fn on_nack(&mut self) -> Option<u32> {
+        match self.state {
+            TxState::WaitFirst => {
+                self.state = TxState::WaitSecond;
+                Some( compress(self.code, self.scheme.mask) )
+            },
+            _ => {
+                self.state = TxState::Idle;
+                None
+            },
+        }
+    }
+}
+
+/// 受信側から返す応答
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feedback {
+    /// 受信できた（データ）
+    Ack(u16),
+    /// 残りのパリティを要求する
+    Nack,
+    /// 全て受け取っても訂正できなかった
+    Fail,
+}
+
+/// 受信側
+#[derive(Clone, Copy, Debug)]
+pub struct HarqRx {
+    scheme: Scheme,
+    first: u32,  // 1回目の受信語（24bitの並び）
+}
+
+impl HarqRx {
+    pub fn new(scheme: Scheme) -> Self {
+        Self{ scheme, first: 0 }
+    }
+
+    /// 1回目の受信
+    ///
+    /// * `r`: 受信したビット（下位`first_bits()`bit）
+    pub fn receive_first(&mut self, r: u32) -> Feedback {
+        self.first = expand(r, self.scheme.keep());
+        if self.scheme.second_bits() > MAX_PUNCTURED {
+            return Feedback::Nack;
+        }
+        match super::ecc_erasure(self.first, self.scheme.mask) {
+            Some(code) => Feedback::Ack( super::decode(code) ),
+            None => Feedback::Nack,
+        }
+    }
+
+    /// 2回目の受信．1回目と合わせて訂正する．
+    ///
+    /// * `r`: 受信したビット（下位`second_bits()`bit）
+    pub fn receive_second(&mut self, r: u32) -> Feedback {
+        let word = self.first | expand(r, self.scheme.mask);
+        match super::ecc(word) {
+            Some(code) => Feedback::Ack( super::decode(code) ),
+            None => Feedback::Fail,
+        }
+    }
+
+    /// 1回目の受信語のデータ部分（確かめていない）
+    #[inline]
+    pub fn tentative(&self) -> u16 {
+        super::decode(self.first)
+    }
+}
+
+#[test]
+fn test_harq() {
+    let data = 0b1001_0110_0011;
+
+    // データだけ先に送る
+    let mut tx = HarqTx::new(Scheme::DATA_ONLY);
+    let mut rx = HarqRx::new(Scheme::DATA_ONLY);
+    let first = tx.start(data);
+    assert_eq!(first, data as u32);
+    assert_eq!(rx.receive_first(first ^ 0b1000_0000_0001), Feedback::Nack);
+    let second = tx.on_nack().unwrap();
+    assert_eq!(tx.state(), TxState::WaitSecond);
+    assert_eq!(rx.receive_second(second ^ 0b100), Feedback::Ack(data));
+    tx.on_ack();
+    assert_eq!(tx.state(), TxState::Idle);
+
+    // パリティを4bit除いて送る．1bit誤りなら1回目で訂正できる．
+    let scheme = Scheme::new(0b1000_0100_0011).unwrap();
+    let mut tx = HarqTx::new(scheme);
+    let mut rx = HarqRx::new(scheme);
+    let first = tx.start(data);
+    assert_eq!(rx.receive_first(first ^ 0x40), Feedback::Ack(data));
+    assert_eq!(rx.receive_first(first ^ 0x41), Feedback::Nack);
+    let second = tx.on_nack().unwrap();
+    assert_eq!(rx.receive_second(second), Feedback::Ack(data));
+    assert_eq!(tx.on_nack(), None);
+}
@@ -0,0 +1,422 @@
+//! ホスト環境向けの機能（`std`フィーチャ）
+//!
+//! OSのある環境で毎回書くことになる繋ぎのコードをまとめる．
+//! * エラー型の`std::error::Error`の実装
+//! * `Write`と`Read`を包んで`protect`と同じ形式で符号化・復号する`ProtectWriter`，`RecoverReader`
+//! * スレッドに分けた一括処理（`encode_parallel`，`ecc_parallel`）
+//! * `Vec`を返す`protect_vec`，`recover_vec`
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::thread;
+use std::vec;
+use std::vec::Vec;
+
+use crate::protect::{protected_len, RawReader, RawWriter};
+
+macro_rules! impl_error {
+    ($t:ty, $self_:ident => $msg:expr) => {
+        impl fmt::Display for $t {
+            fn fmt(&$self_, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str($msg)
+            }
+        }
+
+        impl Error for $t {}
+    };
+}
+
+impl_error!(crate::Detected, self => "uncorrectable error detected");
+impl_error!(crate::in_place::Uncorrectable, self => "uncorrectable element");
+impl_error!(crate::codec::MatrixError, self => match self {
+    Self::Rank => "generator matrix is not full rank",
+    Self::Distance => "minimum distance is not 8",
+});
+impl_error!(crate::codeword::HexError, self => match self {
+    Self::Empty => "no hex digits",
+    Self::InvalidDigit => "invalid hex digit",
+    Self::TooLong => "too many hex digits",
+});
+impl_error!(crate::armor::ArmorError, self => match self {
+    Self::InvalidLength => "armor must be 4 characters",
+    Self::InvalidChar => "invalid armor character",
+});
+impl_error!(crate::text::TextError, self => match self {
+    Self::InvalidChar => "invalid character",
+    Self::InvalidLength => "invalid length",
+    Self::BufferTooSmall => "buffer too small",
+    Self::Uncorrectable => "uncorrectable codeword",
+});
+impl_error!(crate::crc4::Crc4Error, self => match self {
+    Self::Detected => "uncorrectable codeword",
+    Self::Crc => "CRC mismatch",
+});
+impl_error!(crate::can::CanError, self => match self {
+    Self::InvalidLength => "invalid frame length",
+    Self::TooLong => "payload too long",
+    Self::Uncorrectable => "uncorrectable codeword",
+});
+impl_error!(crate::lora::LoraError, self => match self {
+    Self::TooLong => "payload too long",
+    Self::BufferTooSmall => "buffer too small",
+    Self::Malformed => "malformed packet",
+    Self::Header => "uncorrectable header",
+});
+impl_error!(crate::beacon::BeaconError, self => match self {
+    Self::InvalidCallsign => "invalid callsign",
+    Self::TooManyFields => "too many fields",
+    Self::BufferTooSmall => "buffer too small",
+    Self::Malformed => "malformed beacon",
+    Self::Uncorrectable => "uncorrectable codeword",
+});
+impl_error!(crate::journal::JournalError, self => match self {
+    Self::TooLong => "entry too long",
+    Self::Full => "journal full",
+});
+impl_error!(crate::boot::MetaError, self => match self {
+    Self::InvalidLength => "invalid metadata length",
+    Self::Unrecoverable => "no recoverable metadata copy",
+    Self::Conflict => "metadata copies disagree",
+});
+impl_error!(crate::selftest::SelfTestError, self => match self {
+    Self::Table => "matrix tables corrupted",
+    Self::Encode => "encoder self-test failed",
+    Self::Correct => "correction self-test failed",
+    Self::Detect => "detection self-test failed",
+});
+impl_error!(crate::ring::RingError, self => match self {
+    Self::Full => "ring buffer full",
+    Self::Index => "ring buffer index corrupted",
+});
+impl_error!(crate::wire::WireError, self => match self {
+    Self::BufferTooSmall => "buffer too small",
+    Self::UnexpectedEnd => "unexpected end of message",
+    Self::Overflow => "varint overflow",
+    Self::TooManyCodewords => "too many codewords",
+    Self::InvalidCodeword => "codeword exceeds 24 bits",
+});
+impl_error!(crate::channel_map::MapError, self => match self {
+    Self::OutOfRange => "bit position out of range",
+    Self::Duplicate => "duplicate bit position",
+});
+impl_error!(crate::pipeline::PipelineError, self => match self {
+    Self::TooLong => "payload too long",
+    Self::InvalidLength => "invalid frame length",
+    Self::Uncorrectable => "uncorrectable codeword",
+    Self::Empty => "no payload in flight",
+    Self::Disconnected => "worker thread stopped",
+});
+
+impl fmt::Display for crate::region::RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfRange => f.write_str("out of range"),
+            Self::Misaligned => f.write_str("offset is not a multiple of 3"),
+            Self::Uncorrectable(offset) => write!(f, "uncorrectable codeword at offset {}", offset),
+        }
+    }
+}
+
+impl Error for crate::region::RegionError {}
+
+impl fmt::Display for crate::mirror::MirrorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutOfRange => f.write_str("stripe out of range"),
+            Self::Lost(n) => write!(f, "codeword {} lost in both copies", n),
+        }
+    }
+}
+
+impl Error for crate::mirror::MirrorError {}
+
+/// 書き込んだバイト列を符号化して`inner`に書き込む．
+///
+/// 最後に`finish`を呼んで，残りのビットを0で埋めて書き出すこと．
+///
+/// `inner`への書き込みが途中で失敗した場合は，それまでに受け取ったバイト数を返す．
+/// 書き出せなかった符号語は保持しておき，次の呼び出しで先に書き出す．
+pub struct ProtectWriter<W: Write> {
+    inner: W,
+    bits: u32,
+    n_bits: u32,
+    pending: [u8; 3],  // 書き出し待ちの符号語
+    n_pending: usize,  // pendingの残りバイト数
+}
+
+impl<W: Write> ProtectWriter<W> {
+    /// * `inner`: 符号化したバイト列の書き込み先
+    pub fn new(inner: W) -> Self {
+        Self{ inner, bits: 0, n_bits: 0, pending: [0; 3], n_pending: 0 }
+    }
+
+    /// 書き出し待ちの符号語を書き出す．
+    fn drain(&mut self) -> io::Result<()> {
+        while self.n_pending > 0 {
+            match self.inner.write(&self.pending[3 - self.n_pending..]) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(k) => self.n_pending -= k,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn put(&mut self, a: u16) -> io::Result<()> {
+        self.pending.copy_from_slice(&crate::encode(a).to_be_bytes()[1..]);
+        self.n_pending = 3;
+        self.drain()
+    }
+
+    /// 残りのビットを0で埋めて書き出し，`inner`を返す．
+    pub fn finish(mut self) -> io::Result<W> {
+        self.drain()?;
+        if self.n_bits > 0 {
+            let a = (self.bits << (12 - self.n_bits)) as u16 & 0xFFF;
+            self.n_bits = 0;
+            self.put(a)?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ProtectWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // 前回書き出せなかった分が残っていれば，何も受け取らずにエラーを返す
+        self.drain()?;
+        for (i, b) in buf.iter().enumerate() {
+            self.bits = (self.bits << 8) | *b as u32;
+            self.n_bits += 8;
+            if self.n_bits >= 12 {
+                self.n_bits -= 12;
+                let a = (self.bits >> self.n_bits) as u16 & 0xFFF;
+                if self.put(a).is_err() {
+                    // このバイトまでは符号語に入れたので受け取ったことにし，エラーは次の呼び出しで返す
+                    return Ok(i + 1);
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.drain()?;
+        self.inner.flush()
+    }
+}
+
+/// `inner`から符号語を読んで訂正し，元のバイト列として読み出す．
+///
+/// 訂正できない符号語は`io::ErrorKind::InvalidData`のエラーにする．
+/// 途中でエラーになった場合は，それまでに読み出したバイト数を返し，エラーは次の呼び出しで返す．
+/// 長さは記録していないので，元のデータが3で割って2余るバイト数だった場合は最後に0が1バイト付く．
+pub struct RecoverReader<R: Read> {
+    inner: R,
+    bits: u32,
+    n_bits: u32,
+    corrected: u64,
+    pending: Option<io::Error>,  // 読み出した分を返した後で報告するエラー
+}
+
+impl<R: Read> RecoverReader<R> {
+    /// * `inner`: 符号化したバイト列の読み出し元
+    pub fn new(inner: R) -> Self {
+        Self{ inner, bits: 0, n_bits: 0, corrected: 0, pending: None }
+    }
+
+    /// これまでに訂正したビット数
+    #[inline]
+    pub fn corrected(&self) -> u64 {
+        self.corrected
+    }
+
+    /// `inner`を返す．
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// 符号語を1つ読む．終わりならfalse．
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut c = [0u8; 3];
+        let mut n = 0;
+        while n < 3 {
+            match self.inner.read(&mut c[n..]) {
+                Ok(0) if n == 0 => return Ok(false),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(k) => n += k,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(e) => return Err(e),
+            }
+        }
+        let c = crate::ecc_verbose( u32::from_be_bytes([0, c[0], c[1], c[2]]) )
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, crate::Detected))?;
+        self.corrected += c.errors as u64;
+        self.bits = (self.bits << 12) | crate::decode(c.code) as u32;
+        self.n_bits += 12;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for RecoverReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(e) = self.pending.take() {
+            return Err(e);
+        }
+        let mut n = 0;
+        for b in buf.iter_mut() {
+            if self.n_bits < 8 {
+                match self.fill() {
+                    Ok(true) => (),
+                    Ok(false) => break,
+                    Err(e) if n > 0 => {
+                        self.pending = Some(e);
+                        break;
+                    },
+                    Err(e) => return Err(e),
+                }
+            }
+            self.n_bits -= 8;
+            *b = (self.bits >> self.n_bits) as u8;
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+/// バイト列を`protect`と同じ形式で符号化する．
+pub fn protect_vec(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; protected_len(data.len())];
+    let mut w = RawWriter::new(&mut out);
+    w.write(data);
+    w.finish();
+    out
+}
+
+/// `protect_vec`で符号化したバイト列を訂正して戻す．
+///
+/// * `len`: 元のデータのバイト数
+/// * return: （データ，訂正したビット数）
+pub fn recover_vec(protected: &[u8], len: usize) -> Result<(Vec<u8>, u32), crate::Detected> {
+    let mut out = vec![0u8; len];
+    let mut r = RawReader::new(protected);
+    r.read(&mut out);
+    if r.failed {
+        return Err(crate::Detected);
+    }
+    Ok( (out, r.corrected) )
+}
+
+/// 12bitのデータを`threads`個のスレッドに分けて符号化する．
+pub fn encode_parallel(data: &[u16], threads: usize) -> Vec<u32> {
+    let mut out = vec![0u32; data.len()];
+    let chunk = data.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|s| {
+        for (d, o) in data.chunks(chunk).zip(out.chunks_mut(chunk)) {
+            s.spawn(move || {
+                for (a, c) in d.iter().zip(o.iter_mut()) {
+                    *c = crate::encode(*a);
+                }
+            });
+        }
+    });
+    out
+}
+
+/// 受信語を`threads`個のスレッドに分けて訂正する．
+///
+/// 訂正できない語は受信語のまま残す．
+///
+/// * return: 訂正できなかった語の数
+pub fn ecc_parallel(words: &mut [u32], threads: usize) -> usize {
+    let chunk = words.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|s| {
+        let handles: Vec<_> = words.chunks_mut(chunk).map(|w| s.spawn(move || {
+            let mut failed = 0;
+            for r in w.iter_mut() {
+                match crate::ecc(*r) {
+                    Some(code) => *r = code,
+                    None => failed += 1,
+                }
+            }
+            failed
+        })).collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    })
+}
+
+#[test]
+fn test_hosted() {
+    use std::string::ToString;
+
+    let e: &dyn Error = &crate::region::RegionError::Uncorrectable(9);
+    assert_eq!(e.to_string(), "uncorrectable codeword at offset 9");
+    assert_eq!(crate::text::TextError::BufferTooSmall.to_string(), "buffer too small");
+
+    let data: Vec<u8> = (0..100u8).map(|i| i.wrapping_mul(37)).collect();
+    let mut protected = protect_vec(&data);
+    let mut w = ProtectWriter::new(Vec::new());
+    w.write_all(&data[..33]).unwrap();
+    w.write_all(&data[33..]).unwrap();
+    assert_eq!(w.finish().unwrap(), protected);
+
+    protected[7] ^= 0x44;
+    assert_eq!(recover_vec(&protected, data.len()), Ok((data.clone(), 2)));
+    let mut r = RecoverReader::new(&protected[..]);
+    let mut back = Vec::new();
+    r.read_to_end(&mut back).unwrap();
+    assert_eq!(back, data);
+    assert_eq!(r.corrected(), 2);
+
+    protected[10] ^= 0x0F;
+    protected[11] ^= 0xF0;
+    let err = RecoverReader::new(&protected[..]).read_to_end(&mut back).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // 訂正できない符号語の前までを返し，エラーは次の呼び出しで返す
+    let mut r = RecoverReader::new(&protected[..]);
+    let mut buf = [0u8; 16];
+    assert_eq!(r.read(&mut buf).unwrap(), 4);
+    assert_eq!(buf[..4], data[..4]);
+    assert_eq!(r.read(&mut buf).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    /// 決めたバイト数だけ書き込めて，その後は失敗する書き込み先
+    struct Limited {
+        out: Vec<u8>,
+        room: usize,
+    }
+
+    impl Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.room == 0 {
+                return Err(io::ErrorKind::Other.into());
+            }
+            let n = buf.len().min(self.room);
+            self.out.extend_from_slice(&buf[..n]);
+            self.room -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // 書き込みが途中で失敗したら，受け取った分だけを返す．やり直しても重複しない．
+    let mut w = ProtectWriter::new(Limited{ out: Vec::new(), room: 4 });
+    assert_eq!(w.write(&data[..9]).unwrap(), 3);
+    assert_eq!(w.write(&data[3..9]).unwrap_err().kind(), io::ErrorKind::Other);
+    w.inner.room = usize::MAX;
+    w.write_all(&data[3..]).unwrap();
+    assert_eq!(w.finish().unwrap().out, protect_vec(&data));
+
+    let values: Vec<u16> = (0..1000).map(|i| (i * 7) as u16 & 0xFFF).collect();
+    let mut words = encode_parallel(&values, 4);
+    assert!(words.iter().zip(values.iter()).all(|(c, a)| *c == crate::encode(*a)));
+    words[3] ^= 0x000101;
+    words[500] ^= 0x00000F;
+    assert_eq!(ecc_parallel(&mut words, 3), 1);
+    assert_eq!(words[3], crate::encode(values[3]));
+}
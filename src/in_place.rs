@@ -0,0 +1,64 @@
+//! `u32`の配列をその場で符号化・訂正する
+//!
+//! 各要素の下位12bitを値として扱い，同じ要素に24bitの符号語を書き込む．
+//! 既存のファームウェアのデータ構造を，メモリ配置の幅を変えずに保護するためのもの．
+
+/// 訂正できない要素があった
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Uncorrectable {
+    /// 最初に訂正できなかった要素の添字
+    pub first: usize,
+    /// 訂正できなかった要素の数
+    pub count: usize,
+}
+
+/// 各要素の下位12bitを符号語に置き換える．上位20bitは無視する．
+pub fn protect(words: &mut [u32]) {
+    for w in words.iter_mut() {
+        *w = super::encode(*w as u16);
+    }
+}
+
+/// 各要素を訂正して12bitの値に戻す．
+///
+/// 訂正できなかった要素は受信語のまま残し，残りの要素の処理を続ける．
+///
+/// * return: 訂正したビット数の合計
+pub fn recover(words: &mut [u32]) -> Result<usize, Uncorrectable> {
+    let mut corrected = 0;
+    let mut failed: Option<Uncorrectable> = None;
+    for (i, w) in words.iter_mut().enumerate() {
+        match super::ecc_verbose(*w) {
+            Some(c) => {
+                corrected += c.errors as usize;
+                *w = super::decode(c.code) as u32;
+            },
+            None => match failed.as_mut() {
+                Some(f) => f.count += 1,
+                None => failed = Some( Uncorrectable{ first: i, count: 1 } ),
+            },
+        }
+    }
+    match failed {
+        Some(f) => Err(f),
+        None => Ok(corrected),
+    }
+}
+
+#[test]
+fn test_in_place() {
+    let mut words = [0x123, 0xFFFF_FABC, 0x000, 0xFFF];
+    protect(&mut words);
+    assert_eq!(words[1], super::encode(0xABC));
+    words[0] ^= 0x800001;
+    words[3] ^= 0x10;
+    assert_eq!(recover(&mut words), Ok(3));
+    assert_eq!(words, [0x123, 0xABC, 0x000, 0xFFF]);
+
+    protect(&mut words);
+    words[1] ^= 0xF;
+    words[2] ^= 0xF0;
+    assert_eq!(recover(&mut words), Err(Uncorrectable{ first: 1, count: 2 }));
+    assert_eq!(words[0], 0x123);
+    assert_eq!(words[2], 0xF0);
+}
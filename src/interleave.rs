@@ -0,0 +1,66 @@
+//! ビットインターリーブ
+//!
+//! n個の符号語を1bitずつ順番に並べ，バースト誤りを各符号語に分散させる．
+//! 出力ビット列のk番目は，k % n 番目の符号語の上から k / n 番目のビットになる．
+//! 長さnのバースト誤りは各符号語に1bitずつしか入らない．
+//!
+//! ビット列はバイトの上位ビットから順に詰める．1符号語あたり3バイト．
+
+/// 符号語をインターリーブしてバイト列にする．
+///
+/// * `codes`: 符号語（下位24bit）
+/// * `out`: 出力先．`codes.len() * 3`バイト必要．
+/// * return: 書き込んだバイト数．`out`が足りなければNone．
+pub fn interleave(codes: &[u32], out: &mut [u8]) -> Option<usize> {
+    let n = codes.len();
+    let len = n * 3;
+    let out = out.get_mut(..len)?;
+    for byte in out.iter_mut() {
+        *byte = 0;
+    }
+    for k in 0..(n * 24) {
+        let bit = (codes[k % n] >> (23 - k / n)) & 1;
+        out[k / 8] |= (bit as u8) << (7 - k % 8);
+    }
+    Some(len)
+}
+
+/// インターリーブされたバイト列から受信語を取り出す．
+///
+/// * `bytes`: 受信したバイト列．`codes.len() * 3`バイト必要．
+/// * `codes`: 出力先
+/// * return: 取り出した受信語の数．`bytes`が足りなければNone．
+pub fn deinterleave(bytes: &[u8], codes: &mut [u32]) -> Option<usize> {
+    let n = codes.len();
+    let bytes = bytes.get(..n * 3)?;
+    for code in codes.iter_mut() {
+        *code = 0;
+    }
+    for k in 0..(n * 24) {
+        let bit = (bytes[k / 8] >> (7 - k % 8)) & 1;
+        codes[k % n] |= (bit as u32) << (23 - k / n);
+    }
+    Some(n)
+}
+
+#[test]
+fn test_interleave() {
+    let mut codes = [0u32; 5];
+    for (i, code) in codes.iter_mut().enumerate() {
+        *code = super::encode(0x123 * i as u16);
+    }
+    let mut bytes = [0u8; 15];
+    assert_eq!(interleave(&codes, &mut bytes), Some(15));
+
+    // 15bit（3bit×符号語5個）のバースト誤り
+    bytes[4] ^= 0b0000_0111;
+    bytes[5] ^= 0xFF;
+    bytes[6] ^= 0b1111_0000;
+
+    let mut rx = [0u32; 5];
+    assert_eq!(deinterleave(&bytes, &mut rx), Some(5));
+    for (r, code) in rx.iter().zip(codes.iter()) {
+        assert_eq!(super::ecc(*r), Some(*code));
+    }
+    assert_eq!(interleave(&codes, &mut [0u8; 14]), None);
+}
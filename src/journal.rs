@@ -0,0 +1,295 @@
+//! EEPROM/FRAM向けの追記型ジャーナル
+//!
+//! イベントや故障の記録を，電源断やセルの劣化に耐えるように追記していく．
+//! メモリは呼び出し側が`&mut [u8]`で渡す（FRAMをそのまま，またはEEPROMの内容をRAMに写したもの）．
+//!
+//! エントリの並び（全て3バイトの符号語単位）
+//! * ヘッダ: 上位4bitが`0xA`で下位8bitがペイロードのバイト数の語，シーケンス番号の語，チェックサムの語
+//! * ペイロード: `protect`と同じく12bitずつ符号化したもの（`protect::protected_len(len)`バイト）
+//!
+//! `Journal::open`は先頭からエントリをたどり，訂正した符号語を書き戻す．
+//! ペイロードが訂正できないかチェックサムが合わないエントリは飛ばす．
+//! ヘッダが壊れている場合は，符号語単位でずらしながら次のヘッダを探す．
+//! 書き込み途中で電源が切れたエントリもチェックサムが合わないので飛ばされる．
+
+use crate::protect::{protected_len, RawReader, RawWriter};
+
+/// ペイロードの最大バイト数
+pub const MAX_PAYLOAD: usize = 255;
+
+/// ヘッダのバイト数
+const HEADER_BYTES: usize = 9;
+
+/// ヘッダの1語目の上位4bit
+const TAG: u16 = 0xA;
+
+/// ジャーナルのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JournalError {
+    /// ペイロードが`MAX_PAYLOAD`を超えた
+    TooLong,
+    /// 空きが足りない
+    Full,
+}
+
+/// `Journal::open`で調べた結果
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Recovery {
+    /// 読めたエントリの数
+    pub entries: usize,
+    /// 壊れていて飛ばしたエントリの数（ヘッダが読めたもの）
+    pub damaged: usize,
+    /// 訂正して書き戻したビット数
+    pub repaired_bits: usize,
+    /// ヘッダを探すために読み飛ばしたバイト数
+    pub skipped_bytes: usize,
+}
+
+/// 読み出したエントリ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// シーケンス番号（12bit）
+    pub seq: u16,
+    len: usize,
+    data: [u8; MAX_PAYLOAD],
+}
+
+impl Entry {
+    /// ペイロード
+    #[inline]
+    pub fn payload(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+}
+
+/// 追記型ジャーナル
+pub struct Journal<'a> {
+    mem: &'a mut [u8],
+    end: usize,
+    next_seq: u16,
+}
+
+impl<'a> Journal<'a> {
+    /// メモリを全て0で埋めて，空のジャーナルにする．
+    pub fn format(mem: &'a mut [u8]) -> Self {
+        for b in mem.iter_mut() {
+            *b = 0;
+        }
+        Self{ mem, end: 0, next_seq: 0 }
+    }
+
+    /// 書き込まれているジャーナルを開く．
+    ///
+    /// 訂正できた誤りは書き戻す．追記は最後に見つかったエントリの後から行う．
+    pub fn open(mem: &'a mut [u8]) -> (Self, Recovery) {
+        let mut journal = Self{ mem, end: 0, next_seq: 0 };
+        let mut recovery = Recovery::default();
+        let mut pos = 0;
+        let mut last = None;
+        let mut skipped = 0;
+        let mut entry = Entry{ seq: 0, len: 0, data: [0; MAX_PAYLOAD] };
+        while let Some(step) = journal.parse(pos, last, &mut entry) {
+            match step {
+                Step::Entry(corrected) => {
+                    if corrected > 0 {
+                        journal.write_entry(pos, entry.seq, entry.payload());
+                        recovery.repaired_bits += corrected;
+                    }
+                    recovery.entries += 1;
+                },
+                Step::Damaged => recovery.damaged += 1,
+                Step::Skip => {
+                    skipped += 3;
+                    pos += 3;
+                    continue;
+                },
+            }
+            // 最後のエントリの後ろの空きは数えない
+            recovery.skipped_bytes += skipped;
+            skipped = 0;
+            let (seq, len) = (entry.seq, entry.len);
+            last = Some(seq);
+            pos += entry_len(len);
+            journal.end = pos;
+            journal.next_seq = (seq + 1) & 0xFFF;
+        }
+        (journal, recovery)
+    }
+
+    /// エントリを追記する．
+    ///
+    /// * return: 付けたシーケンス番号
+    pub fn append(&mut self, payload: &[u8]) -> Result<u16, JournalError> {
+        if payload.len() > MAX_PAYLOAD {
+            return Err(JournalError::TooLong);
+        }
+        if self.end + entry_len(payload.len()) > self.mem.len() {
+            return Err(JournalError::Full);
+        }
+        let seq = self.next_seq;
+        self.write_entry(self.end, seq, payload);
+        self.end += entry_len(payload.len());
+        self.next_seq = (seq + 1) & 0xFFF;
+        Ok(seq)
+    }
+
+    /// 使っているバイト数
+    #[inline]
+    pub fn used(&self) -> usize {
+        self.end
+    }
+
+    /// 空きのバイト数
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.mem.len() - self.end
+    }
+
+    /// 読めるエントリを古い順に返す．壊れたエントリは飛ばす．
+    pub fn entries(&self) -> Entries<'_, 'a> {
+        Entries{ journal: self, pos: 0, last: None }
+    }
+
+    fn write_entry(&mut self, pos: usize, seq: u16, payload: &[u8]) {
+        let header = [(TAG << 8) | payload.len() as u16, seq, checksum(payload)];
+        for (i, a) in header.iter().enumerate() {
+            let code = crate::encode(*a);
+            self.mem[pos + 3 * i..pos + 3 * i + 3].copy_from_slice(&code.to_be_bytes()[1..]);
+        }
+        let start = pos + HEADER_BYTES;
+        let mut w = RawWriter::new(&mut self.mem[start..start + protected_len(payload.len())]);
+        w.write(payload);
+        w.finish();
+    }
+
+    /// posから1つ読む．メモリの終わりならNone．
+    ///
+    /// ヘッダが読めた場合は`entry`のシーケンス番号と長さを書き換え，読めたペイロードも書き込む．
+    fn parse(&self, pos: usize, last: Option<u16>, entry: &mut Entry) -> Option<Step> {
+        if pos + HEADER_BYTES > self.mem.len() {
+            return None;
+        }
+        let mut header = [0u16; 3];
+        let mut corrected = 0;
+        for (i, h) in header.iter_mut().enumerate() {
+            let c = &self.mem[pos + 3 * i..pos + 3 * i + 3];
+            match crate::ecc_verbose( u32::from_be_bytes([0, c[0], c[1], c[2]]) ) {
+                Some(c) => {
+                    *h = crate::decode(c.code);
+                    corrected += c.errors as usize;
+                },
+                None => return Some(Step::Skip),
+            }
+        }
+        let len = (header[0] & 0xFF) as usize;
+        let seq = header[1];
+        let in_order = match last {
+            Some(l) => (1..=2048).contains(&(seq.wrapping_sub(l) & 0xFFF)),
+            None => true,
+        };
+        if header[0] >> 8 != TAG || !in_order || pos + entry_len(len) > self.mem.len() {
+            return Some(Step::Skip);
+        }
+
+        let start = pos + HEADER_BYTES;
+        let mut r = RawReader::new(&self.mem[start..start + protected_len(len)]);
+        entry.seq = seq;
+        entry.len = len;
+        r.read(&mut entry.data[..len]);
+        if r.failed || checksum(entry.payload()) != header[2] {
+            return Some(Step::Damaged);
+        }
+        Some( Step::Entry(corrected + r.corrected as usize) )
+    }
+}
+
+/// エントリを読んだ結果
+enum Step {
+    /// 読めたエントリ（訂正したビット数）
+    Entry(usize),
+    /// ヘッダは読めたがペイロードが壊れている
+    Damaged,
+    /// ヘッダが無い
+    Skip,
+}
+
+/// エントリのイテレータ
+pub struct Entries<'j, 'a> {
+    journal: &'j Journal<'a>,
+    pos: usize,
+    last: Option<u16>,
+}
+
+impl<'j, 'a> Iterator for Entries<'j, 'a> {
+    type Item = Entry;
+
+    fn next(&mut self) -> Option<Entry> {
+        let mut entry = Entry{ seq: 0, len: 0, data: [0; MAX_PAYLOAD] };
+        while self.pos < self.journal.end {
+            let step = self.journal.parse(self.pos, self.last, &mut entry)?;
+            if let Step::Skip = step {
+                self.pos += 3;
+                continue;
+            }
+            self.last = Some(entry.seq);
+            self.pos += entry_len(entry.len);
+            if let Step::Entry(_) = step {
+                return Some(entry);
+            }
+        }
+        None
+    }
+}
+
+/// エントリ全体のバイト数
+#[inline]
+fn entry_len(len: usize) -> usize {
+    HEADER_BYTES + protected_len(len)
+}
+
+/// ペイロードの12bitのチェックサム（Fletcher方式で，6bitずつの2つの和）
+fn checksum(payload: &[u8]) -> u16 {
+    let mut a = 0u16;
+    let mut b = 0u16;
+    for x in payload {
+        a = (a + *x as u16) % 63;
+        b = (b + a) % 63;
+    }
+    (b << 6) | a
+}
+
+#[test]
+fn test_journal() {
+    let mut mem = [0u8; 128];
+    let mut j = Journal::format(&mut mem);
+    assert_eq!(j.append(b"boot"), Ok(0));
+    assert_eq!(j.append(b"fault 42"), Ok(1));
+    assert_eq!(j.append(b""), Ok(2));
+    assert_eq!(j.append(b"watchdog"), Ok(3));
+    assert_eq!(j.append(&[0; 256]), Err(JournalError::TooLong));
+    assert_eq!(j.append(&[0; 100]), Err(JournalError::Full));
+    let used = j.used();
+    assert_eq!(used, 4 * 9 + 9 + 18 + 18);
+
+    // 誤りの注入: 1番目のヘッダとペイロードに訂正できる誤り，2番目のペイロードに訂正できない誤り
+    mem[1] ^= 0x81;
+    mem[9] ^= 0x07;
+    mem[18 + 9] ^= 0x0F;
+    // 3番目のヘッダを壊す
+    mem[45] ^= 0xF0;
+
+    let (mut j, rec) = Journal::open(&mut mem);
+    assert_eq!(rec, Recovery{ entries: 2, damaged: 1, repaired_bits: 5, skipped_bytes: 9 });
+    assert_eq!(j.used(), used);
+    let mut it = j.entries();
+    assert_eq!(it.next().unwrap().payload(), b"boot");
+    let e = it.next().unwrap();
+    assert_eq!((e.seq, e.payload()), (3, &b"watchdog"[..]));
+    assert!(it.next().is_none());
+
+    // 追記は最後のエントリの後から続ける
+    assert_eq!(j.append(b"x"), Ok(4));
+    let (j, rec) = Journal::open(&mut mem);
+    assert_eq!(rec.repaired_bits, 0);
+    assert_eq!(j.entries().count(), 3);
+}
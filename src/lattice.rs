@@ -0,0 +1,183 @@
+//! ゴレイ符号から作る24次元格子の量子化器
+//!
+//! * 構成A: 各座標を2で割った余りが符号語になる整数点 {x ∈ Z^24 : x mod 2 ∈ C}
+//! * リーチ格子: Conway–Sloane の標準形（整数座標，最小ノルム32）．
+//!   全ての座標が偶数の点 x（x/2 が構成Aの点で，Σx/2 ≡ 0 mod 4）と，
+//!   それを (-3, 1, 1, ..., 1) だけずらした全ての座標が奇数の点からなる．
+//!
+//! 最近点の探索は，座標ごとの2乗誤差を軟判定値とみなした最尤復号になる．
+//! 符号語4096個の全てを調べるので，結果は厳密な最近点．
+//! 座標の添字0が符号語の最上位ビット（bit 23）に対応する．
+
+/// 量子化の結果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantized {
+    /// 格子点の座標
+    pub point: [i32; 24],
+    /// 格子点に対応する符号語（構成Aでは各座標の偶奇，リーチ格子では座標を2で割った値の偶奇）
+    pub code: u32,
+    /// 入力との距離の2乗
+    pub distance: f32,
+}
+
+/// 小数点以下を切り捨てる（no_stdには`floor`が無いため）．
+fn floor(x: f32) -> i32 {
+    let t = x as i32;
+    if (t as f32) > x { t - 1 } else { t }
+}
+
+/// 偶奇がbの整数のうちyに最も近いもの
+#[inline]
+fn nearest_with_parity(y: f32, b: i32) -> i32 {
+    2 * floor((y - b as f32) / 2.0 + 0.5) + b
+}
+
+/// 構成Aの格子，または和の条件を付けた格子（構成B）の最近点を探す．
+///
+/// * `sum_mod4`: trueなら座標の和が4の倍数の点だけを探す
+fn nearest(y: &[f32; 24], sum_mod4: bool) -> Quantized {
+    // 座標ごとに，偶奇ごとの最も近い整数と誤差
+    let mut z = [[0i32; 2]; 24];
+    let mut cost = [[0.0f32; 2]; 24];
+    let mut penalty = [[0.0f32; 2]; 24];  // 2だけずらしたときの誤差の増分
+    for i in 0..24 {
+        for b in 0..2 {
+            let zi = nearest_with_parity(y[i], b as i32);
+            let d = y[i] - zi as f32;
+            let d2 = if d >= 0.0 { d - 2.0 } else { d + 2.0 };
+            z[i][b] = zi;
+            cost[i][b] = d * d;
+            penalty[i][b] = d2 * d2 - d * d;
+        }
+    }
+
+    let mut best = Quantized{ point: [0; 24], code: 0, distance: f32::INFINITY };
+    for data in 0..4096 {
+        let code = crate::encode(data);
+        let mut distance = 0.0;
+        let mut sum = 0;
+        let mut min_penalty = f32::INFINITY;
+        let mut min_i = 0;
+        for i in 0..24 {
+            let b = ((code >> (23 - i)) & 1) as usize;
+            distance += cost[i][b];
+            sum += z[i][b];
+            if penalty[i][b] < min_penalty {
+                min_penalty = penalty[i][b];
+                min_i = i;
+            }
+        }
+        // 符号語の重みは偶数なので，和は4で割って0か2余る
+        let fix = sum_mod4 && sum.rem_euclid(4) != 0;
+        if fix {
+            distance += min_penalty;
+        }
+        if distance < best.distance {
+            best.distance = distance;
+            best.code = code;
+            for i in 0..24 {
+                best.point[i] = z[i][((code >> (23 - i)) & 1) as usize];
+            }
+            if fix {
+                best.point[min_i] += if y[min_i] >= best.point[min_i] as f32 { 2 } else { -2 };
+            }
+        }
+    }
+    best
+}
+
+/// 構成Aの格子の最近点を求める．
+pub fn construction_a(y: &[f32; 24]) -> Quantized {
+    nearest(y, false)
+}
+
+/// リーチ格子（整数座標，最小ノルム32）の最近点を求める．
+pub fn leech(y: &[f32; 24]) -> Quantized {
+    // 偶数の点: x = 2u，u は構成Bの点
+    let mut half = [0.0f32; 24];
+    for (h, v) in half.iter_mut().zip(y.iter()) {
+        *h = v / 2.0;
+    }
+    let mut even = nearest(&half, true);
+    for p in even.point.iter_mut() {
+        *p *= 2;
+    }
+    even.distance *= 4.0;
+
+    // 奇数の点: x = a + 2u，a = (-3, 1, ..., 1)
+    for (i, (h, v)) in half.iter_mut().zip(y.iter()).enumerate() {
+        *h = (v - shift(i) as f32) / 2.0;
+    }
+    let mut odd = nearest(&half, true);
+    for (i, p) in odd.point.iter_mut().enumerate() {
+        *p = 2 * *p + shift(i);
+    }
+    odd.distance *= 4.0;
+
+    if odd.distance < even.distance { odd } else { even }
+}
+
+/// 奇数の点のずらし量 (-3, 1, ..., 1)
+#[inline]
+fn shift(i: usize) -> i32 {
+    if i == 0 { -3 } else { 1 }
+}
+
+/// 点がリーチ格子に入っているか確かめる．
+pub fn is_leech(x: &[i32; 24]) -> bool {
+    let odd = x[0].rem_euclid(2) == 1;
+    let mut code = 0;
+    let mut sum = 0;
+    for (i, v) in x.iter().enumerate() {
+        let u = if odd { v - shift(i) } else { *v };
+        if u.rem_euclid(2) != 0 {
+            return false;
+        }
+        let u = u / 2;
+        code = (code << 1) | u.rem_euclid(2) as u32;
+        sum += u;
+    }
+    crate::syndrome(code) == 0 && sum.rem_euclid(4) == 0
+}
+
+#[test]
+fn test_lattice() {
+    // 構成A: 符号語の点の近く
+    let code = crate::encode(0x9C3);
+    let mut y = [0.0f32; 24];
+    for (i, v) in y.iter_mut().enumerate() {
+        *v = ((code >> (23 - i)) & 1) as f32 + 2.0 * (i % 3) as f32 + 0.3 * if i % 2 == 0 { 1.0 } else { -1.0 };
+    }
+    let q = construction_a(&y);
+    assert_eq!(q.code, code);
+    assert!((q.distance - 24.0 * 0.09).abs() < 1e-3);
+
+    // リーチ格子の最短ベクトルの例
+    let mut x = [1i32; 24];
+    x[0] = -3;
+    assert!(is_leech(&x));
+    assert_eq!(x.iter().map(|v| v * v).sum::<i32>(), 32);
+    let octad = crate::encode(0x800);
+    let mut x2 = [0i32; 24];
+    for (i, v) in x2.iter_mut().enumerate() {
+        if (octad >> (23 - i)) & 1 == 1 {
+            *v = 2;
+        }
+    }
+    x2[19] = -2;
+    x2[23] = -2;
+    assert!(is_leech(&x2));
+    let mut bad = [0i32; 24];
+    bad[0] = 4;
+    assert!(!is_leech(&bad));
+
+    for target in [x, x2].iter() {
+        let mut y = [0.0f32; 24];
+        for (i, v) in y.iter_mut().enumerate() {
+            *v = target[i] as f32 + if i % 4 == 0 { 0.7 } else { -0.4 };
+        }
+        let q = leech(&y);
+        assert_eq!(&q.point, target);
+        assert!(is_leech(&q.point));
+    }
+}
@@ -0,0 +1,279 @@
+//! ビット順と配置を型で区別する
+//!
+//! 符号語の扱いにはモジュールや相手の機器ごとに違う約束事がある．
+//! * ビット順: 上位ビットから送るか（`MsbFirst`），下位ビットから送るか（`LsbFirst`）
+//! * 配置: データが上位12bitにあるか（`DataHigh`，このクレートの形式），検査ビットが上位12bitにあるか（`ParityHigh`）
+//!
+//! `Word<L, O>`はこれらを型引数に持つ符号語で，約束事の違う語を取り違えるとコンパイルエラーになる．
+//! 変換は`Word::convert`で明示的に行う．このクレートの形式の語は`codeword::Codeword`．
+//!
+//! 同じ型引数を`uart::UartTx`，`uart::UartRx`，`text::encode_frame_as`などのバイト列にする部分と，
+//! `codec::Codec::to_word`にも渡せる．型引数を省くとこのクレートの形式（`DataHigh`，`MsbFirst`）になる．
+
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+
+/// ビット順
+pub trait BitOrder: private::Sealed {
+    /// 送信順に並べた24bit（bit 23を最初に送る）にする．
+    fn to_stream(code: u32) -> u32;
+
+    /// 送信順に並べた24bitから戻す．
+    fn from_stream(stream: u32) -> u32;
+
+    /// 3バイトにする．
+    fn to_bytes(code: u32) -> [u8; 3];
+
+    /// 3バイトから戻す．
+    fn from_bytes(bytes: [u8; 3]) -> u32;
+}
+
+/// 配置
+pub trait Layout: private::Sealed {
+    /// このクレートの形式（データが上位）の語から変換する．
+    fn from_native(code: u32) -> u32;
+
+    /// このクレートの形式（データが上位）の語に変換する．
+    fn to_native(word: u32) -> u32;
+}
+
+/// 上位ビットから送る．バイト列はビッグエンディアン．
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MsbFirst;
+
+/// 下位ビットから送る．バイト列はリトルエンディアン（LSBファーストのUARTでbit 0から送られる）．
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LsbFirst;
+
+/// データが上位12bit，検査ビットが下位12bit（このクレートの形式）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DataHigh;
+
+/// 検査ビットが上位12bit，データが下位12bit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ParityHigh;
+
+impl BitOrder for MsbFirst {
+    #[inline]
+    fn to_stream(code: u32) -> u32 {
+        code & 0xFFFFFF
+    }
+
+    #[inline]
+    fn from_stream(stream: u32) -> u32 {
+        stream & 0xFFFFFF
+    }
+
+    #[inline]
+    fn to_bytes(code: u32) -> [u8; 3] {
+        [(code >> 16) as u8, (code >> 8) as u8, code as u8]
+    }
+
+    #[inline]
+    fn from_bytes(bytes: [u8; 3]) -> u32 {
+        u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]])
+    }
+}
+
+impl BitOrder for LsbFirst {
+    #[inline]
+    fn to_stream(code: u32) -> u32 {
+        code.reverse_bits() >> 8
+    }
+
+    #[inline]
+    fn from_stream(stream: u32) -> u32 {
+        stream.reverse_bits() >> 8
+    }
+
+    #[inline]
+    fn to_bytes(code: u32) -> [u8; 3] {
+        [code as u8, (code >> 8) as u8, (code >> 16) as u8]
+    }
+
+    #[inline]
+    fn from_bytes(bytes: [u8; 3]) -> u32 {
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0])
+    }
+}
+
+impl Layout for DataHigh {
+    #[inline]
+    fn from_native(code: u32) -> u32 {
+        code & 0xFFFFFF
+    }
+
+    #[inline]
+    fn to_native(word: u32) -> u32 {
+        word & 0xFFFFFF
+    }
+}
+
+impl Layout for ParityHigh {
+    #[inline]
+    fn from_native(code: u32) -> u32 {
+        ((code & 0xFFF) << 12) | ((code >> 12) & 0xFFF)
+    }
+
+    #[inline]
+    fn to_native(word: u32) -> u32 {
+        Self::from_native(word)
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for super::MsbFirst {}
+    impl Sealed for super::LsbFirst {}
+    impl Sealed for super::DataHigh {}
+    impl Sealed for super::ParityHigh {}
+}
+
+/// 配置`L`とビット順`O`の約束事に従う符号語（または受信語）
+pub struct Word<L: Layout, O: BitOrder> {
+    raw: u32,
+    _marker: PhantomData<(L, O)>,
+}
+
+impl<L: Layout, O: BitOrder> Word<L, O> {
+    /// 配置`L`の24bitの語をそのまま包む．
+    #[inline]
+    pub fn from_raw(raw: u32) -> Self {
+        Self{ raw: raw & 0xFFFFFF, _marker: PhantomData }
+    }
+
+    /// 配置`L`の24bitの語
+    #[inline]
+    pub fn raw(&self) -> u32 {
+        self.raw
+    }
+
+    /// データ（下位12bit）を符号化する．
+    #[inline]
+    pub fn encode(data: u16) -> Self {
+        Self::from_raw( L::from_native(crate::encode(data)) )
+    }
+
+    /// 誤り訂正を行う．訂正できない場合はNone．
+    #[inline]
+    pub fn ecc(&self) -> Option<Self> {
+        crate::ecc( L::to_native(self.raw) ).map(|c| Self::from_raw( L::from_native(c) ))
+    }
+
+    /// 符号語からデータを取り出す．
+    #[inline]
+    pub fn decode(&self) -> u16 {
+        crate::decode( L::to_native(self.raw) )
+    }
+
+    /// ビット順`O`で3バイトにする．
+    #[inline]
+    pub fn to_bytes(&self) -> [u8; 3] {
+        O::to_bytes(self.raw)
+    }
+
+    /// ビット順`O`の3バイトから戻す．
+    #[inline]
+    pub fn from_bytes(bytes: [u8; 3]) -> Self {
+        Self::from_raw( O::from_bytes(bytes) )
+    }
+
+    /// 送信順に並べた24bit（bit 23を最初に送る）
+    #[inline]
+    pub fn to_stream(&self) -> u32 {
+        O::to_stream(self.raw)
+    }
+
+    /// 送信順に並べた24bitから戻す．
+    #[inline]
+    pub fn from_stream(stream: u32) -> Self {
+        Self::from_raw( O::from_stream(stream) )
+    }
+
+    /// 別の約束事の語に変換する．
+    #[inline]
+    pub fn convert<L2: Layout, O2: BitOrder>(&self) -> Word<L2, O2> {
+        Word::from_raw( L2::from_native(L::to_native(self.raw)) )
+    }
+}
+
+// 型引数にderiveの境界が付かないように手で実装する
+impl<L: Layout, O: BitOrder> Clone for Word<L, O> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<L: Layout, O: BitOrder> Copy for Word<L, O> {}
+
+impl<L: Layout, O: BitOrder> PartialEq for Word<L, O> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<L: Layout, O: BitOrder> Eq for Word<L, O> {}
+
+impl<L: Layout, O: BitOrder> Hash for Word<L, O> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state)
+    }
+}
+
+impl<L: Layout, O: BitOrder> Default for Word<L, O> {
+    #[inline]
+    fn default() -> Self {
+        Self::from_raw(0)
+    }
+}
+
+impl<L: Layout, O: BitOrder> fmt::Debug for Word<L, O> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Word({:#08X})", self.raw)
+    }
+}
+
+impl<L: Layout, O: BitOrder> From<u32> for Word<L, O> {
+    #[inline]
+    fn from(raw: u32) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
+impl<L: Layout, O: BitOrder> From<Word<L, O>> for u32 {
+    #[inline]
+    fn from(w: Word<L, O>) -> Self {
+        w.raw
+    }
+}
+
+#[test]
+fn test_layout() {
+    use crate::codeword::Codeword;
+
+    let data = 0xA5C;
+    let native = Codeword::encode(data);
+    assert_eq!(native.raw(), crate::encode(data));
+    assert_eq!(native.to_bytes(), crate::uart::to_bytes(native.raw()));
+
+    let other: Word<ParityHigh, LsbFirst> = native.convert();
+    assert_eq!(other.raw(), ((native.raw() & 0xFFF) << 12) | (native.raw() >> 12));
+    assert_eq!(other, Word::<ParityHigh, LsbFirst>::encode(data));
+    let bytes = other.to_bytes();
+    assert_eq!(bytes[0], other.raw() as u8);
+
+    // 受信側で誤りを訂正する
+    let mut rx = Word::<ParityHigh, LsbFirst>::from_bytes(bytes);
+    rx = Word::from_raw(rx.raw() ^ 0x800003);
+    assert_eq!(rx.ecc().map(|c| c.decode()), Some(data));
+    assert_eq!(rx.ecc().unwrap().convert::<DataHigh, MsbFirst>(), native);
+
+    // 送信順
+    assert_eq!(Word::<DataHigh, LsbFirst>::from_raw(1).to_stream(), 0x800000);
+    assert_eq!(Word::<DataHigh, LsbFirst>::from_stream(0x800000).raw(), 1);
+    assert_eq!(Codeword::from(0x123456).to_stream(), 0x123456);
+}
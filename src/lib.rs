@@ -1,11 +1,169 @@
 //! 拡張２元ゴレイ符号を実装
 //!
 //! 3bitまでのエラー訂正と4bitまでの誤り検出が可能．
+//!
+//! 誤り訂正は既定で有効な`correct`フィーチャに含まれる．
+//! `default-features = false`にすると符号化と検査（`encode`，`decode`，`check`，`syndrome_weight`）だけになり，
+//! 訂正を相手側で行う送信専用の機器やARQだけの機器でフラッシュを節約できる．
 
 #![no_std]
+// unsafeは`accel`フィーチャのSIMD（`accel`モジュール）だけに閉じ込める
+#![cfg_attr(not(feature = "accel"), forbid(unsafe_code))]
+#![cfg_attr(feature = "accel", deny(unsafe_code))]
+
+#[cfg(feature = "std")]
+extern crate std;
+
+// deriveマクロが生成するコードをクレート内のテストでも使えるようにする
+#[cfg(test)]
+extern crate self as golay_code;
+
+#[cfg(feature = "accel")]
+pub mod accel;
+#[cfg(feature = "correct")]
+pub mod adaptive;
+#[cfg(feature = "correct")]
+pub mod armor;
+#[cfg(feature = "correct")]
+pub mod arq;
+#[cfg(feature = "async")]
+pub mod asynch;
+#[cfg(feature = "correct")]
+pub mod atomic;
+#[cfg(feature = "correct")]
+pub mod beacon;
+#[cfg(feature = "correct")]
+pub mod bit_serial;
+#[cfg(feature = "correct")]
+pub mod bits;
+#[cfg(feature = "correct")]
+pub mod bmatrix;
+#[cfg(feature = "correct")]
+pub mod boot;
+#[cfg(feature = "correct")]
+pub mod can;
+#[cfg(feature = "correct")]
+pub mod channel_map;
+#[cfg(feature = "correct")]
+pub mod chunked;
+#[cfg(feature = "correct")]
+pub mod codec;
+#[cfg(feature = "correct")]
+pub mod codeword;
+#[cfg(feature = "correct")]
+pub mod complement;
+#[cfg(feature = "correct")]
+pub mod corpus;
+#[cfg(feature = "correct")]
+pub mod coset;
+#[cfg(feature = "correct")]
+pub mod covering;
+#[cfg(feature = "correct")]
+pub mod crc4;
+#[cfg(feature = "correct")]
+pub mod cyclic;
+#[cfg(feature = "correct")]
+pub mod dc_balance;
+#[cfg(feature = "correct")]
+pub mod decoder;
+#[cfg(feature = "correct")]
+pub mod events;
+#[cfg(all(feature = "correct", feature = "std"))]
+pub mod file;
+#[cfg(feature = "storage")]
+pub mod flash;
+#[cfg(feature = "tokio")]
+pub mod framed;
+#[cfg(feature = "correct")]
+pub mod fsk4;
+#[cfg(feature = "correct")]
+pub mod gf2;
+#[cfg(feature = "correct")]
+pub mod golay23;
+#[cfg(feature = "correct")]
+pub mod harq;
+#[cfg(all(feature = "correct", feature = "std"))]
+pub mod hosted;
+#[cfg(feature = "correct")]
+pub mod in_place;
+#[cfg(feature = "correct")]
+pub mod interleave;
+#[cfg(feature = "correct")]
+pub mod journal;
+#[cfg(feature = "correct")]
+pub mod lattice;
+#[cfg(feature = "correct")]
+pub mod layout;
+#[cfg(feature = "correct")]
+pub mod linear;
+#[cfg(all(feature = "correct", feature = "std"))]
+pub mod link;
+#[cfg(feature = "correct")]
+pub mod lora;
+#[cfg(feature = "correct")]
+pub mod metrics;
+#[cfg(feature = "correct")]
+pub mod mirror;
+#[cfg(feature = "correct")]
+pub mod miscorrection;
+#[cfg(feature = "correct")]
+pub mod mog;
+#[cfg(feature = "correct")]
+pub mod monitor;
+#[cfg(feature = "correct")]
+pub mod nor;
+#[cfg(feature = "correct")]
+pub mod otp;
+#[cfg(all(feature = "correct", feature = "std"))]
+pub mod pipeline;
+#[cfg(feature = "correct")]
+pub mod product;
+#[cfg(feature = "profiling")]
+pub mod profile;
+#[cfg(feature = "correct")]
+pub mod protect;
+#[cfg(feature = "correct")]
+pub mod protected_var;
+#[cfg(feature = "correct")]
+pub mod puncture;
+#[cfg(feature = "correct")]
+pub mod qr;
+#[cfg(feature = "correct")]
+pub mod region;
+#[cfg(feature = "correct")]
+pub mod repetition;
+#[cfg(feature = "correct")]
+pub mod ring;
+#[cfg(feature = "correct")]
+pub mod rll;
+#[cfg(feature = "correct")]
+pub mod scrub;
+#[cfg(feature = "correct")]
+pub mod selftest;
+#[cfg(feature = "correct")]
+pub mod sequenced;
+#[cfg(feature = "correct")]
+pub mod soft;
+#[cfg(feature = "correct")]
+pub mod text;
+#[cfg(feature = "correct")]
+pub mod time_diversity;
+#[cfg(feature = "correct")]
+pub mod u12;
+#[cfg(feature = "correct")]
+pub mod u24;
+#[cfg(feature = "correct")]
+pub mod uart;
+#[cfg(feature = "vectors")]
+pub mod vectors;
+#[cfg(feature = "correct")]
+pub mod wire;
 
 /// 検査行列の転置 (24bit × 12bit)
-const H_T: [u32; 24] = [
+///
+/// 表が壊れていないか`selftest::verify_tables`で確かめられるように，
+/// 定数として埋め込まずにstaticとして1か所に置く．
+static H_T: [u32; 24] = [
     0b100111110001,
     0b010011111010,
     0b001001111101,
@@ -33,7 +191,7 @@ const H_T: [u32; 24] = [
 ];
 
 /// 生成行列 (12bit × 24bit)
-const G: [u32; 12] = [
+static G: [u32; 12] = [
     0b100000000000_100111110001,
     0b010000000000_010011111010,
     0b001000000000_001001111101,
@@ -48,11 +206,37 @@ const G: [u32; 12] = [
     0b000000000001_101010101011,
 ];
 
+// 表の書き換えや写し間違いがあればビルドを失敗させる
+const _: () = {
+    let mut i = 0;
+    while i < 12 {
+        // G = [I | B] で，Bの行がH_Tの上半分と同じ
+        assert!(G[i] >> 12 == 0x800 >> i, "G: identity block is broken");
+        assert!(G[i] & 0xFFF == H_T[i], "G: parity block does not match H_T");
+        // H_Tの下半分は単位行列
+        assert!(H_T[12 + i] == 0x800 >> i, "H_T: identity block is broken");
+        // 生成行列の各行は重み8の符号語
+        assert!(G[i].count_ones() == 8, "G: row weight is not 8");
+
+        // G・H^T = 0
+        let mut s = 0;
+        let mut j = 0;
+        while j < 24 {
+            if (G[i] >> (23 - j)) & 1 == 1 {
+                s ^= H_T[j];
+            }
+            j += 1;
+        }
+        assert!(s == 0, "G * H^T != 0");
+        i += 1;
+    }
+};
+
 /// 12bitのデータを24bitの符合語に変換する．
-/// 
+///
 /// データは下位12bitに入れておく．
 /// 上位4bitは見ないので何でも良い．
-/// 
+///
 /// 変換後の符号語は下位24bitに入っている．
 #[inline]
 pub fn encode(a: u16) -> u32 {
@@ -68,17 +252,255 @@ pub fn encode(a: u16) -> u32 {
     code
 }
 
+/// 12bitのデータを保護する（`encode`と同じ）．
+///
+/// `recover`と対にして使う．
+#[inline]
+pub fn protect(data: u16) -> u32 {
+    encode(data)
+}
+
+/// 受信語を訂正してデータを取り出す．
+///
+/// `ecc`と`decode`をまとめて行う．
+///
+/// * return: データ（下位12bit）と訂正の詳細．訂正できない誤りを検出したらErr．
+#[cfg(feature = "correct")]
+#[inline]
+pub fn recover(r: u32) -> Result<(u16, Correction), Detected> {
+    let c = ecc_verbose(r).ok_or(Detected)?;
+    Ok( (decode(c.code), c) )
+}
+
+/// 訂正できない誤りを検出した
+#[cfg(feature = "correct")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Detected;
+
 /// 受信語のエラー検出と訂正を行う．
-/// 
+///
 /// * `r`: 受信した符号語（下位24bit）
 /// * return: `Option<u32>`
 ///     * `code`: 誤り訂正した受信語．
 ///     * 誤りを訂正できたらSome(code)，4bit誤りの場合はNoneを返す．
 ///     * 5bit以上のエラーではSome(code)を返す場合もあるが，正しく訂正できているわけではない．
 ///     * 4bit以上反転していてもエラービットが全て下位12bitにあれば元データは問題なく復号できる．
+///
+/// `ecc_result(r).codeword()`と同じ．
+#[cfg(feature = "correct")]
 #[inline]
 pub fn ecc(r: u32) -> Option<u32> {
-    // 1つめのシンドローム
+    ecc_result(r).codeword()
+}
+
+/// 受信語のエラー検出と訂正を行い，誤りの有無と訂正したビットを返す．
+///
+/// 訂正できる範囲は`ecc`と同じ．回線品質の監視で，何ビットどこを訂正したか記録するのに使う．
+/// `ecc`，`ecc_verbose`，`ecc_status`はこの結果を変換したもの．
+#[cfg(feature = "correct")]
+#[inline]
+pub fn ecc_result(r: u32) -> DecodeResult {
+    match error_pattern(r) {
+        Some(0) => DecodeResult::NoError{ codeword: r },
+        Some(e) => DecodeResult::Corrected{ bits: weight(e) as u8, error_mask: e, codeword: r ^ e },
+        None    => DecodeResult::Detected,
+    }
+}
+
+/// 誤り訂正の結果（訂正したビット位置を含む）
+#[cfg(feature = "correct")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeResult {
+    /// 誤りなし（シンドロームが0）
+    NoError {
+        /// 受信語（そのまま符号語）
+        codeword: u32,
+    },
+    /// 誤りを訂正した
+    Corrected {
+        /// 訂正したビット数（1〜3）
+        bits: u8,
+        /// 訂正したビット位置（受信語と同じ並び）
+        error_mask: u32,
+        /// 誤り訂正した受信語
+        codeword: u32,
+    },
+    /// 訂正できない誤りを検出した
+    Detected,
+}
+
+#[cfg(feature = "correct")]
+impl DecodeResult {
+    /// 訂正後の符号語．訂正できなかった場合はNone．
+    #[inline]
+    pub fn codeword(&self) -> Option<u32> {
+        match *self {
+            DecodeResult::NoError{ codeword } | DecodeResult::Corrected{ codeword, .. } => Some(codeword),
+            DecodeResult::Detected => None,
+        }
+    }
+
+    /// 訂正したビット数．誤りなしか訂正できなかった場合は0．
+    #[inline]
+    pub fn bits(&self) -> u8 {
+        match *self {
+            DecodeResult::Corrected{ bits, .. } => bits,
+            _ => 0,
+        }
+    }
+
+    /// 訂正したビット位置．誤りなしか訂正できなかった場合は0．
+    #[inline]
+    pub fn error_mask(&self) -> u32 {
+        match *self {
+            DecodeResult::Corrected{ error_mask, .. } => error_mask,
+            _ => 0,
+        }
+    }
+
+    /// 訂正できない誤りを検出したか．
+    #[inline]
+    pub fn is_detected(&self) -> bool {
+        *self == DecodeResult::Detected
+    }
+}
+
+#[cfg(feature = "correct")]
+impl From<DecodeResult> for Option<Correction> {
+    #[inline]
+    fn from(result: DecodeResult) -> Self {
+        let code = result.codeword()?;
+        Some( Correction{ code, errors: result.bits(), error_mask: result.error_mask() } )
+    }
+}
+
+#[cfg(feature = "correct")]
+impl From<DecodeResult> for Decode {
+    #[inline]
+    fn from(result: DecodeResult) -> Self {
+        match result {
+            DecodeResult::NoError{ codeword } => Decode::Clean(codeword),
+            DecodeResult::Corrected{ bits, codeword, .. } => Decode::Corrected(codeword, bits),
+            DecodeResult::Detected => Decode::Detected,
+        }
+    }
+}
+
+/// 受信語のエラー検出と訂正を行い，訂正の詳細を返す．
+///
+/// 訂正できる範囲は`ecc`と同じ．4bit誤りの場合はNoneを返す．
+#[cfg(feature = "correct")]
+#[inline]
+pub fn ecc_verbose(r: u32) -> Option<Correction> {
+    ecc_result(r).into()
+}
+
+/// 誤り訂正の詳細
+#[cfg(feature = "correct")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Correction {
+    /// 誤り訂正した受信語
+    pub code: u32,
+    /// 訂正したビット数
+    pub errors: u8,
+    /// 訂正したビット位置（受信語と同じ並び）
+    pub error_mask: u32,
+}
+
+#[cfg(feature = "correct")]
+impl Correction {
+    /// 訂正したビット位置を下位ビットから順に返すイテレータ．
+    ///
+    /// ビット位置は最下位ビットを0として数える（上位12bitのデータは12〜23）．
+    #[inline]
+    pub fn positions(&self) -> Positions {
+        Positions{ mask: self.error_mask }
+    }
+
+    /// 訂正したビットが全てパリティ側（下位12bit）にあればtrue．
+    ///
+    /// この場合データビットは一度も疑わしくなっていない．
+    /// 誤りなしの場合はfalse．
+    #[inline]
+    pub fn parity_only(&self) -> bool {
+        self.errors > 0 && (self.error_mask & 0xFFF000) == 0
+    }
+}
+
+/// 訂正したビット位置のイテレータ
+#[cfg(feature = "correct")]
+#[derive(Clone, Copy, Debug)]
+pub struct Positions {
+    mask: u32,
+}
+
+#[cfg(feature = "correct")]
+impl Iterator for Positions {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        if self.mask == 0 {
+            return None;
+        }
+        let pos = self.mask.trailing_zeros();
+        self.mask &= self.mask - 1;
+        Some(pos as u8)
+    }
+}
+
+/// 受信語のエラー検出と訂正を行い，誤りの有無を区別して返す．
+///
+/// 訂正できる範囲は`ecc`と同じ．
+#[cfg(feature = "correct")]
+#[inline]
+pub fn ecc_status(r: u32) -> Decode {
+    ecc_result(r).into()
+}
+
+/// 誤り訂正の結果
+#[cfg(feature = "correct")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decode {
+    /// 誤りなし（シンドロームが0）
+    Clean(u32),
+    /// 誤りを訂正した（訂正後の符号語，訂正したビット数）
+    Corrected(u32, u8),
+    /// 訂正できない誤りを検出した
+    Detected,
+}
+
+#[cfg(feature = "correct")]
+impl Decode {
+    /// 訂正後の符号語．訂正できなかった場合はNone．
+    #[inline]
+    pub fn code(&self) -> Option<u32> {
+        match *self {
+            Decode::Clean(code) | Decode::Corrected(code, _) => Some(code),
+            Decode::Detected => None,
+        }
+    }
+}
+
+/// 受信語が符号語ならtrue（誤りを検出しなければtrue）．
+///
+/// 訂正は行わない．
+#[inline]
+pub fn check(r: u32) -> bool {
+    syndrome(r) == 0
+}
+
+/// 受信語のシンドロームの重みを返す．
+///
+/// 誤りなしなら0．訂正できる語でも，伝送路の劣化の傾向を見るのに使える．
+#[inline]
+pub fn syndrome_weight(r: u32) -> u8 {
+    weight(syndrome(r)) as u8
+}
+
+/// 受信語のシンドロームを計算する（下位12bit）．
+#[inline]
+fn syndrome(r: u32) -> u32 {
     let mut s: u32 = 0;
     // rベクトルとH_T行列の積（加算はXOR）
     for (i, h_t_line) in H_T.iter().enumerate() {
@@ -86,22 +508,36 @@ pub fn ecc(r: u32) -> Option<u32> {
         let r_bit = ((r >> (23 - i)) & 1) * 0xFFF;
         s ^= r_bit & *h_t_line;
     }
+    s
+}
+
+/// 受信語の誤りパターンを推定する．
+///
+/// 4bit誤りの場合はNoneを返す．
+#[cfg(feature = "correct")]
+fn error_pattern(r: u32) -> Option<u32> {
+    syndrome_error_pattern( syndrome(r) )
+}
 
+/// シンドロームから誤りパターンを推定する．
+///
+/// 4bit誤りの場合はNoneを返す．
+#[cfg(feature = "correct")]
+fn syndrome_error_pattern(s: u32) -> Option<u32> {
     // シンドロームが0なら誤りなし（もしくは検出できない）．
     // weightの計算が少し重いのでここで返してしまう．
     if s == 0 {
-        return Some(r);
+        return Some(0);
     }
 
     if weight(s) <= 3 {
-        return Some(r ^ s);
+        return Some(s);
     } else {
         for (i, h_t_line) in H_T.iter().take(12).enumerate() {
             let tmp = s ^ *h_t_line;
             if weight(tmp) <= 2 {
-                let e = (0x800000 >> i) | tmp;
-                //let e = G[i] ^ s;  // こう書いても同じ
-                return Some(r ^ e);
+                //return Some(G[i] ^ s);  // こう書いても同じ
+                return Some( (0x800000 >> i) | tmp );
             }
         }
     }
@@ -113,13 +549,12 @@ pub fn ecc(r: u32) -> Option<u32> {
         sh ^= s_bit & *h_t_line;
     }
     if weight(sh) <= 3 {
-        return Some(r ^ (sh << 12));
+        return Some(sh << 12);
     } else {
         for (i, h_t_line) in H_T.iter().take(12).enumerate() {
             let tmp = sh ^ *h_t_line;
             if weight(tmp) <= 2 {
-                let e = (tmp << 12) | (0x800 >> i);
-                return Some(r ^ e);
+                return Some( (tmp << 12) | (0x800 >> i) );
             }
         }
     }
@@ -127,8 +562,51 @@ pub fn ecc(r: u32) -> Option<u32> {
     None  // 4bitエラー
 }
 
+/// 消失位置を考慮して受信語の誤り訂正を行う．
+///
+/// 物理層が信頼できないビットを示せる場合，消失として渡せば`ecc`より多くの誤りを訂正できる．
+/// 最小距離が8なので，2×誤り数 + 消失数 < 8 の範囲の誤りは必ず訂正できる．
+/// （消失なしで3bit，消失2bitで2bit，消失4bitで1bit，消失7bitで誤りなし）
+///
+/// * `r`: 受信語（下位24bit）
+/// * `erasure`: 消失ビットの位置（下位24bit）．消失位置の受信値は見ない．
+/// * return: `Option<u32>`
+///     * 訂正結果が 2×誤り数 + 消失数 < 8 を満たせば訂正後の符号語を返す．それ以外はNone．
+///     * 消失がなければ`ecc`と同じ．
+#[cfg(feature = "correct")]
+pub fn ecc_erasure(r: u32, erasure: u32) -> Option<u32> {
+    let r = r & 0xFFFFFF;
+    let erasure = erasure & 0xFFFFFF;
+    let n_erasure = weight(erasure);
+    if n_erasure == 0 {
+        return ecc(r);
+    }
+    if n_erasure >= 8 {
+        return None;
+    }
+
+    // 消失位置を全て0で埋めた語と全て1で埋めた語の両方を訂正し，
+    // 消失していない位置での誤りが少ない方を採用する．
+    // どちらか一方は誤りが 誤り数 + 消失数/2 以下になるので，3bit訂正で足りる．
+    let mut best: Option<(u32, u32)> = None;
+    for fill in [r & !erasure, r | erasure].iter() {
+        if let Some(code) = ecc(*fill) {
+            let errors = weight((code ^ r) & !erasure);
+            match best {
+                Some((_, e)) if e <= errors => {},
+                _ => best = Some((code, errors)),
+            }
+        }
+    }
+
+    match best {
+        Some((code, errors)) if 2 * errors + n_erasure < 8 => Some(code),
+        _ => None,
+    }
+}
+
 /// 符合語からデータを取り出す．
-/// 
+///
 /// 返り値のデータは下位12bitに入っている．
 /// 上位4bitは必ず0．
 #[inline]
@@ -137,6 +615,29 @@ pub fn decode(code: u32) -> u16 {
     ((code >> 12) & 0xFFF) as u16
 }
 
+/// 受信語を訂正してデータを取り出す．訂正できない場合も受信語のデータ部分をそのまま返す．
+///
+/// 欠落よりも怪しいデータに印を付けて受け取りたい場合に使う．
+/// 訂正できなかった場合は`valid`がfalseになる．
+#[cfg(feature = "correct")]
+#[inline]
+pub fn decode_lenient(r: u32) -> Lenient {
+    match ecc(r) {
+        Some(code) => Lenient{ data: decode(code), valid: true },
+        None       => Lenient{ data: decode(r), valid: false },
+    }
+}
+
+/// `decode_lenient`の結果
+#[cfg(feature = "correct")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lenient {
+    /// データ（下位12bit）
+    pub data: u16,
+    /// 訂正できたかどうか
+    pub valid: bool,
+}
+
 /// シンドロームの重みを計算する（1になっているビットを数える）．
 #[inline]
 fn weight(s: u32) -> u32 {
@@ -144,6 +645,7 @@ fn weight(s: u32) -> u32 {
 }
 
 #[test]
+#[cfg(feature = "correct")]
 fn test() {
     let tx = 0b100110001101;  // 任意のデータ（12bit）
     let encoded = encode(tx);
@@ -173,4 +675,89 @@ fn test() {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+#[cfg(feature = "correct")]
+fn test_verbose() {
+    let code = encode(0b110001011010);
+    let e = 0b0000_0100_0000_0000_0001_0001;
+    let c = ecc_verbose(code ^ e).unwrap();
+    assert_eq!(c.code, code);
+    assert_eq!(c.errors, 3);
+    assert_eq!(c.error_mask, e);
+    let mut pos = c.positions();
+    assert_eq!(pos.next(), Some(0));
+    assert_eq!(pos.next(), Some(4));
+    assert_eq!(pos.next(), Some(18));
+    assert_eq!(pos.next(), None);
+    assert_eq!(ecc_verbose(code ^ 0xF).map(|c| c.errors), None);
+    assert!(!c.parity_only());
+    assert!(ecc_verbose(code ^ 0x111).unwrap().parity_only());
+    assert!(!ecc_verbose(code).unwrap().parity_only());
+
+    assert_eq!(decode_lenient(code ^ e), Lenient{ data: 0b110001011010, valid: true });
+    assert_eq!(decode_lenient(code ^ 0xF), Lenient{ data: 0b110001011010, valid: false });
+
+    assert_eq!(protect(0b110001011010), code);
+    assert_eq!(recover(code ^ e), Ok((0b110001011010, c)));
+    assert_eq!(recover(code ^ 0xF), Err(Detected));
+
+    assert_eq!(ecc_status(code), Decode::Clean(code));
+    assert_eq!(ecc_status(code ^ e), Decode::Corrected(code, 3));
+    assert_eq!(ecc_status(code ^ 0xF), Decode::Detected);
+
+    assert_eq!(ecc_result(code), DecodeResult::NoError{ codeword: code });
+    assert_eq!(ecc_result(code ^ e), DecodeResult::Corrected{ bits: 3, error_mask: e, codeword: code });
+    assert_eq!(ecc_result(code ^ 0xF), DecodeResult::Detected);
+    assert!(ecc_result(code ^ 0xF).is_detected());
+    assert_eq!(ecc_result(code ^ e).bits(), 3);
+    assert_eq!(ecc_result(code ^ e).error_mask(), e);
+    assert_eq!(ecc_result(code).error_mask(), 0);
+    assert_eq!(ecc_result(code ^ 0x800000).codeword(), ecc(code ^ 0x800000));
+}
+
+#[test]
+#[cfg(feature = "correct")]
+fn test_erasure() {
+    let data = 0b011010011100;
+    let code = encode(data);
+
+    // 線形合同法で誤りと消失の位置を選ぶ
+    let mut x: u32 = 12345;
+    let mut rand_bit = || {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        1 << ((x >> 16) % 24)
+    };
+
+    for n_erasure in 0..8 {
+        for n_error in 0..4 {
+            if 2 * n_error + n_erasure >= 8 {
+                continue;
+            }
+            for _ in 0..1000 {
+                let mut erasure = 0;
+                while weight(erasure) < n_erasure {
+                    erasure |= rand_bit();
+                }
+                let mut error = 0;
+                while weight(error) < n_error {
+                    error |= rand_bit() & !erasure;
+                }
+                // 消失位置の値はでたらめにしておく
+                let rx = (code ^ error ^ (erasure & rand_bit().wrapping_mul(0x5A5A5))) & 0xFFFFFF;
+                assert_eq!(Some(code), ecc_erasure(rx, erasure));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_check() {
+    let code = encode(0b101100111000);
+    assert!(check(code));
+    assert!(!check(code ^ 0x000400));
+    assert!(!check(code ^ 0x00F000));
+    assert_eq!(syndrome_weight(code), 0);
+    assert_eq!(decode(code), 0b101100111000);
+}
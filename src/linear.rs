@@ -0,0 +1,209 @@
+//! 小さな2元線形符号と，その組み合わせ
+//!
+//! 長さ24以下の2元線形符号を生成行列で表す．
+//! 符号語は下位nビットに入れ，1番目の座標を最上位（bit n-1）にする．
+//!
+//! ゴレイ符号は次の順に作れる．途中の符号もそれぞれ`LinearCode`として使える．
+//!
+//! 1. 長さ4の偶重み符号と繰り返し符号から，|u|u+v| 構成（Plotkin）で拡大ハミング符号[8,4,4]を作る
+//! 2. 座標を並べ替えて，共通部分が {0, 1} だけになるもう1つの拡大ハミング符号を作る
+//! 3. 2つのハミング符号 H, H' から，|a+x|b+x|a+b+x|（a, b ∈ H，x ∈ H'）の構成（Turyn）で[24,12,8]符号を作る
+
+use crate::gf2;
+
+/// 2元線形符号
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinearCode {
+    n: usize,
+    k: usize,
+    rows: [u32; 24],
+}
+
+impl LinearCode {
+    /// 生成行列から作る．
+    ///
+    /// * `n`: 符号長（1〜24）
+    /// * `rows`: 生成行列の各行（下位nビット）．線形独立でなければNone．
+    pub fn new(n: usize, rows: &[u32]) -> Option<Self> {
+        if n == 0 || n > 24 || rows.len() > n {
+            return None;
+        }
+        let mask = (1u32 << n) - 1;
+        let mut g = [0u32; 24];
+        for (g_line, row) in g.iter_mut().zip(rows.iter()) {
+            *g_line = row & mask;
+        }
+        if gf2::rank(&g[..rows.len()]) != rows.len() {
+            return None;
+        }
+        Some( Self{ n, k: rows.len(), rows: g } )
+    }
+
+    /// 長さnの繰り返し符号[n,1,n]
+    pub fn repetition(n: usize) -> Option<Self> {
+        Self::new(n, &[(1u32 << n.min(24)) - 1])
+    }
+
+    /// 長さnの偶重み符号[n,n-1,2]
+    pub fn even_weight(n: usize) -> Option<Self> {
+        if n < 2 {
+            return None;
+        }
+        let mut rows = [0u32; 23];
+        for (i, row) in rows.iter_mut().enumerate().take(n - 1) {
+            *row = 0b11 << (n - 2 - i);
+        }
+        Self::new(n, &rows[..n - 1])
+    }
+
+    /// 拡大ハミング符号[8,4,4]．偶重み符号[4,3,2]と繰り返し符号[4,1,4]の |u|u+v| 構成．
+    pub fn hamming8() -> Self {
+        let u = Self::even_weight(4).unwrap();
+        let v = Self::repetition(4).unwrap();
+        plotkin(&u, &v).unwrap()
+    }
+
+    /// `hamming8`と共通部分が {0, 1} だけになるように座標を並べ替えた拡大ハミング符号
+    pub fn hamming8_twisted() -> Self {
+        Self::hamming8().permute(&[0, 1, 2, 4, 3, 6, 7, 5]).unwrap()
+    }
+
+    /// Turyn構成によるゴレイ符号[24,12,8]
+    ///
+    /// このクレートの符号とは座標の並べ方が違う．`codec::Codec`に渡せば対応が求まる．
+    pub fn golay() -> Self {
+        turyn(&Self::hamming8(), &Self::hamming8_twisted()).unwrap()
+    }
+
+    /// 符号長
+    #[inline]
+    pub fn length(&self) -> usize {
+        self.n
+    }
+
+    /// 次元（情報ビット数）
+    #[inline]
+    pub fn dimension(&self) -> usize {
+        self.k
+    }
+
+    /// 生成行列
+    #[inline]
+    pub fn rows(&self) -> &[u32] {
+        &self.rows[..self.k]
+    }
+
+    /// 情報（下位kビット，i行目がbit (k-1-i)）を符号化する．
+    pub fn encode(&self, m: u32) -> u32 {
+        gf2::vec_mul(m, self.rows())
+    }
+
+    /// 符号語かどうか
+    pub fn contains(&self, c: u32) -> bool {
+        if c >> self.n != 0 {
+            return false;
+        }
+        let mut g = [0u32; 24];
+        g[..self.k].copy_from_slice(self.rows());
+        g[self.k] = c;
+        gf2::rank(&g[..self.k + 1]) == self.k
+    }
+
+    /// 重み分布（`[i]`が重みiの符号語の数）
+    pub fn weight_distribution(&self) -> [u32; 25] {
+        let mut out = [0u32; 25];
+        for m in 0..(1u32 << self.k) {
+            out[self.encode(m).count_ones() as usize] += 1;
+        }
+        out
+    }
+
+    /// 最小距離．次元0の符号では0．
+    pub fn min_distance(&self) -> u32 {
+        self.weight_distribution().iter().skip(1).position(|n| *n > 0).map_or(0, |w| w as u32 + 1)
+    }
+
+    /// 座標を並べ替えた符号．`perm[i]`はi番目の座標の移り先．
+    pub fn permute(&self, perm: &[usize]) -> Option<Self> {
+        if perm.len() != self.n {
+            return None;
+        }
+        let mut rows = [0u32; 24];
+        for (row, src) in rows.iter_mut().zip(self.rows().iter()) {
+            for (i, p) in perm.iter().enumerate() {
+                *row |= ((src >> (self.n - 1 - i)) & 1) << (self.n - 1 - p);
+            }
+        }
+        Self::new(self.n, &rows[..self.k])
+    }
+}
+
+/// |u|u+v| 構成（Plotkin）．長さの同じ2つの符号から，長さ2倍の符号を作る．
+///
+/// 最小距離は min(2・d(U), d(V)) になる．長さが違うか，24を超えるとNone．
+pub fn plotkin(u: &LinearCode, v: &LinearCode) -> Option<LinearCode> {
+    let n = u.n;
+    if v.n != n || 2 * n > 24 {
+        return None;
+    }
+    let mut rows = [0u32; 24];
+    let mut k = 0;
+    for row in u.rows() {
+        rows[k] = (row << n) | row;
+        k += 1;
+    }
+    for row in v.rows() {
+        rows[k] = *row;
+        k += 1;
+    }
+    LinearCode::new(2 * n, &rows[..k])
+}
+
+/// |a+x|b+x|a+b+x| 構成（Turyn）．a, b ∈ H，x ∈ H'．
+///
+/// H, H' が共通部分 {0, 1} の拡大ハミング符号ならゴレイ符号になる．長さ8でなければNone．
+pub fn turyn(h: &LinearCode, h2: &LinearCode) -> Option<LinearCode> {
+    if h.n != 8 || h2.n != 8 {
+        return None;
+    }
+    let mut rows = [0u32; 24];
+    let mut k = 0;
+    for row in h.rows() {
+        rows[k] = (row << 16) | row;  // a
+        rows[k + 1] = (row << 8) | row;  // b
+        k += 2;
+    }
+    for row in h2.rows() {
+        rows[k] = (row << 16) | (row << 8) | row;  // x
+        k += 1;
+    }
+    LinearCode::new(24, &rows[..k])
+}
+
+#[test]
+fn test_linear() {
+    let h = LinearCode::hamming8();
+    assert_eq!((h.length(), h.dimension(), h.min_distance()), (8, 4, 4));
+    let h2 = LinearCode::hamming8_twisted();
+    let common = (0..16).filter(|m| h2.contains(h.encode(*m))).count();
+    assert_eq!(common, 2);
+
+    let g = LinearCode::golay();
+    assert_eq!((g.length(), g.dimension(), g.min_distance()), (24, 12, 8));
+    let mut expected = [0u32; 25];
+    expected[0] = 1;
+    expected[8] = 759;
+    expected[12] = 2576;
+    expected[16] = 759;
+    expected[24] = 1;
+    assert_eq!(g.weight_distribution(), expected);
+
+    // このクレートの符号と同値
+    let mut rows = [0u32; 12];
+    rows.copy_from_slice(g.rows());
+    assert!(crate::codec::Codec::new(&rows).is_ok());
+
+    assert_eq!(LinearCode::new(4, &[0b1100, 0b0110, 0b1010]), None);
+    assert!(LinearCode::repetition(8).unwrap().contains(0xFF));
+    assert!(!LinearCode::repetition(8).unwrap().contains(0x0F));
+}
@@ -0,0 +1,215 @@
+//! シリアル回線の保護（`std`フィーチャ）
+//!
+//! `Read + Write`を実装したもの（シリアルポートなど）を包んで，
+//! メッセージ単位で送受信する．
+//!
+//! フレームの構成（符号語は3バイト，上位バイトから送る）
+//! * 同期語の符号語
+//! * メッセージ長（バイト）の符号語
+//! * メッセージ本体（`protect`と同じく12bitずつ符号化）
+//!
+//! 受信側は同期語が見つかるまで1バイトずつずらして読み進めるので，
+//! 途中から受信を始めたり，フレームが壊れたりしても次のフレームで同期し直せる．
+//!
+//! 同期語を探している間は，データの中の語を同期語と取り違えないように
+//! `HUNT_MAX_ERRORS`bitまでの誤りしか認めない．フレームを正しく受信して同期が取れた後は，
+//! 次のフレームの先頭の位置がわかっているので，同期語も3bitまで訂正する．
+
+use std::io::{self, Read, Write};
+
+use super::protect::{self, RawReader, RawWriter};
+
+/// 同期語（12bit）
+pub const SYNC: u16 = 0xB38;
+
+/// 同期語を探している間に同期語とみなす誤りのビット数の上限
+pub const HUNT_MAX_ERRORS: u32 = 1;
+
+/// メッセージの最大長（バイト）
+pub const MAX_MESSAGE_LEN: usize = 0xFFF;
+
+/// 送受信の統計
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkStats {
+    /// 送信したフレーム数
+    pub sent: u64,
+    /// 受信できたフレーム数
+    pub received: u64,
+    /// 訂正できずに捨てたフレーム数
+    pub dropped: u64,
+    /// 訂正したビット数の合計
+    pub corrected_bits: u64,
+    /// 同期語を探すために読み飛ばしたバイト数
+    pub skipped_bytes: u64,
+}
+
+/// 保護されたメッセージ送受信
+pub struct ProtectedLink<T> {
+    inner: T,
+    stats: LinkStats,
+    /// 前のフレームを正しく受信して，次のフレームの位置がわかっているか
+    locked: bool,
+}
+
+impl<T> ProtectedLink<T> {
+    pub fn new(inner: T) -> Self {
+        Self{ inner, stats: LinkStats::default(), locked: false }
+    }
+
+    /// 送受信の統計
+    #[inline]
+    pub fn stats(&self) -> &LinkStats {
+        &self.stats
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Write> ProtectedLink<T> {
+    /// メッセージを1つ送信する．
+    pub fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        if msg.len() > MAX_MESSAGE_LEN {
+            return Err( io::Error::new(io::ErrorKind::InvalidInput, "message too long") );
+        }
+        let mut header = [0u8; 6];
+        header[..3].copy_from_slice( &super::encode(SYNC).to_be_bytes()[1..] );
+        header[3..].copy_from_slice( &super::encode(msg.len() as u16).to_be_bytes()[1..] );
+        self.inner.write_all(&header)?;
+
+        let mut body = [0u8; protect::protected_len(MAX_MESSAGE_LEN)];
+        let mut w = RawWriter::new(&mut body);
+        w.write(msg);
+        let len = w.finish().unwrap();
+        self.inner.write_all(&body[..len])?;
+        self.inner.flush()?;
+        self.stats.sent += 1;
+        Ok(())
+    }
+}
+
+impl<T: Read> ProtectedLink<T> {
+    /// メッセージを1つ受信する．
+    ///
+    /// 正しく受信できるまで読み進める．訂正できないフレームや`buf`に入りきらないフレームは捨てる．
+    ///
+    /// * return: メッセージ長
+    pub fn receive(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let sync = super::encode(SYNC);
+        let mut window = [0u8; 3];
+        self.inner.read_exact(&mut window)?;
+        loop {
+            // 同期語を探す
+            let r = u32::from_be_bytes([0, window[0], window[1], window[2]]);
+            let found = if self.locked {
+                super::ecc(r) == Some(sync)
+            } else {
+                (r ^ sync).count_ones() <= HUNT_MAX_ERRORS
+            };
+            if !found {
+                // 同期が外れたので探し直す
+                self.locked = false;
+                window.copy_within(1.., 0);
+                self.inner.read_exact(&mut window[2..])?;
+                self.stats.skipped_bytes += 1;
+                continue;
+            }
+            let sync_errors = (r ^ sync).count_ones();
+
+            // メッセージ長
+            let mut len_bytes = [0u8; 3];
+            self.inner.read_exact(&mut len_bytes)?;
+            let r = u32::from_be_bytes([0, len_bytes[0], len_bytes[1], len_bytes[2]]);
+            let len = match super::ecc_verbose(r) {
+                Some(c) if (super::decode(c.code) as usize) <= buf.len() => {
+                    self.stats.corrected_bits += (sync_errors + c.errors as u32) as u64;
+                    super::decode(c.code) as usize
+                },
+                _ => {
+                    // 長さを読めなければ，読んだ分から同期語を探し直す
+                    self.stats.dropped += 1;
+                    self.locked = false;
+                    window = len_bytes;
+                    continue;
+                },
+            };
+
+            let mut body = [0u8; protect::protected_len(MAX_MESSAGE_LEN)];
+            let body = &mut body[..protect::protected_len(len)];
+            self.inner.read_exact(body)?;
+            let mut reader = RawReader::new(body);
+            reader.read(&mut buf[..len]);
+            if reader.failed {
+                self.stats.dropped += 1;
+                self.locked = false;
+                self.inner.read_exact(&mut window)?;
+                continue;
+            }
+            self.stats.corrected_bits += reader.corrected as u64;
+            self.stats.received += 1;
+            self.locked = true;
+            return Ok(len);
+        }
+    }
+}
+
+#[test]
+fn test_link() {
+    use std::vec::Vec;
+
+    let mut tx = ProtectedLink::new(Vec::new());
+    tx.send(b"hello").unwrap();
+    tx.send(b"").unwrap();
+    tx.send(b"golay code").unwrap();
+    assert_eq!(tx.stats().sent, 3);
+
+    let mut wire = tx.into_inner();
+    wire.insert(0, 0x55);  // 途中から受信した想定のごみ
+    wire[5] ^= 0x81;       // メッセージ長に2bit誤り
+    wire[9] ^= 0x10;
+    let n = wire.len();
+    wire[n - 2] ^= 0x0F;   // 最後のフレームに4bit誤り
+
+    let mut rx = ProtectedLink::new(&wire[..]);
+    let mut buf = [0u8; 16];
+    assert_eq!(rx.receive(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"hello");
+    assert_eq!(rx.receive(&mut buf).unwrap(), 0);
+    assert!(rx.receive(&mut buf).is_err());  // 最後のフレームは訂正できずに捨てて，そのまま終端
+    let s = rx.stats();
+    assert_eq!((s.received, s.dropped, s.skipped_bytes), (2, 1, 1));
+    assert_eq!(s.corrected_bits, 3);
+
+    // 同期語の2bit誤りは，同期が取れていれば訂正し，探している間は同期語とみなさない
+    let mut tx = ProtectedLink::new(Vec::new());
+    tx.send(b"first").unwrap();
+    tx.send(b"second").unwrap();
+    let clean = tx.into_inner();
+    let second = 6 + protect::protected_len(5);
+    let mut wire = clean.clone();
+    wire[second] ^= 0x41;
+    let mut rx = ProtectedLink::new(&wire[..]);
+    assert_eq!(rx.receive(&mut buf).unwrap(), 5);
+    assert_eq!(rx.receive(&mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"second");
+    assert_eq!(rx.stats().corrected_bits, 2);
+
+    let mut wire = clean;
+    wire[0] ^= 0x41;
+    let mut rx = ProtectedLink::new(&wire[..]);
+    assert_eq!(rx.receive(&mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"second");
+    assert_eq!(rx.stats().skipped_bytes, second as u64);
+}
@@ -0,0 +1,199 @@
+//! LoRaなどのISMバンド無線向けのペイロード保護
+//!
+//! 無線機自体の符号化では足りない電波の届く限界付近で，アプリケーション層に重ねて使う．
+//!
+//! パケットの構成
+//! * 先頭の符号語にペイロード長（バイト）を入れる．
+//! * ペイロードは上位ビットから12bitずつ区切って符号化する（端数は0で埋める）．
+//! * 全ての符号語をパケット全体でインターリーブする．
+//!
+//! 1パケットは最大255バイトなので，符号語は85個（ペイロード126バイト）まで．
+
+use super::{interleave, Decode};
+
+/// パケットの最大長（バイト）
+pub const MAX_PACKET: usize = 255;
+
+/// 1パケットの符号語の最大数
+pub const MAX_CODEWORDS: usize = MAX_PACKET / 3;
+
+/// ペイロードの最大長（バイト）
+pub const MAX_PAYLOAD: usize = (MAX_CODEWORDS - 1) * 12 / 8;
+
+/// LoRaペイロードのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoraError {
+    /// ペイロードが長すぎる
+    TooLong,
+    /// 出力先のバッファが足りない
+    BufferTooSmall,
+    /// パケット長が不正か，ペイロード長と合わない
+    Malformed,
+    /// ペイロード長の符号語を訂正できない
+    Header,
+}
+
+/// データの符号語の数
+#[inline]
+fn data_codewords(payload_len: usize) -> usize {
+    (payload_len * 8).div_ceil(12)
+}
+
+/// 符号化後のパケット長（バイト）
+#[inline]
+pub fn encoded_len(payload_len: usize) -> usize {
+    (1 + data_codewords(payload_len)) * 3
+}
+
+/// ペイロードを符号化してインターリーブする．
+///
+/// * return: パケット長
+pub fn encode(payload: &[u8], out: &mut [u8]) -> Result<usize, LoraError> {
+    if payload.len() > MAX_PAYLOAD {
+        return Err(LoraError::TooLong);
+    }
+    let n = 1 + data_codewords(payload.len());
+    let mut codes = [0u32; MAX_CODEWORDS];
+    codes[0] = super::encode(payload.len() as u16);
+    for (i, code) in codes[1..n].iter_mut().enumerate() {
+        *code = super::encode( get_word(payload, i) );
+    }
+    interleave::interleave(&codes[..n], out).ok_or(LoraError::BufferTooSmall)
+}
+
+/// バイト列のi番目の12bitを取り出す（範囲外は0）．
+fn get_word(bytes: &[u8], i: usize) -> u16 {
+    let mut a = 0;
+    for k in 0..12 {
+        let bit = 12 * i + k;
+        let b = bytes.get(bit / 8).map_or(0, |b| (b >> (7 - bit % 8)) & 1);
+        a = (a << 1) | b as u16;
+    }
+    a
+}
+
+/// バイト列のi番目の12bitに書き込む（範囲外は捨てる）．
+fn put_word(bytes: &mut [u8], i: usize, a: u16) {
+    for k in 0..12 {
+        let bit = 12 * i + k;
+        if let Some(b) = bytes.get_mut(bit / 8) {
+            let shift = 7 - bit % 8;
+            *b = (*b & !(1 << shift)) | ((((a >> (11 - k)) & 1) as u8) << shift);
+        }
+    }
+}
+
+/// 受信したパケットの各符号語の状態
+#[derive(Clone, Copy, Debug)]
+pub struct Report {
+    status: [Decode; MAX_CODEWORDS],
+    n: usize,
+}
+
+impl Report {
+    /// 各符号語の状態．
+    ///
+    /// 添字0はペイロード長の符号語，添字iはペイロードの 12(i-1)〜12i-1 bit目の符号語．
+    #[inline]
+    pub fn status(&self) -> &[Decode] {
+        &self.status[..self.n]
+    }
+
+    /// 訂正したビット数の合計
+    pub fn corrected_bits(&self) -> u32 {
+        self.status().iter().map(|s| match s {
+            Decode::Corrected(_, bits) => *bits as u32,
+            _ => 0,
+        }).sum()
+    }
+
+    /// 全ての符号語を訂正できたらtrue
+    pub fn is_ok(&self) -> bool {
+        self.status().iter().all(|s| *s != Decode::Detected)
+    }
+
+    /// i番目の符号語が含まれるペイロードのバイト範囲
+    ///
+    /// 添字0はペイロード長の符号語なのでNone．
+    pub fn byte_range(&self, i: usize) -> Option<core::ops::Range<usize>> {
+        if i == 0 {
+            return None;
+        }
+        let start = 12 * (i - 1) / 8;
+        let end = (12 * i).div_ceil(8);
+        Some( start..end )
+    }
+}
+
+/// パケットを訂正してペイロードを取り出す．
+///
+/// 訂正できない符号語があっても，その部分は受信したままのデータを入れて続ける．
+/// どの部分が壊れているかは`Report`で確かめる．
+///
+/// * return: ペイロード長と各符号語の状態
+pub fn decode(packet: &[u8], out: &mut [u8]) -> Result<(usize, Report), LoraError> {
+    if packet.len() > MAX_PACKET || packet.len() < 3 || !packet.len().is_multiple_of(3) {
+        return Err(LoraError::Malformed);
+    }
+    let n = packet.len() / 3;
+    let mut codes = [0u32; MAX_CODEWORDS];
+    interleave::deinterleave(packet, &mut codes[..n]).ok_or(LoraError::Malformed)?;
+
+    let mut report = Report{ status: [Decode::Detected; MAX_CODEWORDS], n };
+    let len = match super::ecc_status(codes[0]) {
+        Decode::Detected => return Err(LoraError::Header),
+        s => {
+            report.status[0] = s;
+            super::decode(s.code().unwrap()) as usize
+        },
+    };
+    if encoded_len(len) != packet.len() {
+        return Err(LoraError::Malformed);
+    }
+    let out = out.get_mut(..len).ok_or(LoraError::BufferTooSmall)?;
+
+    for (i, r) in codes[..n].iter().enumerate().skip(1) {
+        let s = super::ecc_status(*r);
+        report.status[i] = s;
+        let word = super::decode( s.code().unwrap_or(*r) );
+        put_word(out, i - 1, word);
+    }
+    Ok( (len, report) )
+}
+
+#[test]
+fn test_lora() {
+    let payload = b"fringe of coverage";
+    let mut packet = [0u8; MAX_PACKET];
+    let len = encode(payload, &mut packet).unwrap();
+    assert_eq!(len, encoded_len(payload.len()));
+    assert_eq!(len, 3 * 13);
+
+    // 26bitのバースト誤り（13符号語に2bitずつ）
+    for b in packet[10..13].iter_mut() {
+        *b ^= 0xFF;
+    }
+    packet[13] ^= 0b1100_0000;
+
+    let mut out = [0u8; MAX_PAYLOAD];
+    let (n, report) = decode(&packet[..len], &mut out).unwrap();
+    assert_eq!(&out[..n], payload);
+    assert!(report.is_ok());
+    assert_eq!(report.corrected_bits(), 26);
+
+    // 1つの符号語だけ壊す（4bit）．パケット全体で13符号語なので13bitおきに同じ符号語に入る．
+    encode(payload, &mut packet).unwrap();
+    for k in 0..4 {
+        let bit = 5 + 13 * k;
+        packet[bit / 8] ^= 1 << (7 - bit % 8);
+    }
+    let (_, report) = decode(&packet[..len], &mut out).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.status()[5], Decode::Detected);
+    assert_eq!(report.byte_range(5), Some(6..8));
+    assert_eq!(report.byte_range(1), Some(0..2));
+    assert_eq!(report.byte_range(0), None);
+    assert_eq!(&out[..6], &payload[..6]);
+
+    assert_eq!(encode(&[0; MAX_PAYLOAD + 1], &mut packet), Err(LoraError::TooLong));
+}
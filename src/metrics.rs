@@ -0,0 +1,118 @@
+//! 復号の結果を利用者の計測系に渡す
+//!
+//! `Codec`，`text`のフレーム，`Scrubber`は1語を訂正するごとに`MetricsSink`を呼ぶ．
+//! このクレートはログの仕組みに依存しないので，集計や送信の方法は利用者が決める．
+//! 何もしない`()`を渡せば計測しない．
+
+use super::Decode;
+
+/// 復号の結果を受け取る．メソッドは既定では何もしない．
+pub trait MetricsSink {
+    /// 誤りのない語を受け取った．
+    #[inline]
+    fn on_clean(&mut self) {}
+
+    /// 誤りを訂正した．
+    ///
+    /// * `errors`: 訂正したビット数（1〜3）
+    #[inline]
+    fn on_corrected(&mut self, errors: u8) {
+        let _ = errors;
+    }
+
+    /// 訂正できない誤りを検出した．
+    #[inline]
+    fn on_detected(&mut self) {}
+
+    /// 誤り訂正の結果を対応するメソッドに振り分ける．
+    #[inline]
+    fn record(&mut self, result: Decode) {
+        match result {
+            Decode::Clean(_) => self.on_clean(),
+            Decode::Corrected(_, errors) => self.on_corrected(errors),
+            Decode::Detected => self.on_detected(),
+        }
+    }
+}
+
+/// 何もしない
+impl MetricsSink for () {}
+
+impl<M: MetricsSink + ?Sized> MetricsSink for &mut M {
+    #[inline]
+    fn on_clean(&mut self) {
+        (**self).on_clean()
+    }
+
+    #[inline]
+    fn on_corrected(&mut self, errors: u8) {
+        (**self).on_corrected(errors)
+    }
+
+    #[inline]
+    fn on_detected(&mut self) {
+        (**self).on_detected()
+    }
+}
+
+/// 受信語の誤り訂正を行い，結果を`sink`に渡す．訂正できない場合はNone．
+#[inline]
+pub fn ecc<M: MetricsSink + ?Sized>(r: u32, sink: &mut M) -> Option<u32> {
+    let result = super::ecc_status(r);
+    sink.record(result);
+    result.code()
+}
+
+#[test]
+fn test_metrics() {
+    #[derive(Default)]
+    struct Counter {
+        clean: u32,
+        corrected: u32,
+        bits: u32,
+        detected: u32,
+    }
+    impl MetricsSink for Counter {
+        fn on_clean(&mut self) {
+            self.clean += 1;
+        }
+        fn on_corrected(&mut self, errors: u8) {
+            self.corrected += 1;
+            self.bits += errors as u32;
+        }
+        fn on_detected(&mut self) {
+            self.detected += 1;
+        }
+    }
+
+    let code = crate::encode(0x5A5);
+    let mut c = Counter::default();
+    assert_eq!(ecc(code, &mut c), Some(code));
+    assert_eq!(ecc(code ^ 0x800100, &mut c), Some(code));
+    assert_eq!(ecc(code ^ 0xF, &mut c), None);
+    assert_eq!((c.clean, c.corrected, c.bits, c.detected), (1, 1, 2, 1));
+    assert_eq!(ecc(code ^ 1, &mut ()), Some(code));
+
+    // 符号語のビット順を変えるCodec
+    let mut g = crate::G;
+    for row in g.iter_mut() {
+        *row = row.reverse_bits() >> 8;
+    }
+    let codec = crate::codec::Codec::new(&g).unwrap();
+    let tx = codec.encode(0x123);
+    assert_eq!(codec.ecc_metered(tx ^ 4, &mut c), Some(tx));
+    assert_eq!(c.corrected, 2);
+
+    // スクラバ
+    let mut words = [crate::encode(1), crate::encode(2) ^ 0x7, crate::encode(3) ^ 0x800];
+    let mut s = crate::scrub::Scrubber::new(3, 1).unwrap();
+    s.step_metered(&mut [&mut words[..]], &mut c);
+    assert_eq!((c.clean, c.corrected, c.bits, c.detected), (2, 4, 7, 1));
+
+    // 文字列のフレーム
+    let mut text = [0u8; 32];
+    let n = crate::text::encode_frame(crate::text::Encoding::Base64, b"abc", &mut text).unwrap();
+    let mut out = [0u8; 4];
+    crate::text::decode_frame_metered(crate::text::Encoding::Base64, &text[..n], &mut out, &mut c).unwrap();
+    assert_eq!(c.clean, 2 + 3);
+}
@@ -0,0 +1,152 @@
+//! 2つのメモリに2重化して置くストレージ
+//!
+//! データを`stripe`バイトずつのストライプに分け，各ストライプを`protect`と同じ形式で符号化して
+//! 2つの独立したメモリ（2個のSPI FRAMなど）の同じ位置に書き込む（ECC付きのRAID-1）．
+//!
+//! 読み出しでは符号語ごとに2つのコピーを訂正し，訂正できたほうを採用する．
+//! 片方のメモリで訂正できない符号語があっても，もう片方から復元して両方に書き戻す．
+//! 同じ符号語が両方とも訂正できない場合だけ失敗する．
+//!
+//! メモリは呼び出し側が`&mut [u8]`で渡す（デバイスの内容をRAMに写したものなど）．
+
+use crate::protect::{protected_len, RawReader, RawWriter};
+
+/// 2重化ストレージのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MirrorError {
+    /// ストライプの番号が範囲外か，データの長さがストライプと違う
+    OutOfRange,
+    /// 両方のメモリで訂正できない符号語があった（ストライプ内の符号語の番号）
+    Lost(usize),
+}
+
+/// 読み出しで直したもの
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Repair {
+    /// 訂正したビット数（両方のメモリの合計）
+    pub corrected_bits: usize,
+    /// もう片方のメモリから復元した符号語の数
+    pub reconstructed: usize,
+}
+
+/// 2重化ストレージ
+pub struct Mirror<'a> {
+    a: &'a mut [u8],
+    b: &'a mut [u8],
+    stripe: usize,
+}
+
+impl<'a> Mirror<'a> {
+    /// * `a`, `b`: 2つのメモリ．短いほうに合わせる．
+    /// * `stripe`: 1ストライプのデータのバイト数（1以上）
+    pub fn new(a: &'a mut [u8], b: &'a mut [u8], stripe: usize) -> Option<Self> {
+        if stripe == 0 {
+            return None;
+        }
+        Some( Self{ a, b, stripe } )
+    }
+
+    /// ストライプの数
+    #[inline]
+    pub fn stripes(&self) -> usize {
+        self.a.len().min(self.b.len()) / protected_len(self.stripe)
+    }
+
+    /// 1ストライプのデータのバイト数
+    #[inline]
+    pub fn stripe_len(&self) -> usize {
+        self.stripe
+    }
+
+    /// ストライプを両方のメモリに書き込む．
+    ///
+    /// * `data`: `stripe_len()`バイトのデータ
+    pub fn write(&mut self, index: usize, data: &[u8]) -> Result<(), MirrorError> {
+        let range = self.range(index, data.len())?;
+        let mut w = RawWriter::new(&mut self.a[range.clone()]);
+        w.write(data);
+        w.finish();
+        self.b[range.clone()].copy_from_slice(&self.a[range]);
+        Ok(())
+    }
+
+    /// ストライプを読み出す．誤りがあれば両方のメモリに正しい符号語を書き戻す．
+    ///
+    /// * `buf`: 読み出し先（`stripe_len()`バイト）
+    pub fn read(&mut self, index: usize, buf: &mut [u8]) -> Result<Repair, MirrorError> {
+        let range = self.range(index, buf.len())?;
+        let mut repair = Repair::default();
+        for (j, pos) in range.clone().step_by(3).enumerate() {
+            let ra = word(self.a, pos);
+            let rb = word(self.b, pos);
+            let ca = crate::ecc_verbose(ra);
+            let cb = crate::ecc_verbose(rb);
+            let code = match (ca, cb) {
+                (Some(ca), Some(cb)) => {
+                    repair.corrected_bits += (ca.errors + cb.errors) as usize;
+                    // 食い違う場合は訂正したビット数の少ないほうを信じる
+                    if ca.errors <= cb.errors { ca.code } else { cb.code }
+                },
+                (Some(c), None) | (None, Some(c)) => {
+                    repair.corrected_bits += c.errors as usize;
+                    repair.reconstructed += 1;
+                    c.code
+                },
+                (None, None) => return Err(MirrorError::Lost(j)),
+            };
+            if ra != code {
+                self.a[pos..pos + 3].copy_from_slice(&code.to_be_bytes()[1..]);
+            }
+            if rb != code {
+                self.b[pos..pos + 3].copy_from_slice(&code.to_be_bytes()[1..]);
+            }
+        }
+        let mut r = RawReader::new(&self.a[range]);
+        r.read(buf);
+        Ok(repair)
+    }
+
+    /// ストライプのバイト位置
+    fn range(&self, index: usize, len: usize) -> Result<core::ops::Range<usize>, MirrorError> {
+        if index >= self.stripes() || len != self.stripe {
+            return Err(MirrorError::OutOfRange);
+        }
+        let n = protected_len(self.stripe);
+        Ok(index * n..(index + 1) * n)
+    }
+}
+
+/// 3バイトを受信語にする．
+#[inline]
+fn word(mem: &[u8], pos: usize) -> u32 {
+    u32::from_be_bytes([0, mem[pos], mem[pos + 1], mem[pos + 2]])
+}
+
+#[test]
+fn test_mirror() {
+    let mut a = [0u8; 64];
+    let mut b = [0u8; 60];
+    {
+        let mut m = Mirror::new(&mut a, &mut b, 6).unwrap();
+        assert_eq!(m.stripes(), 5);
+        assert_eq!(m.write(5, b"abcdef"), Err(MirrorError::OutOfRange));
+        assert_eq!(m.write(0, b"abc"), Err(MirrorError::OutOfRange));
+        m.write(1, b"Golay!").unwrap();
+        m.write(2, b"mirror").unwrap();
+    }
+
+    // aの1語目とbの2語目は訂正できない．aの3語目は訂正できる．
+    a[12] ^= 0xF0;
+    b[15] ^= 0x0F;
+    a[18] ^= 0x01;
+    let mut m = Mirror::new(&mut a, &mut b, 6).unwrap();
+    let mut buf = [0u8; 6];
+    assert_eq!(m.read(1, &mut buf), Ok(Repair{ corrected_bits: 1, reconstructed: 2 }));
+    assert_eq!(&buf, b"Golay!");
+    assert_eq!(m.read(1, &mut buf), Ok(Repair::default()));
+
+    // 同じ語が両方とも訂正できない
+    m.a[24] ^= 0xF0;
+    m.b[24] ^= 0x0F;
+    assert_eq!(m.read(2, &mut buf), Err(MirrorError::Lost(0)));
+}
@@ -0,0 +1,141 @@
+//! 誤訂正される誤りパターンの解析
+//!
+//! 5bit以上の誤りは，別の符号語から3bit以内に入ると，その符号語に誤って訂正される（検出できない）．
+//! 符号は線形なので，誤訂正されるかどうかは送った符号語によらず誤りパターンだけで決まる．
+//! 誤りパターンそのものを受信語として訂正し，0以外の符号語になれば誤訂正される．
+//!
+//! 5bitの誤りは必ずどれか1つのオクタッド（重み8の符号語）に含まれるので，全て誤訂正される．
+//! 安全性の評価に使うために，指定した重みの誤りパターンを全て調べる．
+
+/// 誤りパターンが誤訂正されるかどうか．誤訂正される場合は，誤って訂正した先の符号語
+/// （符号語0を送った場合．一般には送った符号語とのXOR）を返す．
+#[inline]
+pub fn miscorrection(e: u32) -> Option<u32> {
+    match super::ecc(e & 0xFFFFFF) {
+        Some(code) if code != 0 => Some(code),
+        _ => None,
+    }
+}
+
+/// 指定した重みの誤りパターンのうち，誤訂正されるものを小さい順に列挙する．
+///
+/// 要素は（誤りパターン，誤って訂正した先の符号語）．
+#[derive(Clone, Copy, Debug)]
+pub struct Miscorrections {
+    next: u32,
+}
+
+impl Miscorrections {
+    /// * `weight`: 誤りパターンの重み（1〜24）
+    pub fn new(weight: u32) -> Self {
+        let next = if (1..=24).contains(&weight) { (1u32 << weight) - 1 } else { 1 << 24 };
+        Self{ next }
+    }
+}
+
+impl Iterator for Miscorrections {
+    type Item = (u32, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next < (1 << 24) {
+            let e = self.next;
+            // 同じ重みの次のパターン（Gosper's hack）
+            let c = e & e.wrapping_neg();
+            let r = e + c;
+            self.next = if r >= (1 << 24) { 1 << 24 } else { (((r ^ e) >> 2) / c) | r };
+            if let Some(code) = miscorrection(e) {
+                return Some((e, code));
+            }
+        }
+        None
+    }
+}
+
+/// 指定した重みの誤りパターンの数と，そのうち誤訂正されるものの数を数える．
+///
+/// * return: （誤訂正される数，全ての数）
+pub fn count(weight: u32) -> (usize, usize) {
+    let miscorrected = Miscorrections::new(weight).count();
+    let mut total = 1usize;
+    for i in 0..weight.min(24) as usize {
+        total = total * (24 - i) / (i + 1);
+    }
+    (miscorrected, total)
+}
+
+/// 符号の重み分布（重み，符号語の数）．0以外．
+pub const WEIGHT_DISTRIBUTION: [(u32, usize); 4] = [(8, 759), (12, 2576), (16, 759), (24, 1)];
+
+/// 二項係数
+const fn binomial(n: u32, k: u32) -> usize {
+    if k > n {
+        return 0;
+    }
+    let mut c = 1usize;
+    let mut i = 0;
+    while i < k {
+        c = c * (n - i) as usize / (i + 1) as usize;
+        i += 1;
+    }
+    c
+}
+
+/// 指定した重みの誤りパターンのうち，誤訂正されるものの数を重み分布から求める．
+///
+/// 0以外の符号語から距離3以内にある重み`weight`の語を数える．
+/// 重みkの符号語からiビットを0にしてjビットを1にした語（i + j ≤ 3，k - i + j = weight）は
+/// C(k, i)・C(24 - k, j)個ある．各語は最も近い符号語1つにしか入らないので重複しない．
+/// 距離0の語（符号語そのもの，検出も訂正もされない）も含む．`count`と同じ値になる．
+pub fn expected(weight: u32) -> usize {
+    let mut n = 0;
+    for (k, a) in WEIGHT_DISTRIBUTION.iter() {
+        for i in 0..=3u32.min(*k) {
+            for j in 0..=(3 - i) {
+                if k - i + j == weight {
+                    n += a * binomial(*k, i) * binomial(24 - k, j);
+                }
+            }
+        }
+    }
+    n
+}
+
+/// 指定した重みの誤りパターンのうち，符号語そのものになる（全く検出されない）ものの数
+pub fn undetected(weight: u32) -> usize {
+    WEIGHT_DISTRIBUTION.iter().find(|(k, _)| *k == weight).map_or(0, |(_, a)| *a)
+}
+
+/// 誤訂正されるパターンを1行に1つ「誤りパターン,訂正先の符号語」の16進数で書き出す．
+///
+/// * return: 書き出した行数
+#[cfg(feature = "std")]
+pub fn export<W: std::io::Write>(weight: u32, out: &mut W) -> std::io::Result<usize> {
+    let mut n = 0;
+    for (e, code) in Miscorrections::new(weight) {
+        writeln!(out, "{:06X},{:06X}", e, code)?;
+        n += 1;
+    }
+    Ok(n)
+}
+
+#[test]
+fn test_miscorrection() {
+    assert_eq!(count(4), (0, 10626));
+    assert_eq!(count(5), (42504, 42504));
+    // 6bitの誤りはオクタッドに含まれるときだけ誤訂正される（759 × 28）
+    assert_eq!(count(6), (21252, 134596));
+
+    let (e, code) = Miscorrections::new(5).next().unwrap();
+    assert_eq!(e, 0b11111);
+    assert_eq!(code.count_ones(), 8);
+    assert_eq!(code & e, e);
+
+    for w in 4..=8 {
+        assert_eq!(expected(w), count(w).0);
+    }
+    // 重みごとの数の合計は，0以外の符号語の半径3の球の大きさの合計
+    let total: usize = (0..=24).map(expected).sum();
+    assert_eq!(total, 4095 * (1 + 24 + 276 + 2024));
+    assert_eq!(undetected(8), 759);
+    assert_eq!(undetected(7), 0);
+}
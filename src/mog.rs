@@ -0,0 +1,144 @@
+//! MOG（Miracle Octad Generator）座標
+//!
+//! 符号語の24bitを4行6列の配列に並べ替える．
+//! 行には上から GF(4) の 0, 1, ω, ω̄ のラベルが付いていて，
+//! 配列が符号語になるのは次の2つを満たすときだけ．
+//!
+//! * 各列の重みの偶奇が，一番上の行の重みの偶奇と一致する
+//! * 各列の1になっている行のラベルの和（スコア）を並べたものがヘキサコード（GF(4)上の[6,3,4]符号）の符号語
+//!
+//! 列は左から2列ずつ3つのブリックに分かれる．
+
+use core::fmt;
+
+/// `POSITION[i]`は符号語のbit iのMOGでの位置（列×4 + 行）．
+///
+/// データの上位ビットから順に左上の列に入るように，オクタッドの同型写像を探索して求めた．
+const POSITION: [u8; 24] = [
+    19, 10,  9, 20,  6, 13, 22, 18,
+    17, 21, 23, 11, 12,  5, 14, 16,
+     7, 15,  8,  4,  3,  2,  1,  0,
+];
+
+/// 符号語のビット位置からMOGの（行，列）を求める．
+#[inline]
+pub fn position(bit: u8) -> (u8, u8) {
+    let p = POSITION[bit as usize];
+    (p % 4, p / 4)
+}
+
+/// MOGの（行，列）から符号語のビット位置を求める．
+#[inline]
+pub fn bit(row: u8, col: u8) -> u8 {
+    let p = col * 4 + row;
+    POSITION.iter().position(|x| *x == p).unwrap() as u8
+}
+
+/// 符号語を4行6列の配列にする．
+pub fn to_array(r: u32) -> [[bool; 6]; 4] {
+    let mut out = [[false; 6]; 4];
+    for i in 0..24 {
+        let (row, col) = position(i);
+        out[row as usize][col as usize] = (r >> i) & 1 == 1;
+    }
+    out
+}
+
+/// 4行6列の配列を符号語のビット順に戻す．
+pub fn from_array(array: &[[bool; 6]; 4]) -> u32 {
+    let mut r = 0;
+    for i in 0..24 {
+        let (row, col) = position(i);
+        r |= (array[row as usize][col as usize] as u32) << i;
+    }
+    r
+}
+
+/// 各列のスコア（GF(4)の元を 0, 1, 2 = ω, 3 = ω̄ で表す）
+///
+/// GF(4)の加算はXORなので，1になっている行の番号のXORになる．
+pub fn scores(r: u32) -> [u8; 6] {
+    let mut out = [0; 6];
+    for i in 0..24 {
+        let (row, col) = position(i);
+        if (r >> i) & 1 == 1 {
+            out[col as usize] ^= row;
+        }
+    }
+    out
+}
+
+/// 符号語をMOGの形で表示する．1は`x`，0は`.`．
+///
+/// ```text
+/// x . | x . | x .
+/// x . | x . | x .
+/// . . | . . | . .
+/// . . | . . | . .
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mog(pub u32);
+
+impl fmt::Display for Mog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let array = to_array(self.0);
+        for (i, row) in array.iter().enumerate() {
+            for (col, x) in row.iter().enumerate() {
+                if col > 0 {
+                    f.write_str(if col % 2 == 0 { " | " } else { " " })?;
+                }
+                f.write_str(if *x { "x" } else { "." })?;
+            }
+            if i < 3 {
+                f.write_str("\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_mog() {
+    let gf4_mul = |a: u8, b: u8| -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        let log = [0, 0, 1, 2];
+        [1, 2, 3][(log[a as usize] + log[b as usize]) % 3]
+    };
+
+    for data in 0..4096 {
+        let code = super::encode(data);
+        let array = to_array(code);
+        assert_eq!(from_array(&array), code);
+
+        // 列の重みの偶奇が一番上の行と同じ
+        let top = array[0].iter().filter(|x| **x).count() % 2;
+        for col in 0..6 {
+            assert_eq!(array.iter().filter(|row| row[col]).count() % 2, top);
+        }
+
+        // スコアがヘキサコードの符号語 (a, b, c, φ(1), φ(ω), φ(ω̄))，φ(x) = ax^2 + bx + c
+        let s = scores(code);
+        for (x, expected) in [1u8, 2, 3].iter().zip(s[3..].iter()) {
+            let phi = gf4_mul(s[0], gf4_mul(*x, *x)) ^ gf4_mul(s[1], *x) ^ s[2];
+            assert_eq!(phi, *expected);
+        }
+    }
+    for i in 0..24 {
+        let (row, col) = position(i);
+        assert_eq!(bit(row, col), i);
+    }
+
+    struct Buf { b: [u8; 64], n: usize }
+    impl fmt::Write for Buf {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.b[self.n..self.n + s.len()].copy_from_slice(s.as_bytes());
+            self.n += s.len();
+            Ok(())
+        }
+    }
+    let mut buf = Buf{ b: [0; 64], n: 0 };
+    fmt::Write::write_fmt(&mut buf, format_args!("{}", Mog(1 << 23))).unwrap();
+    assert_eq!(&buf.b[..buf.n], b"x . | . . | . .\n. . | . . | . .\n. . | . . | . .\n. . | . . | . .");
+}
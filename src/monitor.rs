@@ -0,0 +1,104 @@
+//! 伝送路の監視
+//!
+//! 一定数の受信語ごとに訂正の統計をとり，フレームを落とし始める前に伝送路の劣化を観測する．
+
+use super::Decode;
+
+/// 受信語の統計
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// 受信語の数
+    pub words: u32,
+    /// 誤りなしの語の数
+    pub clean: u32,
+    /// 訂正した語の数
+    pub corrected: u32,
+    /// 訂正できない誤りを検出した語の数
+    pub detected: u32,
+    /// 訂正したビット数の合計
+    pub corrected_bits: u32,
+    /// シンドロームの重みの合計
+    pub syndrome_weight: u32,
+}
+
+impl Stats {
+    /// 1語あたりの訂正ビット数の平均
+    #[inline]
+    pub fn mean_corrected_bits(&self) -> f32 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        self.corrected_bits as f32 / self.words as f32
+    }
+
+    /// 受信語1つ分の結果を加える．
+    #[inline]
+    pub fn add(&mut self, syndrome_weight: u8, result: Decode) {
+        self.words += 1;
+        self.syndrome_weight += syndrome_weight as u32;
+        match result {
+            Decode::Clean(_) => self.clean += 1,
+            Decode::Corrected(_, bits) => {
+                self.corrected += 1;
+                self.corrected_bits += bits as u32;
+            },
+            Decode::Detected => self.detected += 1,
+        }
+    }
+}
+
+/// 窓ごとに統計をとりながら誤り訂正を行う．
+#[derive(Clone, Copy, Debug)]
+pub struct LinkMonitor {
+    window: u32,
+    current: Stats,
+    last: Option<Stats>,
+}
+
+impl LinkMonitor {
+    /// * `window`: 統計をとる受信語の数（1以上）
+    pub fn new(window: u32) -> Self {
+        assert!(window > 0);
+        Self{ window, current: Stats::default(), last: None }
+    }
+
+    /// 受信語のエラー検出と訂正を行い，結果を統計に加える．
+    pub fn ecc(&mut self, r: u32) -> Decode {
+        let result = super::ecc_status(r);
+        self.current.add(super::syndrome_weight(r), result);
+        if self.current.words >= self.window {
+            self.last = Some(self.current);
+            self.current = Stats::default();
+        }
+        result
+    }
+
+    /// 集計中の窓の統計
+    #[inline]
+    pub fn current(&self) -> &Stats {
+        &self.current
+    }
+
+    /// 直前に集計が終わった窓の統計．まだ一つも終わっていなければNone．
+    #[inline]
+    pub fn last_window(&self) -> Option<&Stats> {
+        self.last.as_ref()
+    }
+}
+
+#[test]
+fn test_monitor() {
+    let code = super::encode(0x5A5);
+    let mut m = LinkMonitor::new(4);
+    m.ecc(code);
+    m.ecc(code ^ 0x100);
+    m.ecc(code ^ 0x801001);
+    assert_eq!(m.last_window(), None);
+    m.ecc(code ^ 0xF);
+
+    let s = m.last_window().unwrap();
+    assert_eq!((s.words, s.clean, s.corrected, s.detected), (4, 1, 2, 1));
+    assert_eq!(s.corrected_bits, 4);
+    assert!(s.syndrome_weight > 0);
+    assert_eq!(m.current().words, 0);
+}
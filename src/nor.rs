@@ -0,0 +1,155 @@
+//! NORフラッシュの12bitレコード
+//!
+//! 12bitのレコードを24bitのセル（3バイト）に符号語として書き込む．
+//! NORフラッシュは消去で全ビットが1になり，書き込みでは1→0にしかできない．
+//! 書き込み時の隣接セルへの干渉や，読み出しの閾値のずれで起きる誤りも多くは1→0なので，
+//! 読み出したときは「1→0の誤りだけが起きた」として訂正する．
+//!
+//! * 3bit以下の誤りは（向きによらず）`ecc`と同じく訂正する．
+//! * 1→0の誤りが4bitの場合も，受信語を覆う符号語がセクステットの候補の中に1つしかなければ訂正する．
+//!   1→0の誤りだけなら正しい符号語は必ず候補に入るので，誤訂正はしない．
+//!   ただし候補が複数あることが多く，訂正できるのは2割程度（残りは検出になる）．
+//!
+//! 消去したセル（0xFFFFFF）は全1の符号語なので，レコード0xFFFとして読める．
+//! 0xFFFは空きを表す値（`EMPTY`）として使い，カウンタや状態の値には使わないこと．
+
+/// 消去したセル
+pub const ERASED: u32 = 0xFFFFFF;
+
+/// 空きのレコード（消去したセルを読んだ値）
+pub const EMPTY: u16 = 0xFFF;
+
+/// 読み出したレコード
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// レコードの値（下位12bit）
+    pub data: u16,
+    /// 訂正したビット数
+    pub errors: u8,
+}
+
+impl Record {
+    /// 空き（消去したまま）のセルか
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data == EMPTY
+    }
+
+    /// 書き直したほうが良いか（誤りを訂正した）
+    #[inline]
+    pub fn needs_rewrite(&self) -> bool {
+        self.errors > 0
+    }
+}
+
+/// 書き込み後の読み出し確認の結果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verify {
+    /// 書き込んだとおりに読めた
+    Ok,
+    /// 誤りがあるが正しいレコードに訂正できる（訂正したビット数）
+    Marginal(u8),
+    /// 正しいレコードに戻せない．セルを消去して書き直すか，別のセルを使う．
+    Failed,
+}
+
+/// レコード（下位12bit）をセルに書き込む値にする．
+#[inline]
+pub fn encode(data: u16) -> u32 {
+    crate::encode(data)
+}
+
+/// セルに書き込む3バイト（上位バイトから）
+#[inline]
+pub fn to_bytes(data: u16) -> [u8; 3] {
+    let code = encode(data);
+    [(code >> 16) as u8, (code >> 8) as u8, code as u8]
+}
+
+/// 読み出したセルを訂正してレコードにする．訂正できなければNone．
+pub fn read(r: u32) -> Option<Record> {
+    correct(r).map(|(code, errors)| Record{ data: crate::decode(code), errors })
+}
+
+/// 読み出した3バイトを訂正してレコードにする．
+#[inline]
+pub fn read_bytes(bytes: [u8; 3]) -> Option<Record> {
+    read( ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32 )
+}
+
+/// 1→0の誤りを優先して訂正する．
+///
+/// * return: （符号語，訂正したビット数）
+pub fn correct(r: u32) -> Option<(u32, u8)> {
+    let r = r & 0xFFFFFF;
+    if let Some(c) = crate::ecc_verbose(r) {
+        return Some( (c.code, c.errors) );
+    }
+
+    // 4bitの誤り: 受信語で0のビットだけを1に戻す候補を探す
+    let mut found = None;
+    for e in crate::coset::candidates(r)?.iter() {
+        if e & r == 0 {
+            if found.is_some() {
+                return None;
+            }
+            found = Some(r ^ e);
+        }
+    }
+    found.map(|code| (code, 4))
+}
+
+/// 書き込んだ直後に読み出した値を確かめる．
+///
+/// * `data`: 書き込んだレコード
+/// * `read_back`: 読み出したセルの値
+pub fn verify(data: u16, read_back: u32) -> Verify {
+    let read_back = read_back & 0xFFFFFF;
+    if read_back == encode(data) {
+        return Verify::Ok;
+    }
+    match read(read_back) {
+        Some(rec) if rec.data == data & 0xFFF => Verify::Marginal(rec.errors),
+        _ => Verify::Failed,
+    }
+}
+
+#[test]
+fn test_nor() {
+    assert_eq!(read(ERASED), Some(Record{ data: EMPTY, errors: 0 }));
+    assert!(read(ERASED).unwrap().is_empty());
+
+    // 1→0の4bitの誤りは，訂正できる場合は必ず正しく訂正する
+    let mut ok = 0;
+    let mut total = 0;
+    for data in (0..4096).step_by(37) {
+        let code = encode(data);
+        let mut e = 0;
+        let mut n = 0;
+        for i in 0..24 {
+            if (code >> i) & 1 == 1 && n < 4 && (i * 7 + data as u32).is_multiple_of(3) {
+                e |= 1 << i;
+                n += 1;
+            }
+        }
+        if n < 4 {
+            continue;
+        }
+        total += 1;
+        if let Some(rec) = read(code & !e) {
+            assert_eq!(rec, Record{ data, errors: 4 });
+            ok += 1;
+        }
+    }
+    assert!(total > 50);
+    assert!(ok > 0 && ok < total);
+
+    // 読み出し確認
+    let data = 0x5C3;
+    let code = encode(data);
+    assert_eq!(verify(data, code), Verify::Ok);
+    assert_eq!(verify(data, code ^ 0x10), Verify::Marginal(1));
+    assert_eq!(verify(data, code ^ encode(0x001)), Verify::Failed);
+    let b = to_bytes(data);
+    assert_eq!(read_bytes(b), Some(Record{ data, errors: 0 }));
+}
@@ -0,0 +1,136 @@
+//! OTP/eFuseのフィールド
+//!
+//! 12bitの値を24bitの符号語にしてワンタイムプログラマブルなヒューズに書き込む．
+//! 未書き込みのヒューズは0で，書き込み（溶断）で1になり，0には戻せない．
+//!
+//! 読み出しの訂正には2つのモードがある．
+//! * `ErrorModel::Symmetric`: `ecc`と同じ．
+//! * `ErrorModel::ZeroToOne`: 誤りは0→1だけ（書き込んでいないヒューズが1に読める）として訂正する．
+//!   1→0の向きの訂正が必要になる受信語は訂正せずに検出とする．
+//!   0→1の誤りが4bitの場合も，候補が1つに絞れれば訂正する．
+//!
+//! 未書き込みのフィールドは0の符号語なので，値0として読める．
+
+/// 読み出しの誤りのモデル
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorModel {
+    /// 向きを区別しない
+    Symmetric,
+    /// 0→1の誤りだけ
+    ZeroToOne,
+}
+
+/// 読み出したフィールド
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// 値（下位12bit）
+    pub value: u16,
+    /// 訂正したビット数
+    pub errors: u8,
+}
+
+/// 値（下位12bit）をヒューズに書き込む符号語にする．
+#[inline]
+pub fn encode(value: u16) -> u32 {
+    crate::encode(value)
+}
+
+/// 書き込まれたヒューズに値を書き込むために溶断するビットを求める．
+///
+/// * `current`: 今のヒューズの値（下位24bit）
+/// * return: 新たに1にするビット．1→0が必要なら書き込めないのでNone．
+pub fn program_mask(current: u32, value: u16) -> Option<u32> {
+    let code = encode(value);
+    let current = current & 0xFFFFFF;
+    if current & !code != 0 {
+        return None;
+    }
+    Some(code & !current)
+}
+
+/// 書き込まれた値を別の値に書き換えられるか（ヒューズを追加で溶断するだけで済むか）
+#[inline]
+pub fn can_update(old: u16, new: u16) -> bool {
+    program_mask(encode(old), new).is_some()
+}
+
+/// 読み出したヒューズを訂正する．訂正できなければNone．
+pub fn read(r: u32, model: ErrorModel) -> Option<Field> {
+    correct(r, model).map(|(code, errors)| Field{ value: crate::decode(code), errors })
+}
+
+/// 読み出したヒューズを訂正する．
+///
+/// * return: （符号語，訂正したビット数）
+pub fn correct(r: u32, model: ErrorModel) -> Option<(u32, u8)> {
+    let r = r & 0xFFFFFF;
+    let c = crate::ecc_verbose(r);
+    if model == ErrorModel::Symmetric {
+        return c.map(|c| (c.code, c.errors));
+    }
+    if let Some(c) = c {
+        // 誤りは受信語で1になっているビットにしか無いはず
+        return if (c.code ^ r) & !r == 0 { Some( (c.code, c.errors) ) } else { None };
+    }
+
+    // 4bitの誤り: 受信語で1のビットだけを0に戻す候補を探す
+    let mut found = None;
+    for e in crate::coset::candidates(r)?.iter() {
+        if e & !r == 0 {
+            if found.is_some() {
+                return None;
+            }
+            found = Some(r ^ e);
+        }
+    }
+    found.map(|code| (code, 4))
+}
+
+#[test]
+fn test_otp() {
+    assert_eq!(read(0, ErrorModel::ZeroToOne), Some(Field{ value: 0, errors: 0 }));
+
+    let value = 0x6A5;
+    let code = encode(value);
+    assert_eq!(program_mask(0, value), Some(code));
+    assert_eq!(program_mask(code, value), Some(0));
+    assert_eq!(program_mask(0x800000 | encode(0), 0x000), None);
+    assert!(can_update(0, value));
+    assert!(can_update(value, 0xFFF));
+    assert!(!can_update(0xFFF, value));
+
+    // 0→1の誤り3bitはどちらのモードでも訂正できる
+    let zeros = !code & 0xFFFFFF;
+    let mut e = 0;
+    let mut z = zeros;
+    for _ in 0..3 {
+        e |= z & z.wrapping_neg();
+        z &= z - 1;
+    }
+    assert_eq!(read(code | e, ErrorModel::ZeroToOne), Some(Field{ value, errors: 3 }));
+    assert_eq!(read(code | e, ErrorModel::Symmetric), Some(Field{ value, errors: 3 }));
+
+    // 1→0の誤りはZeroToOneでは検出になる
+    let one = code & code.wrapping_neg();
+    assert_eq!(read(code & !one, ErrorModel::Symmetric), Some(Field{ value, errors: 1 }));
+    assert_eq!(read(code & !one, ErrorModel::ZeroToOne), None);
+
+    // 0→1の誤り4bitは，訂正する場合は必ず正しい
+    let mut corrected = 0;
+    for value in (0..4096).step_by(101) {
+        let code = encode(value);
+        let zeros = !code & 0xFFFFFF;
+        let mut e = 0;
+        let mut z = zeros;
+        for _ in 0..4 {
+            e |= z & z.wrapping_neg();
+            z &= z.wrapping_sub(1);
+        }
+        assert_eq!(read(code | e, ErrorModel::Symmetric), None);
+        if let Some(f) = read(code | e, ErrorModel::ZeroToOne) {
+            assert_eq!(f, Field{ value, errors: 4 });
+            corrected += 1;
+        }
+    }
+    assert!(corrected > 0);
+}
@@ -0,0 +1,188 @@
+//! スレッドとチャネルによる符号化・復号のパイプライン（`std`フィーチャ）
+//!
+//! 高速な保護付きシリアル回線とネットワークを繋ぐゲートウェイ向け．
+//! ワーカースレッドを立ててチャネルで繋ぎ，`send`で入れたものを`recv`で取り出す．
+//! ワーカーが複数あっても，`recv`は`send`した順に結果を返す．
+//!
+//! フレームの構成（符号語は3バイト，上位バイトから送る）
+//! * ペイロード長（バイト）の符号語
+//! * ペイロード（`protect`と同じく12bitずつ符号化）
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::vec::Vec;
+
+use crate::hosted::{protect_vec, recover_vec};
+use crate::protect::protected_len;
+
+/// ペイロードの最大長（バイト）
+pub const MAX_PAYLOAD_LEN: usize = 0xFFF;
+
+/// パイプラインのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipelineError {
+    /// ペイロードが長すぎる
+    TooLong,
+    /// フレームの長さが合わない
+    InvalidLength,
+    /// 訂正できない符号語がある
+    Uncorrectable,
+    /// 結果を待っているものが無い
+    Empty,
+    /// ワーカースレッドが止まった
+    Disconnected,
+}
+
+/// ペイロードをフレームにする．
+pub fn encode_frame(payload: &[u8]) -> Result<Vec<u8>, PipelineError> {
+    if payload.len() > MAX_PAYLOAD_LEN {
+        return Err(PipelineError::TooLong);
+    }
+    let mut frame = Vec::with_capacity(3 + protected_len(payload.len()));
+    frame.extend_from_slice(&crate::uart::to_bytes( crate::encode(payload.len() as u16) ));
+    frame.extend_from_slice(&protect_vec(payload));
+    Ok(frame)
+}
+
+/// フレームを訂正してペイロードに戻す．
+pub fn decode_frame(frame: &[u8]) -> Result<Vec<u8>, PipelineError> {
+    if frame.len() < 3 {
+        return Err(PipelineError::InvalidLength);
+    }
+    let header = crate::uart::from_bytes([frame[0], frame[1], frame[2]]);
+    let len = crate::ecc(header).ok_or(PipelineError::Uncorrectable)?;
+    let len = crate::decode(len) as usize;
+    let body = &frame[3..];
+    if body.len() != protected_len(len) {
+        return Err(PipelineError::InvalidLength);
+    }
+    recover_vec(body, len).map(|(payload, _)| payload).map_err(|_| PipelineError::Uncorrectable)
+}
+
+type Job = (u64, Vec<u8>);
+type Done = (u64, Result<Vec<u8>, PipelineError>);
+
+/// ワーカースレッドで符号化または復号するパイプライン
+pub struct Pipeline {
+    input: Option<Sender<Job>>,
+    output: Receiver<Done>,
+    workers: Vec<JoinHandle<()>>,
+    pending: BTreeMap<u64, Result<Vec<u8>, PipelineError>>,
+    sent: u64,
+    next: u64,
+}
+
+impl Pipeline {
+    /// ペイロードをフレームにするパイプラインを作る．
+    ///
+    /// * `threads`: ワーカースレッドの数（0なら1）
+    pub fn encoder(threads: usize) -> Self {
+        Self::spawn(threads, encode_frame)
+    }
+
+    /// フレームをペイロードに戻すパイプラインを作る．
+    ///
+    /// * `threads`: ワーカースレッドの数（0なら1）
+    pub fn decoder(threads: usize) -> Self {
+        Self::spawn(threads, decode_frame)
+    }
+
+    fn spawn(threads: usize, work: fn(&[u8]) -> Result<Vec<u8>, PipelineError>) -> Self {
+        let (input, jobs) = mpsc::channel::<Job>();
+        let (done, output) = mpsc::channel();
+        let jobs = Arc::new(Mutex::new(jobs));
+        let workers = (0..threads.max(1)).map(|_| {
+            let jobs = Arc::clone(&jobs);
+            let done = done.clone();
+            thread::spawn(move || loop {
+                // 受け取ったらすぐロックを外して，他のワーカーが次を受け取れるようにする
+                let job = jobs.lock().map_err(|_| ()).and_then(|j| j.recv().map_err(|_| ()));
+                let (seq, data) = match job {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+                if done.send((seq, work(&data))).is_err() {
+                    break;
+                }
+            })
+        }).collect();
+        Self{ input: Some(input), output, workers, pending: BTreeMap::new(), sent: 0, next: 0 }
+    }
+
+    /// ワーカーに渡す．
+    pub fn send(&mut self, payload: Vec<u8>) -> Result<(), PipelineError> {
+        let input = self.input.as_ref().ok_or(PipelineError::Disconnected)?;
+        input.send((self.sent, payload)).map_err(|_| PipelineError::Disconnected)?;
+        self.sent += 1;
+        Ok(())
+    }
+
+    /// `send`した順に結果を受け取る．結果が出るまで待つ．
+    ///
+    /// 待っているものが無ければ`PipelineError::Empty`．
+    pub fn recv(&mut self) -> Result<Vec<u8>, PipelineError> {
+        if self.next == self.sent {
+            return Err(PipelineError::Empty);
+        }
+        while !self.pending.contains_key(&self.next) {
+            let (seq, result) = self.output.recv().map_err(|_| PipelineError::Disconnected)?;
+            self.pending.insert(seq, result);
+        }
+        let result = self.pending.remove(&self.next).unwrap_or(Err(PipelineError::Disconnected));
+        self.next += 1;
+        result
+    }
+
+    /// 結果を受け取っていない数
+    #[inline]
+    pub fn in_flight(&self) -> u64 {
+        self.sent - self.next
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        // 送信側を閉じるとワーカーのループが終わる
+        self.input = None;
+        for w in self.workers.drain(..) {
+            let _ = w.join();
+        }
+    }
+}
+
+#[test]
+fn test_pipeline() {
+    use std::vec;
+
+    let payloads: Vec<Vec<u8>> = (0..50u8).map(|i| (0..i).map(|b| b.wrapping_mul(i)).collect()).collect();
+    let mut enc = Pipeline::encoder(4);
+    let mut dec = Pipeline::decoder(3);
+    assert_eq!(enc.recv(), Err(PipelineError::Empty));
+
+    for p in payloads.iter() {
+        enc.send(p.clone()).unwrap();
+    }
+    assert_eq!(enc.in_flight(), 50);
+    for (i, p) in payloads.iter().enumerate() {
+        let mut frame = enc.recv().unwrap();
+        assert_eq!(frame, encode_frame(p).unwrap());
+        let j = i % frame.len();
+        frame[j] ^= 0x21;
+        dec.send(frame).unwrap();
+    }
+    for p in payloads.iter() {
+        assert_eq!(dec.recv().as_ref(), Ok(p));
+    }
+    assert_eq!(dec.in_flight(), 0);
+
+    enc.send(vec![0; MAX_PAYLOAD_LEN + 1]).unwrap();
+    assert_eq!(enc.recv(), Err(PipelineError::TooLong));
+    let mut frame = encode_frame(b"gateway").unwrap();
+    dec.send(frame[..frame.len() - 1].to_vec()).unwrap();
+    frame[4] ^= 0xFF;
+    dec.send(frame).unwrap();
+    assert_eq!(dec.recv(), Err(PipelineError::InvalidLength));
+    assert_eq!(dec.recv(), Err(PipelineError::Uncorrectable));
+}
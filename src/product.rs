@@ -0,0 +1,226 @@
+//! ゴレイ符号×ゴレイ符号の積符号
+//!
+//! 12×12bit（144bit）のデータを24×24bitのブロックにする．
+//! データの各行を符号化した後，24列をそれぞれ符号化する．
+//! 符号が線形なので，検査部分の行（12〜23行目）も符号語になる．
+//!
+//! ブロックは24個の24bitの行で，i行j列は`block[i]`の bit (23 - j)．
+//! 復号は行と列の訂正を交互に繰り返す（硬判定）．
+//! 1行がまるごと壊れるようなバースト誤りでも，各列では1bitの誤りになるので訂正できる．
+//!
+//! 軟判定値がある場合は`decode_soft`で繰り返し復号する（ターボ積符号）．
+//! 行と列を交互に軟入力軟出力（SISO）で復号し，外部情報 W = 軟出力 - 入力 に α を掛けて
+//! 通信路の値に足したものを次の半繰り返しの入力にする．
+//! SISOには`chase::Chase`（Pyndiah）か`bcjr::Bcjr`（真の事後確率）を選べる．
+//! α と，競合語が無い場合の軟出力の大きさ β は Pyndiah の論文の値を使う．
+
+pub mod bcjr;
+pub mod chase;
+
+use crate::soft::SoftWord;
+
+/// 1ブロックのデータのビット数
+pub const DATA_BITS: usize = 144;
+
+/// 1ブロックのデータのバイト数
+pub const DATA_BYTES: usize = DATA_BITS / 8;
+
+/// 24×24bitのブロック
+pub type Block = [u32; 24];
+
+/// 1ブロック分の軟判定値．`block[i][j]`がi行j列．
+pub type SoftBlock = [SoftWord; 24];
+
+/// 外部情報の重み（半繰り返しごと）
+const ALPHA: [f32; 8] = [0.0, 0.2, 0.3, 0.5, 0.7, 0.9, 1.0, 1.0];
+
+/// 競合語が無い場合の軟出力の大きさ（半繰り返しごと）
+const BETA: [f32; 8] = [0.2, 0.4, 0.6, 0.8, 1.0, 1.0, 1.0, 1.0];
+
+/// 1語分の軟入力軟出力復号
+pub trait Siso {
+    /// 1語分の軟入力から軟出力を求める．値は正なら0，添字0がbit 23．
+    ///
+    /// * `y`: 軟入力．通信路の値は最大の大きさが1になるようにそろえてある．
+    /// * `beta`: 競合語が無い場合の軟出力の大きさ（使わない復号器もある）
+    fn siso(&mut self, y: &[f32; 24], beta: f32) -> [f32; 24];
+}
+
+/// j列目を24bitの語として取り出す（i行目が bit (23 - i)）．
+pub fn column(block: &Block, j: usize) -> u32 {
+    let mut c = 0;
+    for row in block.iter() {
+        c = (c << 1) | ((row >> (23 - j)) & 1);
+    }
+    c
+}
+
+/// j列目を書き換える．
+pub fn set_column(block: &mut Block, j: usize, c: u32) {
+    for (i, row) in block.iter_mut().enumerate() {
+        let bit = (c >> (23 - i)) & 1;
+        *row = (*row & !(1 << (23 - j))) | (bit << (23 - j));
+    }
+}
+
+/// 12行×12bitのデータを符号化する．
+pub fn encode(data: &[u16; 12]) -> Block {
+    let mut block = [0u32; 24];
+    for (row, d) in block.iter_mut().zip(data.iter()) {
+        *row = super::encode(*d);
+    }
+    for j in 0..24 {
+        // 上12行がデータ部分なので，列の上位12bitを符号化すれば良い
+        let c = super::encode( (column(&block, j) >> 12) as u16 );
+        set_column(&mut block, j, c);
+    }
+    block
+}
+
+/// 行と列の訂正を交互に行い，データを取り出す．ブロックは訂正した結果に書き換える．
+///
+/// * `iterations`: 行と列の訂正を繰り返す回数の上限
+/// * return: 全ての行と列が符号語になればデータ．ならなければNone．
+pub fn decode(block: &mut Block, iterations: usize) -> Option<[u16; 12]> {
+    for _ in 0..iterations {
+        let mut changed = false;
+        for row in block.iter_mut() {
+            if let Some(code) = super::ecc(*row) {
+                changed |= code != *row;
+                *row = code;
+            }
+        }
+        for j in 0..24 {
+            let c = column(block, j);
+            if let Some(code) = super::ecc(c) {
+                if code != c {
+                    set_column(block, j, code);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    if !is_valid(block) {
+        return None;
+    }
+    let mut data = [0u16; 12];
+    for (d, row) in data.iter_mut().zip(block.iter()) {
+        *d = super::decode(*row);
+    }
+    Some(data)
+}
+
+/// 全ての行と列が符号語になっているか確かめる．
+pub fn is_valid(block: &Block) -> bool {
+    block.iter().all(|row| super::syndrome(*row) == 0)
+        && (0..24).all(|j| super::syndrome(column(block, j)) == 0)
+}
+
+/// 軟判定値で積符号を繰り返し復号する．
+///
+/// * `channel`: 受信した軟判定値
+/// * `block`: 復号結果の硬判定を書き込む
+/// * `siso`: 行と列の復号に使う軟入力軟出力復号器
+/// * `iterations`: 行と列の組を繰り返す回数の上限
+/// * return: 全ての行と列が符号語になればデータ．ならなければNone．
+pub fn decode_soft<S: Siso>(channel: &SoftBlock, block: &mut Block, siso: &mut S, iterations: usize) -> Option<[u16; 12]> {
+    // 通信路の値を最大の大きさが1程度になるようにそろえる
+    let mut ch = [[0.0f32; 24]; 24];
+    for (c_row, s_row) in ch.iter_mut().zip(channel.iter()) {
+        for (c, s) in c_row.iter_mut().zip(s_row.iter()) {
+            *c = *s as f32 / 127.0;
+        }
+    }
+
+    let mut w = [[0.0f32; 24]; 24];
+    let mut soft = ch;
+    for half in 0..2 * iterations {
+        let h = half.min(ALPHA.len() - 1);
+        for i in 0..24 {
+            let mut y = [0.0f32; 24];
+            for (j, v) in y.iter_mut().enumerate() {
+                let (r, c) = if half % 2 == 0 { (i, j) } else { (j, i) };
+                *v = ch[r][c] + ALPHA[h] * w[r][c];
+            }
+            let out = siso.siso(&y, BETA[h]);
+            for j in 0..24 {
+                let (r, c) = if half % 2 == 0 { (i, j) } else { (j, i) };
+                w[r][c] = out[j] - y[j];
+                soft[r][c] = out[j];
+            }
+        }
+
+        for (row, s_row) in block.iter_mut().zip(soft.iter()) {
+            *row = s_row.iter().fold(0, |r, s| (r << 1) | (*s <= 0.0) as u32);
+        }
+        if half % 2 == 1 && is_valid(block) {
+            break;
+        }
+    }
+
+    if !is_valid(block) {
+        return None;
+    }
+    let mut data = [0u16; 12];
+    for (d, row) in data.iter_mut().zip(block.iter()) {
+        *d = super::decode(*row);
+    }
+    Some(data)
+}
+
+/// 18バイトを12行×12bitのデータにする（上位ビットから詰める）．
+pub fn data_from_bytes(bytes: &[u8; DATA_BYTES]) -> [u16; 12] {
+    let mut data = [0u16; 12];
+    for (i, d) in data.iter_mut().enumerate() {
+        let b = &bytes[i / 2 * 3..i / 2 * 3 + 3];
+        *d = if i % 2 == 0 {
+            ((b[0] as u16) << 4) | (b[1] >> 4) as u16
+        } else {
+            (((b[1] & 0xF) as u16) << 8) | b[2] as u16
+        };
+    }
+    data
+}
+
+/// 12行×12bitのデータを18バイトにする．
+pub fn data_to_bytes(data: &[u16; 12]) -> [u8; DATA_BYTES] {
+    let mut bytes = [0u8; DATA_BYTES];
+    for (pair, b) in data.chunks(2).zip(bytes.chunks_mut(3)) {
+        b[0] = (pair[0] >> 4) as u8;
+        b[1] = (((pair[0] & 0xF) << 4) | ((pair[1] >> 8) & 0xF)) as u8;
+        b[2] = pair[1] as u8;
+    }
+    bytes
+}
+
+#[test]
+fn test_product() {
+    let mut bytes = [0u8; DATA_BYTES];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = (i as u8).wrapping_mul(37) ^ 0x5A;
+    }
+    let data = data_from_bytes(&bytes);
+    assert_eq!(data_to_bytes(&data), bytes);
+
+    let block = encode(&data);
+    assert!(is_valid(&block));
+
+    // 1行がまるごと壊れ，さらに4bit誤りの行が2つある
+    let mut rx = block;
+    rx[5] ^= 0xFFFFFF;
+    rx[9] ^= 0x00F000;
+    rx[20] ^= 0x0000F0;
+    assert_eq!(decode(&mut rx, 4), Some(data));
+    assert_eq!(rx, block);
+
+    // 4×4の正方形の誤りは行でも列でも4bitになるので訂正できない
+    let mut rx = block;
+    for row in rx[2..6].iter_mut() {
+        *row ^= 0x0F0000;
+    }
+    assert_eq!(decode(&mut rx, 4), None);
+}
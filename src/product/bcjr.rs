@@ -0,0 +1,254 @@
+//! 符号トレリス上のBCJR復号（真の事後確率）
+//!
+//! 検査行列 H^T = [B ; I] のシンドロームを状態とするトレリス（Wolfのトレリス）で，
+//! 前向きと後ろ向きの確率を計算し，各ビットの事後確率の対数比を出力する．
+//!
+//! データビットを先に並べているので，前半12段では状態がデータビットだけで決まり（2^k 状態），
+//! 12段目で4096状態になった後，後半12段では検査ビットを1つ読むごとに状態が半分になる．
+//! 状態数が多いので，作業領域は約72KBになる．`Bcjr::new`はconst fnなので，
+//! `static`（`Mutex`などで包む）の初期化式に書けば，スタックに置かずに使える．
+//!
+//! no_stdで使えるように，指数関数と対数関数は近似式で計算する（相対誤差1e-6程度）．
+
+use super::Siso;
+
+/// 出力する対数尤度比の大きさの上限
+const MAX_LLR: f32 = 50.0;
+
+/// BCJR復号器
+pub struct Bcjr {
+    scale: f32,
+    syn: [u16; 4096],   // 前半のデータビットの並び（bit iが i段目）に対応する12段目の状態
+    a1: [f32; 4095],    // α_0〜α_11（α_kは 2^k - 1 から 2^k 個）
+    a12: [f32; 4096],   // α_12．後ろ向きの計算では前半の β に使う
+    a2: [f32; 4095],    // α_13〜α_24（α_kは 4096 - 2^(25-k) から 2^(24-k) 個）
+    beta: [f32; 4096],
+}
+
+impl Bcjr {
+    /// * `scale`: 軟入力に掛けて対数尤度比にする係数（通信路の信頼度）．出力はこれで割って返す．
+    pub const fn new(scale: f32) -> Self {
+        let mut syn = [0u16; 4096];
+        let mut idx = 0;
+        while idx < 4096 {
+            let mut i = 0;
+            while i < 12 {
+                if (idx >> i) & 1 == 1 {
+                    syn[idx] ^= crate::H_T[i] as u16;
+                }
+                i += 1;
+            }
+            idx += 1;
+        }
+        Self{ scale, syn, a1: [0.0; 4095], a12: [0.0; 4096], a2: [0.0; 4095], beta: [0.0; 4096] }
+    }
+
+    /// 1語分の事後確率の対数比 ln(P(0) / P(1)) を求める．添字0がbit 23．
+    ///
+    /// * `llr`: 各ビットの対数尤度比（正なら0）
+    pub fn app(&mut self, llr: &[f32; 24]) -> [f32; 24] {
+        // 各ビットの尤度（大きいほうを1にする）
+        let mut q = [[0.0f32; 2]; 24];
+        for (q_k, l) in q.iter_mut().zip(llr.iter()) {
+            *q_k = if *l >= 0.0 { [1.0, exp_neg(*l)] } else { [exp_neg(-*l), 1.0] };
+        }
+
+        // 前向き（前半）
+        self.a1[0] = 1.0;
+        for (k, q_k) in q.iter().enumerate().take(11) {
+            let n = 1 << k;
+            let (cur, next) = self.a1.split_at_mut(2 * n - 1);
+            for (idx, a) in cur[n - 1..].iter().enumerate() {
+                next[idx] = a * q_k[0];
+                next[idx + n] = a * q_k[1];
+            }
+            normalize(&mut next[..2 * n]);
+        }
+        for (idx, a) in self.a1[2047..].iter().enumerate() {
+            self.a12[self.syn[idx] as usize] = a * q[11][0];
+            self.a12[self.syn[idx + 2048] as usize] = a * q[11][1];
+        }
+        normalize(&mut self.a12);
+
+        // 前向き（後半）．状態の bit (23 - k) が k段目の検査ビットになる．
+        for (k, q_k) in q.iter().enumerate().skip(12) {
+            let h = 1 << (23 - k);
+            let next_off = 4096 - 2 * h;
+            for s in 0..h {
+                let (a0, a1) = if k == 12 {
+                    (self.a12[s], self.a12[s + h])
+                } else {
+                    let off = 4096 - 4 * h;
+                    (self.a2[off + s], self.a2[off + s + h])
+                };
+                self.a2[next_off + s] = a0 * q_k[0] + a1 * q_k[1];
+            }
+            normalize(&mut self.a2[next_off..next_off + h]);
+        }
+
+        let mut p = [[0.0f32; 2]; 24];
+
+        // 後ろ向き（後半）
+        self.beta[0] = 1.0;
+        for k in (12..24).rev() {
+            let h = 1 << (23 - k);
+            let alpha = if k == 12 { &self.a12[..] } else { &self.a2[4096 - 4 * h..4096 - 2 * h] };
+            for s in 0..h {
+                p[k][0] += alpha[s] * q[k][0] * self.beta[s];
+                p[k][1] += alpha[s + h] * q[k][1] * self.beta[s];
+            }
+            for s in 0..h {
+                self.beta[s + h] = self.beta[s] * q[k][1];
+                self.beta[s] *= q[k][0];
+            }
+            normalize(&mut self.beta[..2 * h]);
+        }
+
+        // 後ろ向き（前半）．β_12をデータビットの並びに直す．
+        for idx in 0..4096 {
+            self.a12[idx] = self.beta[self.syn[idx] as usize];
+        }
+        for k in (0..12).rev() {
+            let n = 1 << k;
+            let alpha = &self.a1[n - 1..2 * n - 1];
+            let beta = &mut self.a12;
+            for idx in 0..n {
+                p[k][0] += alpha[idx] * q[k][0] * beta[idx];
+                p[k][1] += alpha[idx] * q[k][1] * beta[idx + n];
+                beta[idx] = q[k][0] * beta[idx] + q[k][1] * beta[idx + n];
+            }
+            normalize(&mut beta[..n]);
+        }
+
+        let mut out = [0.0f32; 24];
+        for (o, p_k) in out.iter_mut().zip(p.iter()) {
+            *o = ln_ratio(p_k[0], p_k[1]);
+        }
+        out
+    }
+}
+
+impl Siso for Bcjr {
+    fn siso(&mut self, y: &[f32; 24], _beta: f32) -> [f32; 24] {
+        let mut llr = [0.0f32; 24];
+        for (l, v) in llr.iter_mut().zip(y.iter()) {
+            *l = *v * self.scale;
+        }
+        let mut out = self.app(&llr);
+        for o in out.iter_mut() {
+            *o /= self.scale;
+        }
+        out
+    }
+}
+
+/// 和が1になるように割る．
+fn normalize(x: &mut [f32]) {
+    let sum: f32 = x.iter().sum();
+    if sum > 0.0 {
+        for v in x.iter_mut() {
+            *v /= sum;
+        }
+    }
+}
+
+/// e^(-x)（x ≥ 0）
+fn exp_neg(x: f32) -> f32 {
+    if x > 87.0 {
+        return 0.0;
+    }
+    // e^(-x) = 2^(-n) × e^(-f・ln2)，0 ≤ f < 1
+    let t = x * core::f32::consts::LOG2_E;
+    let n = t as i32;
+    let f = (t - n as f32) * core::f32::consts::LN_2;
+    let mut term = 1.0f32;
+    let mut sum = 1.0f32;
+    for i in 1..9 {
+        term *= -f / i as f32;
+        sum += term;
+    }
+    sum * f32::from_bits(((127 - n) as u32) << 23)
+}
+
+/// 自然対数（x > 0 の正規化数）
+fn ln(x: f32) -> f32 {
+    let bits = x.to_bits();
+    let mut e = ((bits >> 23) & 0xFF) as i32 - 127;
+    let mut m = f32::from_bits((bits & 0x7FFFFF) | (127 << 23));
+    if m > core::f32::consts::SQRT_2 {
+        m /= 2.0;
+        e += 1;
+    }
+    // ln(m) = 2・atanh(z)，z = (m - 1) / (m + 1)
+    let z = (m - 1.0) / (m + 1.0);
+    let z2 = z * z;
+    let series = z * (1.0 + z2 * (1.0 / 3.0 + z2 * (1.0 / 5.0 + z2 * (1.0 / 7.0 + z2 / 9.0))));
+    e as f32 * core::f32::consts::LN_2 + 2.0 * series
+}
+
+/// ln(p0 / p1) を±`MAX_LLR`に収める．
+fn ln_ratio(p0: f32, p1: f32) -> f32 {
+    if p1 <= f32::MIN_POSITIVE {
+        return MAX_LLR;
+    }
+    if p0 <= f32::MIN_POSITIVE {
+        return -MAX_LLR;
+    }
+    (ln(p0) - ln(p1)).clamp(-MAX_LLR, MAX_LLR)
+}
+
+#[test]
+fn test_bcjr() {
+    assert!((exp_neg(1.0) - 0.367_879_4).abs() < 1e-6);
+    assert!((ln(10.0) - core::f32::consts::LN_10).abs() < 1e-5);
+
+    // 全ての符号語について和をとった事後確率と比べる
+    let code = crate::encode(0xB4E);
+    let mut llr = [0.0f32; 24];
+    for (k, l) in llr.iter_mut().enumerate() {
+        let sign = if (code >> (23 - k)) & 1 == 0 { 1.0 } else { -1.0 };
+        *l = sign * (0.5 + (k % 5) as f32 * 0.7);
+    }
+    llr[3] = -llr[3] * 0.4;
+    llr[17] = -llr[17] * 0.2;
+    let mut bcjr = Bcjr::new(1.0);
+    let out = bcjr.app(&llr);
+    for (k, o) in out.iter().enumerate() {
+        let mut p = [0.0f32; 2];
+        for data in 0..4096 {
+            let c = crate::encode(data);
+            // 硬判定と異なるビットの|L|の和が小さいほど確からしい
+            let mut m = 0.0;
+            for (i, l) in llr.iter().enumerate() {
+                let bit = (c >> (23 - i)) & 1;
+                if (bit == 1) != (*l < 0.0) {
+                    m += l.abs();
+                }
+            }
+            p[((c >> (23 - k)) & 1) as usize] += exp_neg(m);
+        }
+        assert!((o - ln_ratio(p[0], p[1])).abs() < 1e-3, "bit {}: {} {}", k, o, ln_ratio(p[0], p[1]));
+        assert_eq!(*o < 0.0, (code >> (23 - k)) & 1 == 1);
+    }
+
+    // 積符号の繰り返し復号に使う
+    let data = [0x123, 0x456, 0x789, 0xABC, 0xDEF, 0x000, 0xFFF, 0x5A5, 0xA5A, 0x3C3, 0xC3C, 0x999];
+    let tx = super::encode(&data);
+    let mut channel = [[0i8; 24]; 24];
+    for (s_row, row) in channel.iter_mut().zip(tx.iter()) {
+        for (j, s) in s_row.iter_mut().enumerate() {
+            *s = if (row >> (23 - j)) & 1 == 0 { 90 } else { -90 };
+        }
+    }
+    for row in channel[10..14].iter_mut() {
+        for s in row[0..4].iter_mut() {
+            *s = -*s / 8;
+        }
+    }
+    let mut block = [0u32; 24];
+    let mut bcjr = Bcjr::new(4.0);
+    // constで作ったものと同じ
+    static SHARED: Bcjr = Bcjr::new(4.0);
+    assert_eq!(SHARED.syn[..], bcjr.syn[..]);
+    assert_eq!(super::decode_soft(&channel, &mut block, &mut bcjr, 4), Some(data));
+}
@@ -0,0 +1,133 @@
+//! Chase–Pyndiah 法の軟入力軟出力復号
+//!
+//! Pyndiah のターボ積符号の復号で使う，Chase 復号（Chase-II）による軟出力．
+//!
+//! * Chase 復号: 信頼度の低い`p`ビットの反転を全て試して`ecc`で訂正し，候補の符号語を集める．
+//!   受信値との相関が最も大きい候補を判定結果 D とする．
+//! * 軟出力: 各ビットについて，D とそのビットが異なる候補のうち最良のもの（競合語 C）を探し，
+//!   ((|R - C|^2 - |R - D|^2) / 4)・d_j を軟出力にする．競合語が無ければ β・d_j．
+//!
+//! 出力は対数尤度比ではなく，候補の相関の差なので，`Siso`として積符号の繰り返し復号に使う．
+
+use super::Siso;
+
+/// 反転を試すビット数の上限
+pub const MAX_TEST_BITS: usize = 6;
+
+/// Chase 復号器
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chase {
+    test_bits: usize,
+}
+
+impl Chase {
+    /// * `test_bits`: 反転を試す信頼度の低いビットの数（1〜`MAX_TEST_BITS`）．2^test_bits 個の候補を作る．
+    pub fn new(test_bits: usize) -> Option<Self> {
+        if test_bits == 0 || test_bits > MAX_TEST_BITS {
+            return None;
+        }
+        Some( Self{ test_bits } )
+    }
+}
+
+impl Siso for Chase {
+    fn siso(&mut self, y: &[f32; 24], beta: f32) -> [f32; 24] {
+        // 硬判定と，信頼度の低いビットの位置
+        let mut z = 0u32;
+        for v in y.iter() {
+            z = (z << 1) | (*v <= 0.0) as u32;
+        }
+        let mut weak = [0usize; MAX_TEST_BITS];
+        let mut used = 0u32;
+        for w in weak[..self.test_bits].iter_mut() {
+            let mut min = f32::INFINITY;
+            for (k, v) in y.iter().enumerate() {
+                let a = if *v < 0.0 { -*v } else { *v };
+                if (used >> k) & 1 == 0 && a < min {
+                    min = a;
+                    *w = k;
+                }
+            }
+            used |= 1 << *w;
+        }
+
+        // 候補の符号語と，硬判定と異なるビットの信頼度の和（小さいほど良い）
+        let mut cands = [(0u32, f32::INFINITY); 1 << MAX_TEST_BITS];
+        for (t, cand) in cands[..1 << self.test_bits].iter_mut().enumerate() {
+            let mut flip = 0;
+            for (b, k) in weak[..self.test_bits].iter().enumerate() {
+                flip |= (((t >> b) & 1) as u32) << (23 - k);
+            }
+            if let Some(code) = crate::ecc(z ^ flip) {
+                *cand = (code, metric(y, code ^ z));
+            }
+        }
+        let cands = &cands[..1 << self.test_bits];
+
+        let mut out = [0.0f32; 24];
+        let (d, m_d) = match cands.iter().min_by(|a, b| a.1.total_cmp(&b.1)) {
+            Some(&(d, m)) if m.is_finite() => (d, m),
+            _ => {
+                // 候補が無ければ入力をそのまま返す
+                out.copy_from_slice(y);
+                return out;
+            },
+        };
+        for (k, o) in out.iter_mut().enumerate() {
+            let mask = 1 << (23 - k);
+            let sign = if d & mask == 0 { 1.0 } else { -1.0 };
+            let m_c = cands.iter()
+                .filter(|c| (c.0 ^ d) & mask != 0)
+                .fold(f32::INFINITY, |m, c| if c.1 < m { c.1 } else { m });
+            *o = if m_c.is_finite() { (m_c - m_d) * sign } else { beta * sign };
+        }
+        out
+    }
+}
+
+/// 硬判定と異なるビットの信頼度の和
+fn metric(y: &[f32; 24], diff: u32) -> f32 {
+    let mut m = 0.0;
+    for (k, v) in y.iter().enumerate() {
+        if (diff >> (23 - k)) & 1 == 1 {
+            m += if *v < 0.0 { -*v } else { *v };
+        }
+    }
+    m
+}
+
+#[test]
+fn test_chase() {
+    let mut data = [0u16; 12];
+    for (i, d) in data.iter_mut().enumerate() {
+        *d = (i as u16 * 0x2F1) & 0xFFF;
+    }
+    let tx = super::encode(&data);
+
+    let mut channel = [[0i8; 24]; 24];
+    for (s_row, row) in channel.iter_mut().zip(tx.iter()) {
+        for (j, s) in s_row.iter_mut().enumerate() {
+            *s = if (row >> (23 - j)) & 1 == 0 { 100 } else { -100 };
+        }
+    }
+    // 4×4の正方形の誤り（硬判定の繰り返し復号では訂正できない）を弱い値で入れ，
+    // 強い誤りも1つ入れる
+    for row in channel[2..6].iter_mut() {
+        for s in row[8..12].iter_mut() {
+            *s = -*s / 10;
+        }
+    }
+    channel[15][3] = -channel[15][3];
+
+    let mut hard = [0u32; 24];
+    for (h, s_row) in hard.iter_mut().zip(channel.iter()) {
+        *h = crate::soft::hard_decision(s_row);
+    }
+    assert_eq!(super::decode(&mut hard.clone(), 8), None);
+
+    let mut block = [0u32; 24];
+    let mut chase = Chase::new(4).unwrap();
+    assert_eq!(super::decode_soft(&channel, &mut block, &mut chase, 4), Some(data));
+    assert_eq!(block, tx);
+    assert_eq!(Chase::new(7), None);
+}
@@ -0,0 +1,125 @@
+//! サイクルカウンタによる処理時間の計測（`profiling`フィーチャ）
+//!
+//! 符号化と復号の前後で利用者が渡したサイクルカウンタ（Cortex-MのDWT_CYCCNTなど）を読み，
+//! 処理時間の最小・平均・最大を積算する．外部のプロファイラを使わずに，
+//! 実機でリアルタイムの予算に収まっているかを確かめるためのもの．
+//!
+//! カウンタは32bitで一周してもよい（差は`wrapping_sub`でとる）．
+//! 計測値にはカウンタを読む時間も含まれる．
+
+use crate::decoder::Decoder;
+
+/// 処理時間の統計（単位はカウンタの値）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Latency {
+    /// 計測した回数
+    pub count: u32,
+    /// 最小
+    pub min: u32,
+    /// 最大
+    pub max: u32,
+    /// 合計
+    pub total: u64,
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Self{ count: 0, min: u32::MAX, max: 0, total: 0 }
+    }
+}
+
+impl Latency {
+    /// 1回分の時間を加える．
+    #[inline]
+    pub fn add(&mut self, cycles: u32) {
+        self.count += 1;
+        self.min = self.min.min(cycles);
+        self.max = self.max.max(cycles);
+        self.total += cycles as u64;
+    }
+
+    /// 平均．計測していなければ0．
+    #[inline]
+    pub fn mean(&self) -> u32 {
+        self.total.checked_div(self.count as u64).unwrap_or(0) as u32
+    }
+}
+
+/// サイクルカウンタを読みながら符号化と復号を行う．
+pub struct Profiler<C: FnMut() -> u32> {
+    counter: C,
+    encode: Latency,
+    decode: Latency,
+}
+
+impl<C: FnMut() -> u32> Profiler<C> {
+    /// * `counter`: サイクルカウンタの値を返す関数
+    pub fn new(counter: C) -> Self {
+        Self{ counter, encode: Latency::default(), decode: Latency::default() }
+    }
+
+    /// 時間を測りながら符号化する．
+    #[inline]
+    pub fn encode(&mut self, data: u16) -> u32 {
+        let start = (self.counter)();
+        let code = crate::encode(data);
+        self.encode.add( (self.counter)().wrapping_sub(start) );
+        code
+    }
+
+    /// 時間を測りながら誤り訂正を行う．訂正できない場合はNone．
+    #[inline]
+    pub fn ecc(&mut self, r: u32) -> Option<u32> {
+        self.ecc_with(&crate::decoder::Arithmetic, r)
+    }
+
+    /// 復号器を指定して，時間を測りながら誤り訂正を行う．
+    #[inline]
+    pub fn ecc_with<D: Decoder>(&mut self, decoder: &D, r: u32) -> Option<u32> {
+        let start = (self.counter)();
+        let code = decoder.ecc(r);
+        self.decode.add( (self.counter)().wrapping_sub(start) );
+        code
+    }
+
+    /// 符号化の時間
+    #[inline]
+    pub fn encode_latency(&self) -> &Latency {
+        &self.encode
+    }
+
+    /// 誤り訂正の時間
+    #[inline]
+    pub fn decode_latency(&self) -> &Latency {
+        &self.decode
+    }
+
+    /// 統計を消す．
+    pub fn reset(&mut self) {
+        self.encode = Latency::default();
+        self.decode = Latency::default();
+    }
+}
+
+#[test]
+fn test_profile() {
+    // 読むたびに進み幅が増える（0, 1, 3, 6, 10, ...）カウンタ．上位で一周させる．
+    let mut t = u32::MAX - 2;
+    let mut step = 0;
+    let mut p = Profiler::new(|| {
+        t = t.wrapping_add(step);
+        step += 1;
+        t
+    });
+    let code = p.encode(0x3A7);
+    assert_eq!(p.ecc(code ^ 0x10001), Some(code));
+    assert_eq!(p.ecc(code ^ 0xF), None);
+    let e = *p.encode_latency();
+    assert_eq!((e.count, e.min, e.max, e.mean()), (1, 1, 1, 1));
+    let d = *p.decode_latency();
+    assert_eq!((d.count, d.min, d.max, d.total, d.mean()), (2, 3, 5, 8, 4));
+
+    p.reset();
+    assert_eq!(p.decode_latency().mean(), 0);
+    assert_eq!(*p.encode_latency(), Latency::default());
+}
@@ -0,0 +1,249 @@
+//! 構造体の保護
+//!
+//! 構造体を直列化したバイト列を12bitずつに分けて符号化する．
+//! EEPROMやフラッシュに置く設定ブロック向け．
+//!
+//! 直列化はフィールド順にリトルエンディアンで詰める．
+//! 12bitのデータ1つにつき3バイトの符号語になるので，保護後の大きさは元の約2倍になる．
+//!
+//! `derive`フィーチャを有効にすると`#[derive(GolayProtect)]`で実装を生成できる．
+
+#[cfg(feature = "derive")]
+pub use golay_code_derive::GolayProtect;
+
+/// 直列化したバイト数から保護後のバイト数を求める．
+#[inline]
+pub const fn protected_len(raw_len: usize) -> usize {
+    (raw_len * 8).div_ceil(12) * 3
+}
+
+/// 直列化したバイト列を符号化しながら書き込む．
+pub struct RawWriter<'a> {
+    out: &'a mut [u8],
+    pos: usize,     // 書き込んだバイト数
+    bits: u32,      // 符号化待ちのビット
+    n_bits: u32,
+    overflow: bool,
+}
+
+impl<'a> RawWriter<'a> {
+    pub(crate) fn new(out: &'a mut [u8]) -> Self {
+        Self{ out, pos: 0, bits: 0, n_bits: 0, overflow: false }
+    }
+
+    fn put_code(&mut self, a: u16) {
+        let code = super::encode(a);
+        match self.out.get_mut(self.pos..self.pos + 3) {
+            Some(dst) => dst.copy_from_slice(&code.to_be_bytes()[1..]),
+            None => self.overflow = true,
+        }
+        self.pos += 3;
+    }
+
+    /// バイト列を書き込む．
+    pub fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.bits = (self.bits << 8) | *b as u32;
+            self.n_bits += 8;
+            if self.n_bits >= 12 {
+                self.n_bits -= 12;
+                let a = (self.bits >> self.n_bits) as u16 & 0xFFF;
+                self.put_code(a);
+            }
+        }
+    }
+
+    /// 残りのビットを0で埋めて書き込む．
+    pub(crate) fn finish(mut self) -> Option<usize> {
+        if self.n_bits > 0 {
+            let a = (self.bits << (12 - self.n_bits)) as u16 & 0xFFF;
+            self.put_code(a);
+        }
+        if self.overflow {
+            None
+        } else {
+            Some(self.pos)
+        }
+    }
+}
+
+/// 保護されたバイト列を訂正しながら読み出す．
+pub struct RawReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,     // 読み出したバイト数
+    bits: u32,      // 取り出し待ちのビット
+    n_bits: u32,
+    pub(crate) failed: bool,
+    pub(crate) corrected: u32,  // 訂正したビット数
+}
+
+impl<'a> RawReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self{ bytes, pos: 0, bits: 0, n_bits: 0, failed: false, corrected: 0 }
+    }
+
+    /// バイト列を読み出す．
+    ///
+    /// 訂正できない符号語があった場合は最後に失敗として扱う．
+    pub fn read(&mut self, buf: &mut [u8]) {
+        for b in buf.iter_mut() {
+            if self.n_bits < 8 {
+                let a = match self.bytes.get(self.pos..self.pos + 3) {
+                    Some(c) => {
+                        let r = u32::from_be_bytes([0, c[0], c[1], c[2]]);
+                        match super::ecc_verbose(r) {
+                            Some(c) => {
+                                self.corrected += c.errors as u32;
+                                super::decode(c.code)
+                            },
+                            None => {
+                                self.failed = true;
+                                0
+                            },
+                        }
+                    },
+                    None => {
+                        self.failed = true;
+                        0
+                    },
+                };
+                self.pos += 3;
+                self.bits = (self.bits << 12) | a as u32;
+                self.n_bits += 12;
+            }
+            self.n_bits -= 8;
+            *b = (self.bits >> self.n_bits) as u8;
+        }
+    }
+}
+
+/// 符号化して保護できる型
+pub trait GolayProtect: Sized {
+    /// 直列化したバイト数
+    const RAW_LEN: usize;
+
+    /// 直列化して書き込む．
+    fn write_raw(&self, w: &mut RawWriter);
+
+    /// 直列化したものを読み出す．
+    fn read_raw(r: &mut RawReader) -> Self;
+
+    /// 保護後のバイト数
+    #[inline]
+    fn protected_len() -> usize {
+        protected_len(Self::RAW_LEN)
+    }
+
+    /// 保護したバイト列を書き込む．
+    ///
+    /// * return: 書き込んだバイト数．`out`が足りなければNone．
+    fn to_protected_bytes(&self, out: &mut [u8]) -> Option<usize> {
+        let mut w = RawWriter::new(out);
+        self.write_raw(&mut w);
+        w.finish()
+    }
+
+    /// 保護したバイト列を訂正して読み出す．
+    ///
+    /// 訂正できない符号語があるか，長さが足りなければNone．
+    fn from_protected_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = RawReader::new(bytes);
+        let value = Self::read_raw(&mut r);
+        if r.failed {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => {$(
+        impl GolayProtect for $t {
+            const RAW_LEN: usize = core::mem::size_of::<$t>();
+
+            #[inline]
+            fn write_raw(&self, w: &mut RawWriter) {
+                w.write(&self.to_le_bytes());
+            }
+
+            #[inline]
+            fn read_raw(r: &mut RawReader) -> Self {
+                let mut buf = [0u8; core::mem::size_of::<$t>()];
+                r.read(&mut buf);
+                <$t>::from_le_bytes(buf)
+            }
+        }
+    )*};
+}
+
+impl_primitive!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl GolayProtect for bool {
+    const RAW_LEN: usize = 1;
+
+    #[inline]
+    fn write_raw(&self, w: &mut RawWriter) {
+        w.write(&[*self as u8]);
+    }
+
+    #[inline]
+    fn read_raw(r: &mut RawReader) -> Self {
+        let mut buf = [0u8];
+        r.read(&mut buf);
+        buf[0] != 0
+    }
+}
+
+impl<T: GolayProtect + Copy + Default, const N: usize> GolayProtect for [T; N] {
+    const RAW_LEN: usize = T::RAW_LEN * N;
+
+    fn write_raw(&self, w: &mut RawWriter) {
+        for x in self.iter() {
+            x.write_raw(w);
+        }
+    }
+
+    fn read_raw(r: &mut RawReader) -> Self {
+        let mut out = [T::default(); N];
+        for x in out.iter_mut() {
+            *x = T::read_raw(r);
+        }
+        out
+    }
+}
+
+#[test]
+fn test_protect() {
+    use golay_code_derive::GolayProtect;
+
+    #[derive(GolayProtect, Debug, PartialEq)]
+    struct Config {
+        id: u16,
+        gain: f32,
+        enabled: bool,
+        pub table: [i8; 3],
+    }
+
+    #[derive(GolayProtect, Debug, PartialEq)]
+    struct Block(u8, Config);
+
+    let block = Block(7, Config{ id: 0xBEEF, gain: 1.5, enabled: true, table: [-1, 0, 100] });
+    assert_eq!(Block::RAW_LEN, 1 + 2 + 4 + 1 + 3);
+    assert_eq!(Block::protected_len(), 24);
+
+    let mut buf = [0u8; 24];
+    assert_eq!(block.to_protected_bytes(&mut buf), Some(24));
+
+    // 各符号語に3bitずつ誤り
+    for i in 0..8 {
+        buf[3 * i] ^= 0b1000_0001;
+        buf[3 * i + 2] ^= 0b0001_0000;
+    }
+    assert_eq!(Block::from_protected_bytes(&buf), Some(block));
+
+    buf[0] ^= 0b0100_0000;  // 4bit誤り
+    assert_eq!(Block::from_protected_bytes(&buf), None);
+    assert_eq!(Block::from_protected_bytes(&buf[..21]), None);
+    assert_eq!(0u32.to_protected_bytes(&mut buf[..5]), None);
+}
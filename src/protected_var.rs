@@ -0,0 +1,138 @@
+//! 2重化した保護変数（seqlock方式）
+//!
+//! 値を符号化したものを2つの領域に交互に書き込み，最後に書いた領域をシーケンス番号で示す．
+//! 読み出し中に書き込みで割り込まれても，読み直すことで一貫した値を得られる．
+//! マイコンのメインループと割り込みハンドラで共有する変数向け．
+//!
+//! 書き込むのは1か所（メインループか割り込みハンドラのどちらか）だけにすること．
+//! シーケンス番号も12bitに切り詰めて符号化しておく．
+
+use core::marker::PhantomData;
+use core::sync::atomic::{fence, AtomicU32, AtomicU8, Ordering};
+
+use super::protect::GolayProtect;
+
+/// 2重化した保護変数
+///
+/// `N`は`T`を保護した後のバイト数（`T::protected_len()`）にする．
+pub struct ProtectedVar<T, const N: usize> {
+    seq: AtomicU32,  // 符号化したシーケンス番号
+    slots: [[AtomicU8; N]; 2],
+    _marker: PhantomData<T>,
+}
+
+impl<T: GolayProtect, const N: usize> ProtectedVar<T, N> {
+    /// 初期値を書き込んだ変数を作る．
+    ///
+    /// `N`が`T::protected_len()`と違う場合はpanicする．
+    pub fn new(value: T) -> Self {
+        assert_eq!(N, T::protected_len());
+        let var = Self{
+            seq: AtomicU32::new( super::encode(0) ),
+            slots: [[0; N].map(AtomicU8::new), [0; N].map(AtomicU8::new)],
+            _marker: PhantomData,
+        };
+        var.store_slot(0, &value);
+        var.store_slot(1, &value);
+        var
+    }
+
+    fn store_slot(&self, slot: usize, value: &T) {
+        let mut buf = [0u8; N];
+        value.to_protected_bytes(&mut buf);
+        for (dst, b) in self.slots[slot].iter().zip(buf.iter()) {
+            dst.store(*b, Ordering::Relaxed);
+        }
+    }
+
+    fn load_slot(&self, slot: usize) -> [u8; N] {
+        let mut buf = [0u8; N];
+        for (b, src) in buf.iter_mut().zip(self.slots[slot].iter()) {
+            *b = src.load(Ordering::Relaxed);
+        }
+        buf
+    }
+
+    /// シーケンス番号を読み出す．訂正できなければNone．
+    fn load_seq(&self) -> Option<u16> {
+        super::ecc( self.seq.load(Ordering::Acquire) ).map(super::decode)
+    }
+
+    /// 値を書き込む．
+    ///
+    /// 今読まれていない方の領域に書いてから，シーケンス番号を進める．
+    pub fn write(&self, value: &T) {
+        let seq = self.load_seq().unwrap_or(0);
+        let next = (seq + 1) & 0xFFF;
+        self.store_slot((next & 1) as usize, value);
+        self.seq.store(super::encode(next), Ordering::Release);
+    }
+
+    /// 最後に書き込んだ値を読み出す．
+    ///
+    /// 読み出し中に書き込まれた場合は読み直す．
+    /// 符号語かシーケンス番号を訂正できなければNone．
+    pub fn read(&self) -> Option<T> {
+        loop {
+            let seq = self.load_seq()?;
+            let buf = self.load_slot((seq & 1) as usize);
+            fence(Ordering::Acquire);
+            if self.load_seq() == Some(seq) {
+                return T::from_protected_bytes(&buf);
+            }
+        }
+    }
+
+    /// 1つ前に書き込んだ値を読み出す．
+    ///
+    /// `read`が訂正できずにNoneを返した場合の予備として使う．
+    pub fn read_previous(&self) -> Option<T> {
+        loop {
+            let seq = self.load_seq()?;
+            let buf = self.load_slot((!seq & 1) as usize);
+            fence(Ordering::Acquire);
+            if self.load_seq() == Some(seq) {
+                return T::from_protected_bytes(&buf);
+            }
+        }
+    }
+
+    /// 各領域の符号語を訂正して書き戻す．
+    ///
+    /// 書き込みと同じところ（同じ側）から呼ぶこと．
+    pub fn scrub(&self) {
+        if let Some(seq) = self.load_seq() {
+            self.seq.store(super::encode(seq), Ordering::Release);
+        }
+        for slot in 0..2 {
+            if let Some(value) = T::from_protected_bytes(&self.load_slot(slot)) {
+                self.store_slot(slot, &value);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_protected_var() {
+    let var: ProtectedVar<u32, 9> = ProtectedVar::new(0xDEADBEEF);
+    assert_eq!(var.read(), Some(0xDEADBEEF));
+    var.write(&12345);
+    assert_eq!(var.read(), Some(12345));
+    assert_eq!(var.read_previous(), Some(0xDEADBEEF));
+
+    // 現在の領域を壊す
+    let seq = var.load_seq().unwrap() as usize;
+    var.slots[seq & 1][0].fetch_xor(0b1110_0001, Ordering::Relaxed);
+    assert_eq!(var.read(), None);
+    assert_eq!(var.read_previous(), Some(0xDEADBEEF));
+
+    // 領域の誤りを3bitに戻し，シーケンス番号にも3bit誤り
+    var.slots[seq & 1][0].fetch_xor(0b1000_0000, Ordering::Relaxed);
+    var.seq.fetch_xor(0x800101, Ordering::Relaxed);
+    var.scrub();
+    assert_eq!(var.seq.load(Ordering::Relaxed), super::encode(seq as u16));
+    let mut expected = [0u8; 9];
+    12345u32.to_protected_bytes(&mut expected);
+    assert_eq!(var.load_slot(seq & 1), expected);
+    assert_eq!(var.read(), Some(12345));
+}
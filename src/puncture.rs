@@ -0,0 +1,114 @@
+//! パンクチャド符号
+//!
+//! 送信側で一部のパリティビットを削除して符号化率を上げる．
+//! 受信側では削除したビットを消失として扱って訂正する．
+//!
+//! 削除ビット数をpとすると，2×誤り数 + p < 8 の範囲で訂正できる．
+
+/// 削除できるパリティビット数の上限．
+///
+/// 8bit以上削除すると誤りが無くても復号できなくなる．
+pub const MAX_PUNCTURED: u32 = 7;
+
+/// パンクチャパターン
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Puncture {
+    mask: u32,  // 削除するビット位置（符号語の下位12bit）
+}
+
+impl Puncture {
+    /// パンクチャパターンを作る．
+    ///
+    /// * `mask`: 削除するパリティビットの位置（下位12bit）．
+    ///     * 符号語の下位12bitがパリティビットなので，ビット位置は符号語と同じ．
+    /// * return: 上位4bitが立っている場合や，削除ビット数が`MAX_PUNCTURED`を超える場合はNone．
+    pub fn new(mask: u16) -> Option<Self> {
+        if mask > 0xFFF || (mask as u32).count_ones() > MAX_PUNCTURED {
+            return None;
+        }
+        Some( Self{ mask: mask as u32 } )
+    }
+
+    /// 削除するビット位置（符号語の下位12bit）
+    #[inline]
+    pub fn mask(&self) -> u16 {
+        self.mask as u16
+    }
+
+    /// パンクチャ後の語長（bit）
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        24 - self.mask.count_ones()
+    }
+
+    /// 符号語から削除位置のビットを取り除く．
+    ///
+    /// 残ったビットを順序を保ったまま下位`bits()`bitに詰める．
+    pub fn puncture(&self, code: u32) -> u32 {
+        compress(code, !self.mask & 0xFFFFFF)
+    }
+
+    /// 受信語を24bitに戻す．削除位置には0が入る．
+    pub fn depuncture(&self, r: u32) -> u32 {
+        expand(r, !self.mask & 0xFFFFFF)
+    }
+
+    /// 12bitのデータをパンクチャした符号語に変換する．
+    #[inline]
+    pub fn encode(&self, a: u16) -> u32 {
+        self.puncture( super::encode(a) )
+    }
+
+    /// パンクチャした受信語のエラー検出と訂正を行う．
+    ///
+    /// * `r`: 受信語（下位`bits()`bit）
+    /// * return: 訂正した24bitの符号語．訂正できなければNone．
+    #[inline]
+    pub fn ecc(&self, r: u32) -> Option<u32> {
+        super::ecc_erasure(self.depuncture(r), self.mask)
+    }
+}
+
+/// `keep`のビット位置にある値を，順序を保ったまま下位ビットに詰める．
+pub(crate) fn compress(x: u32, keep: u32) -> u32 {
+    let mut out = 0;
+    for i in (0..24).rev() {
+        if (keep >> i) & 1 == 1 {
+            out = (out << 1) | ((x >> i) & 1);
+        }
+    }
+    out
+}
+
+/// `compress`の逆．下位ビットから順に`keep`のビット位置に戻す．それ以外の位置は0．
+pub(crate) fn expand(x: u32, keep: u32) -> u32 {
+    let mut out = 0;
+    let mut j = 0;  // xのビット位置
+    for i in 0..24 {
+        if (keep >> i) & 1 == 1 {
+            out |= ((x >> j) & 1) << i;
+            j += 1;
+        }
+    }
+    out
+}
+
+#[test]
+fn test_puncture() {
+    let data = 0b101101001110;
+    let p = Puncture::new(0b000100100101).unwrap();  // 4bit削除
+    assert_eq!(p.bits(), 20);
+
+    let tx = p.encode(data);
+    assert!(tx < (1 << 20));
+    assert_eq!(p.depuncture(tx), super::encode(data) & !0b000100100101);
+
+    // 2×誤り数 + 4 < 8 なので1bit誤りまで訂正できる
+    for i in 0..20 {
+        let corrected = p.ecc(tx ^ (1 << i));
+        assert_eq!(data, super::decode(corrected.unwrap()));
+    }
+
+    assert_eq!(None, Puncture::new(0xFF));  // 8bit削除
+    assert_eq!(None, Puncture::new(0x1000));
+}
@@ -0,0 +1,144 @@
+//! 平方剰余符号としての構成
+//!
+//! (23,12)ゴレイ符号は23を法とする平方剰余符号．
+//! 23の平方剰余 Q = {1, 2, 3, 4, 6, 8, 9, 12, 13, 16, 18} に対し，
+//! 1の原始23乗根 α を使って生成多項式を g(x) = Π_{i∈Q} (x - α^i) とする．
+//!
+//! α は GF(2^11)（原始多項式 x^11 + x^2 + 1）の原始元 β を使って α = β^(-89) とする（2^11 - 1 = 23 × 89）．
+//! α = β^89 とすると根が非剰余の側になり，相反多項式が出てくる．
+//! この α の選び方で`cyclic::GENERATOR`と同じ多項式になり，
+//! `cyclic`の並べ替えでこのクレートの生成行列と対応する．
+
+use crate::cyclic;
+
+/// GF(2^11)の原始多項式 x^11 + x^2 + 1
+const PRIMITIVE: u16 = 0x805;
+
+/// α = β^(-89) = β^(2047 - 89)
+const ALPHA_EXP: u32 = 2047 - 89;
+
+/// GF(2^11)の乗算
+const fn gf_mul(a: u16, b: u16) -> u16 {
+    let mut a = a;
+    let mut b = b;
+    let mut out = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            out ^= a;
+        }
+        a <<= 1;
+        if a & 0x800 != 0 {
+            a ^= PRIMITIVE;
+        }
+        b >>= 1;
+    }
+    out
+}
+
+/// GF(2^11)のべき乗
+const fn gf_pow(a: u16, n: u32) -> u16 {
+    let mut out = 1;
+    let mut i = 0;
+    while i < n {
+        out = gf_mul(out, a);
+        i += 1;
+    }
+    out
+}
+
+/// 23を法とする平方剰余（小さい順）
+pub const fn residues() -> [u8; 11] {
+    let mut out = [0; 11];
+    let mut n = 0;
+    let mut i = 1;
+    while i < 23 {
+        let mut is_residue = false;
+        let mut x = 1;
+        while x < 23 {
+            if (x * x) % 23 == i {
+                is_residue = true;
+            }
+            x += 1;
+        }
+        if is_residue {
+            out[n] = i as u8;
+            n += 1;
+        }
+        i += 1;
+    }
+    out
+}
+
+/// 平方剰余の定義から生成多項式を求める（bit iが x^i の係数）．
+///
+/// 根 α^i の積を GF(2^11) 上で展開し，係数が全て0か1になることも確かめる．
+/// 係数が GF(2) に入らなければNone（起こらない）．
+pub const fn generator_polynomial() -> Option<u32> {
+    let alpha = gf_pow(0b10, ALPHA_EXP);
+    let q = residues();
+
+    // coef[k]は x^k の係数
+    let mut coef = [0u16; 12];
+    coef[0] = 1;
+    let mut i = 0;
+    while i < 11 {
+        let root = gf_pow(alpha, q[i] as u32);
+        // (x + root)を掛ける（標数2なので -root = root）
+        let mut k = 11;
+        while k > 0 {
+            coef[k] = coef[k - 1] ^ gf_mul(root, coef[k]);
+            k -= 1;
+        }
+        coef[0] = gf_mul(root, coef[0]);
+        i += 1;
+    }
+
+    let mut g = 0;
+    let mut k = 0;
+    while k < 12 {
+        if coef[k] > 1 {
+            return None;
+        }
+        g |= (coef[k] as u32) << k;
+        k += 1;
+    }
+    Some(g)
+}
+
+/// (23,12)巡回符号の生成行列（巡回表現）．i行目は x^i・g(x)．
+///
+/// 生成多項式が求まらなければNone．
+pub fn generator_matrix() -> Option<[u32; 12]> {
+    let g = generator_polynomial()?;
+    let mut out = [0; 12];
+    for (i, row) in out.iter_mut().enumerate() {
+        *row = g << i;
+    }
+    Some(out)
+}
+
+/// 平方剰余の定義から作った符号が，このクレートの生成行列と一致するか確かめる．
+///
+/// 生成多項式が`cyclic::GENERATOR`と同じで，生成行列の各行の巡回表現が
+/// 生成多項式で割り切れ，bit 23が全体パリティになっていればtrue．
+pub fn matches_stored() -> bool {
+    let g = match generator_polynomial() {
+        Some(g) => g,
+        None => return false,
+    };
+    g == cyclic::GENERATOR && crate::G.iter().all(|g_line| {
+        let c = cyclic::to_cyclic(*g_line);
+        cyclic::remainder(c) == 0 && c.count_ones().is_multiple_of(2)
+    })
+}
+
+#[test]
+fn test_qr() {
+    assert_eq!(residues(), [1, 2, 3, 4, 6, 8, 9, 12, 13, 16, 18]);
+    assert_eq!(gf_pow(gf_pow(0b10, ALPHA_EXP), 23), 1);
+    assert_ne!(gf_pow(0b10, ALPHA_EXP), 1);
+    assert_eq!(generator_polynomial(), Some(cyclic::GENERATOR));
+    assert!(matches_stored());
+    let rows = generator_matrix().unwrap();
+    assert!(rows.iter().all(|row| cyclic::remainder(*row) == 0));
+}
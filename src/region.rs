@@ -0,0 +1,175 @@
+//! 保護されたメモリ領域
+//!
+//! 電池でバックアップしたSRAMや，`std`環境でmmapしたファイルなどの生のメモリ領域を
+//! `GolayProtect`を実装した型の置き場所として使う．
+//! 書き込みでは符号化し，読み出しでは訂正して，訂正した符号語をその場で書き戻す．
+//! 起きた誤りは`CorruptionReport`に積算する．
+//!
+//! 値の位置はバイト単位のオフセットで指定する．符号語の境界（3の倍数）にそろえること．
+//! 型`T`の値は`T::protected_len()`バイトを使う．
+
+use crate::protect::{GolayProtect, RawReader, RawWriter};
+
+/// 保護された領域のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionError {
+    /// 領域をはみ出した
+    OutOfRange,
+    /// オフセットが3の倍数でない
+    Misaligned,
+    /// 訂正できない符号語があった（その符号語のオフセット）
+    Uncorrectable(usize),
+}
+
+/// 誤りの積算
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CorruptionReport {
+    /// 訂正したビット数
+    pub corrected_bits: u32,
+    /// 訂正して書き戻した符号語の数
+    pub repaired_words: u32,
+    /// 訂正できなかった符号語の数（読むたびに数える）
+    pub uncorrectable_words: u32,
+    /// 最後に訂正できなかった符号語のオフセット
+    pub last_uncorrectable: Option<usize>,
+}
+
+/// 保護されたメモリ領域
+pub struct ProtectedRegion<'a> {
+    mem: &'a mut [u8],
+    report: CorruptionReport,
+}
+
+impl<'a> ProtectedRegion<'a> {
+    /// 既に書き込まれている領域をそのまま使う．
+    pub fn new(mem: &'a mut [u8]) -> Self {
+        Self{ mem, report: CorruptionReport::default() }
+    }
+
+    /// 領域を全て0の符号語で埋めて使う（0の符号語は0なので，全て0で埋める）．
+    pub fn format(mem: &'a mut [u8]) -> Self {
+        for b in mem.iter_mut() {
+            *b = 0;
+        }
+        Self::new(mem)
+    }
+
+    /// 領域のバイト数
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.mem.len()
+    }
+
+    /// 領域が空か
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.mem.is_empty()
+    }
+
+    /// これまでの誤りの積算
+    #[inline]
+    pub fn report(&self) -> &CorruptionReport {
+        &self.report
+    }
+
+    /// 誤りの積算を0に戻す．
+    #[inline]
+    pub fn clear_report(&mut self) {
+        self.report = CorruptionReport::default();
+    }
+
+    /// 値を符号化して書き込む．
+    pub fn write<T: GolayProtect>(&mut self, offset: usize, value: &T) -> Result<(), RegionError> {
+        let range = self.range(offset, T::protected_len())?;
+        let mut w = RawWriter::new(&mut self.mem[range]);
+        value.write_raw(&mut w);
+        w.finish();
+        Ok(())
+    }
+
+    /// 値を訂正して読み出す．訂正した符号語は書き戻す．
+    pub fn read<T: GolayProtect>(&mut self, offset: usize) -> Result<T, RegionError> {
+        let range = self.range(offset, T::protected_len())?;
+        self.repair(range.clone())?;
+        let mut r = RawReader::new(&self.mem[range]);
+        Ok( T::read_raw(&mut r) )
+    }
+
+    /// 領域全体を調べて，訂正できる誤りを書き戻す（スクラビング）．
+    ///
+    /// * return: 訂正できない符号語があれば，最初のもののオフセット
+    pub fn check(&mut self) -> Result<(), RegionError> {
+        let end = self.mem.len() / 3 * 3;
+        let mut first = None;
+        for pos in (0..end).step_by(3) {
+            if let Err(e) = self.repair(pos..pos + 3) {
+                first.get_or_insert(e);
+            }
+        }
+        match first {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// 範囲の符号語を訂正して書き戻す．
+    fn repair(&mut self, range: core::ops::Range<usize>) -> Result<(), RegionError> {
+        let mut failed = None;
+        for pos in range.step_by(3) {
+            let c = &mut self.mem[pos..pos + 3];
+            match crate::ecc_verbose( u32::from_be_bytes([0, c[0], c[1], c[2]]) ) {
+                Some(x) => if x.errors > 0 {
+                    c.copy_from_slice(&x.code.to_be_bytes()[1..]);
+                    self.report.corrected_bits += x.errors as u32;
+                    self.report.repaired_words += 1;
+                },
+                None => {
+                    self.report.uncorrectable_words += 1;
+                    self.report.last_uncorrectable = Some(pos);
+                    failed.get_or_insert(pos);
+                },
+            }
+        }
+        match failed {
+            Some(pos) => Err(RegionError::Uncorrectable(pos)),
+            None => Ok(()),
+        }
+    }
+
+    fn range(&self, offset: usize, len: usize) -> Result<core::ops::Range<usize>, RegionError> {
+        if !offset.is_multiple_of(3) {
+            return Err(RegionError::Misaligned);
+        }
+        let end = offset.checked_add(len).filter(|e| *e <= self.mem.len()).ok_or(RegionError::OutOfRange)?;
+        Ok(offset..end)
+    }
+}
+
+#[test]
+fn test_region() {
+    let mut sram = [0xFFu8; 40];
+    let mut region = ProtectedRegion::format(&mut sram);
+    assert_eq!(region.read::<u32>(0), Ok(0));
+    region.write(0, &0xDEAD_BEEFu32).unwrap();
+    region.write(9, &[1.5f32, -2.0]).unwrap();
+    assert_eq!(region.write(1, &0u8), Err(RegionError::Misaligned));
+    assert_eq!(region.write(39, &0u8), Err(RegionError::OutOfRange));
+    assert_eq!(region.range(usize::MAX / 3 * 3, 3), Err(RegionError::OutOfRange));
+
+    sram[0] ^= 0x81;
+    sram[12] ^= 0x10;
+    let mut region = ProtectedRegion::new(&mut sram);
+    assert_eq!(region.read::<u32>(0), Ok(0xDEAD_BEEF));
+    assert_eq!(region.read::<[f32; 2]>(9), Ok([1.5, -2.0]));
+    assert_eq!(region.report(), &CorruptionReport{ corrected_bits: 3, repaired_words: 2, ..CorruptionReport::default() });
+
+    // 書き戻したので2回目は誤りが無い
+    region.clear_report();
+    assert_eq!(region.read::<u32>(0), Ok(0xDEAD_BEEF));
+    assert_eq!(region.report().corrected_bits, 0);
+
+    sram[30] ^= 0xF0;
+    let mut region = ProtectedRegion::new(&mut sram);
+    assert_eq!(region.check(), Err(RegionError::Uncorrectable(30)));
+    assert_eq!(region.report().last_uncorrectable, Some(30));
+}
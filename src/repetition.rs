@@ -0,0 +1,166 @@
+//! 内符号に繰り返し符号を使う連接
+//!
+//! 符号語の各ビットを3回または5回続けて送る（外符号がゴレイ符号，内符号が繰り返し符号）．
+//! 受信側はビットごとに多数決（硬判定）か軟判定値の和（軟判定）で合成してから訂正する．
+//! 周波数利用効率は悪いが，処理が単純なので非常に低いSNRで送るビーコン向け．
+//!
+//! 送信ビット列は最大120bitなので`u128`の下位`bits_per_word()`bitに入れる．
+//! 符号語の最上位ビット（bit 23）の繰り返しが先頭（最上位）に来る．
+//! 軟判定値の列も同じ順に並べる．
+
+use crate::soft::SoftWord;
+
+/// 繰り返し回数の上限
+pub const MAX_REPETITION: usize = 5;
+
+/// 繰り返し回数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Repetition {
+    times: usize,
+}
+
+impl Repetition {
+    /// * `times`: 繰り返し回数（3か5）
+    pub fn new(times: usize) -> Option<Self> {
+        if !matches!(times, 3 | 5) {
+            return None;
+        }
+        Some( Self{ times } )
+    }
+
+    /// 繰り返し回数
+    #[inline]
+    pub fn times(&self) -> usize {
+        self.times
+    }
+
+    /// 1符号語あたりの送信ビット数
+    #[inline]
+    pub fn bits_per_word(&self) -> usize {
+        24 * self.times
+    }
+
+    /// 12bitのデータを符号化して，各ビットを繰り返す．
+    pub fn encode(&self, data: u16) -> u128 {
+        let code = crate::encode(data);
+        let ones = (1u128 << self.times) - 1;
+        let mut out = 0;
+        for i in (0..24).rev() {
+            out = (out << self.times) | if (code >> i) & 1 == 1 { ones } else { 0 };
+        }
+        out
+    }
+
+    /// ビットごとの多数決で24bitの受信語に合成する．
+    pub fn combine(&self, r: u128) -> u32 {
+        let mask = (1u128 << self.times) - 1;
+        let mut out = 0;
+        for i in (0..24).rev() {
+            let ones = ((r >> (i * self.times)) & mask).count_ones() as usize;
+            out = (out << 1) | (2 * ones > self.times) as u32;
+        }
+        out
+    }
+
+    /// 軟判定値をビットごとに足して1符号語分にする．和は±127で飽和させる．
+    ///
+    /// * `soft`: 受信した軟判定値（`bits_per_word()`個）
+    pub fn combine_soft(&self, soft: &[i8]) -> SoftWord {
+        assert!(soft.len() >= self.bits_per_word());
+        let mut out = [0; 24];
+        for (o, chunk) in out.iter_mut().zip(soft.chunks(self.times)) {
+            let sum: i32 = chunk.iter().map(|s| *s as i32).sum();
+            *o = sum.clamp(-127, 127) as i8;
+        }
+        out
+    }
+
+    /// 硬判定で復号する．訂正できなければNone．
+    pub fn decode(&self, r: u128) -> Option<u16> {
+        crate::ecc( self.combine(r) ).map(crate::decode)
+    }
+
+    /// 軟判定で復号する．訂正できなければNone．
+    ///
+    /// 合成した軟判定値の信頼度が低い順に0, 2, 4, 6bitを消失として`ecc_erasure`で訂正し，
+    /// 受信値との相関が最も高い候補を選ぶ（GMD復号）．
+    pub fn decode_soft(&self, soft: &[i8]) -> Option<u16> {
+        decode_soft_word(&self.combine_soft(soft)).map(crate::decode)
+    }
+}
+
+/// 1符号語分の軟判定値をGMD復号する．
+fn decode_soft_word(soft: &SoftWord) -> Option<u32> {
+    let r = crate::soft::hard_decision(soft);
+
+    // 信頼度の低い順のビット位置
+    let mut order = [0u8; 24];
+    for (i, o) in order.iter_mut().enumerate() {
+        *o = i as u8;
+    }
+    order.sort_unstable_by_key(|i| (soft[*i as usize] as i16).abs());
+
+    let mut best: Option<(u32, i32)> = None;
+    let mut erasure = 0u32;
+    for n in [0, 2, 4, 6].iter() {
+        while erasure.count_ones() < *n {
+            let k = order[erasure.count_ones() as usize];
+            erasure |= 1u32 << (23 - k);
+        }
+        if let Some(code) = crate::ecc_erasure(r, erasure) {
+            // 受信値と食い違うビットの信頼度の和
+            let diff = code ^ r;
+            let cost = (0..24).filter(|i| (diff >> (23 - i)) & 1 == 1).map(|i| (soft[i] as i32).abs()).sum();
+            match best {
+                Some((_, c)) if c <= cost => {},
+                _ => best = Some((code, cost)),
+            }
+        }
+    }
+    best.map(|(code, _)| code)
+}
+
+#[test]
+fn test_repetition() {
+    assert_eq!(Repetition::new(4), None);
+    for times in [3, 5].iter() {
+        let rep = Repetition::new(*times).unwrap();
+        let data = 0x5A3;
+        let tx = rep.encode(data);
+        assert_eq!(rep.combine(tx), crate::encode(data));
+
+        // 各ビットの繰り返しのうち少数を反転させても元に戻る．加えて3つのビットは多数が反転．
+        let mut r = tx;
+        for i in 0..24 {
+            r ^= 1 << (i * times);
+            if times == &5 {
+                r ^= 1 << (i * times + 2);
+            }
+        }
+        for i in [0usize, 7, 20].iter() {
+            r ^= ((1u128 << times) - 1) << (i * times);
+        }
+        assert_eq!(rep.decode(r), Some(data));
+    }
+
+    // 軟判定: 硬判定では4bitの誤りだが，そのうち2bitは信頼度が低い
+    let rep = Repetition::new(3).unwrap();
+    let code = crate::encode(0x123);
+    let mut soft = [0i8; 72];
+    for (j, s) in soft.iter_mut().enumerate() {
+        let bit = (code >> (23 - j / 3)) & 1;
+        *s = if bit == 1 { -20 } else { 20 };
+    }
+    for i in [1usize, 5].iter() {
+        for s in soft[3 * i..3 * i + 3].iter_mut() {
+            *s = -*s;
+        }
+    }
+    for i in [10usize, 15].iter() {
+        soft[3 * i] = -soft[3 * i];
+        soft[3 * i + 1] = -soft[3 * i + 1] / 2;
+        soft[3 * i + 2] = -soft[3 * i + 2] / 2;
+    }
+    assert_eq!(crate::ecc(crate::soft::hard_decision(&rep.combine_soft(&soft))), None);
+    assert_eq!(rep.decode_soft(&soft), Some(0x123));
+}
@@ -0,0 +1,168 @@
+//! 要素を符号化したまま置くリングバッファ
+//!
+//! ECCの無いRAMに長く置いておくキュー（何日も溜めてから吐き出すログなど）向け．
+//! 要素は`GolayProtect`で符号化して置き，取り出すときに訂正する．
+//! 先頭の位置と要素数も12bitに収めて符号化しておくので，容量は4095要素まで．
+//!
+//! 訂正できない要素は取り出すときに捨てて数える．
+
+use core::marker::PhantomData;
+
+use super::protect::{GolayProtect, RawReader};
+
+/// リングバッファのエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RingError {
+    /// 満杯
+    Full,
+    /// 先頭の位置か要素数を訂正できない（`clear`で空にするしかない）
+    Index,
+}
+
+/// 訂正の統計
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RingStats {
+    /// 入れた要素の数
+    pub pushed: u32,
+    /// 取り出せた要素の数
+    pub popped: u32,
+    /// 訂正したビット数（要素と位置の両方）
+    pub corrected_bits: u32,
+    /// 訂正できずに捨てた要素の数
+    pub lost: u32,
+}
+
+/// 要素を符号化したまま置くリングバッファ
+///
+/// `N`は`T`を保護した後のバイト数（`T::protected_len()`），`CAP`は容量．
+pub struct ProtectedRing<T, const N: usize, const CAP: usize> {
+    slots: [[u8; N]; CAP],
+    head: u32,  // 符号化した先頭の位置
+    len: u32,   // 符号化した要素数
+    stats: RingStats,
+    _marker: PhantomData<T>,
+}
+
+impl<T: GolayProtect, const N: usize, const CAP: usize> ProtectedRing<T, N, CAP> {
+    /// 空のリングバッファを作る．
+    ///
+    /// `N`が`T::protected_len()`と違うか，`CAP`が0か4095を超える場合はpanicする．
+    pub fn new() -> Self {
+        assert_eq!(N, T::protected_len());
+        assert!(CAP > 0 && CAP <= 0xFFF);
+        Self{
+            slots: [[0; N]; CAP],
+            head: super::encode(0),
+            len: super::encode(0),
+            stats: RingStats::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// 符号化した位置を訂正して読み，訂正したら書き戻す．
+    fn load(word: &mut u32, stats: &mut RingStats) -> Result<usize, RingError> {
+        let c = super::ecc_verbose(*word).ok_or(RingError::Index)?;
+        if c.errors > 0 {
+            *word = c.code;
+            stats.corrected_bits += c.errors as u32;
+        }
+        Ok( super::decode(c.code) as usize )
+    }
+
+    /// 要素数
+    pub fn len(&mut self) -> Result<usize, RingError> {
+        Self::load(&mut self.len, &mut self.stats)
+    }
+
+    /// 空ならtrue．
+    pub fn is_empty(&mut self) -> Result<bool, RingError> {
+        self.len().map(|n| n == 0)
+    }
+
+    /// 末尾に入れる．
+    pub fn push(&mut self, value: &T) -> Result<(), RingError> {
+        let head = Self::load(&mut self.head, &mut self.stats)?;
+        let len = Self::load(&mut self.len, &mut self.stats)?;
+        if len >= CAP {
+            return Err(RingError::Full);
+        }
+        value.to_protected_bytes(&mut self.slots[(head + len) % CAP]);
+        self.len = super::encode(len as u16 + 1);
+        self.stats.pushed += 1;
+        Ok(())
+    }
+
+    /// 先頭から取り出す．空ならNone．
+    ///
+    /// 訂正できない要素は捨てて（`RingStats::lost`に数える），次の要素を返す．
+    pub fn pop(&mut self) -> Result<Option<T>, RingError> {
+        let mut head = Self::load(&mut self.head, &mut self.stats)?;
+        let mut len = Self::load(&mut self.len, &mut self.stats)?;
+        if len > CAP || head >= CAP {
+            return Err(RingError::Index);
+        }
+        let mut out = None;
+        while len > 0 && out.is_none() {
+            let mut r = RawReader::new(&self.slots[head]);
+            let value = T::read_raw(&mut r);
+            if r.failed {
+                self.stats.lost += 1;
+            } else {
+                self.stats.corrected_bits += r.corrected;
+                self.stats.popped += 1;
+                out = Some(value);
+            }
+            head = (head + 1) % CAP;
+            len -= 1;
+        }
+        self.head = super::encode(head as u16);
+        self.len = super::encode(len as u16);
+        Ok(out)
+    }
+
+    /// 全ての要素を捨てる．統計は残す．
+    pub fn clear(&mut self) {
+        self.head = super::encode(0);
+        self.len = super::encode(0);
+    }
+
+    /// 訂正の統計
+    #[inline]
+    pub fn stats(&self) -> &RingStats {
+        &self.stats
+    }
+}
+
+impl<T: GolayProtect, const N: usize, const CAP: usize> Default for ProtectedRing<T, N, CAP> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_ring() {
+    let mut ring: ProtectedRing<u32, 9, 4> = ProtectedRing::new();
+    assert_eq!(ring.pop(), Ok(None));
+    for v in 0..4u32 {
+        ring.push(&(v * 1000)).unwrap();
+    }
+    assert_eq!(ring.push(&9), Err(RingError::Full));
+    assert_eq!(ring.pop(), Ok(Some(0)));
+    ring.push(&4000).unwrap();
+
+    // 2番目の要素に2bit，3番目に4bitの誤り，要素数にも1bitの誤り
+    ring.slots[1][0] ^= 0x81;
+    ring.slots[2][3] ^= 0x0F;
+    ring.len ^= 0x000800;
+    assert_eq!(ring.len(), Ok(4));
+    assert_eq!(ring.pop(), Ok(Some(1000)));
+    assert_eq!(ring.pop(), Ok(Some(3000)));
+    assert_eq!(ring.pop(), Ok(Some(4000)));
+    assert_eq!(ring.pop(), Ok(None));
+    assert_eq!(*ring.stats(), RingStats{ pushed: 5, popped: 4, corrected_bits: 3, lost: 1 });
+
+    ring.head ^= 0x00000F;
+    assert_eq!(ring.push(&1), Err(RingError::Index));
+    ring.clear();
+    assert_eq!(ring.is_empty(), Ok(true));
+}
@@ -0,0 +1,173 @@
+//! ランレングス制限（ビットスタッフィング）
+//!
+//! 同じビットが`max_run`個続いたら反転したビットを1つ挿入して，
+//! 出力ビット列の最大ラン長を`max_run`以下に抑える．
+//!
+//! ラン長の状態は符号語をまたいで引き継ぐので，符号語の境界でもラン長は制限される．
+//! 受信側は挿入ビットを取り除いたビットを24bitずつ数えて符号語を取り出すので，
+//! 挿入ビットが符号語の境界をずらすことはない．
+//!
+//! ビット列はバイトの上位ビットから順に詰める．
+
+/// バッファが足りない
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferFull;
+
+/// ラン長の状態
+#[derive(Clone, Copy, Debug)]
+struct Run {
+    max: u8,
+    last: u8,
+    len: u8,
+}
+
+impl Run {
+    fn new(max: u8) -> Self {
+        assert!(max >= 2);
+        Self{ max, last: 0, len: 0 }
+    }
+
+    /// ビットを1つ進める．次にスタッフィングビットが必要ならtrueを返す．
+    fn push(&mut self, bit: u8) -> bool {
+        if self.len > 0 && bit == self.last {
+            self.len += 1;
+        } else {
+            self.last = bit;
+            self.len = 1;
+        }
+        self.len == self.max
+    }
+}
+
+/// 符号語をビットスタッフィングしながらバッファに書き込む．
+pub struct RllWriter<'a> {
+    buf: &'a mut [u8],
+    bit_pos: usize,
+    run: Run,
+}
+
+impl<'a> RllWriter<'a> {
+    /// * `buf`: 出力先
+    /// * `max_run`: 最大ラン長（2以上）
+    pub fn new(buf: &'a mut [u8], max_run: u8) -> Self {
+        Self{ buf, bit_pos: 0, run: Run::new(max_run) }
+    }
+
+    /// 書き込んだビット数
+    #[inline]
+    pub fn bit_len(&self) -> usize {
+        self.bit_pos
+    }
+
+    /// 書き込んだバイト数（最後のバイトの残りは0埋め）
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.bit_pos.div_ceil(8)
+    }
+
+    fn put(&mut self, bit: u8) -> Result<(), BufferFull> {
+        let byte = self.buf.get_mut(self.bit_pos / 8).ok_or(BufferFull)?;
+        let shift = 7 - (self.bit_pos % 8);
+        *byte = (*byte & !(1 << shift)) | (bit << shift);
+        self.bit_pos += 1;
+        Ok(())
+    }
+
+    /// 符号語（下位24bit）を上位ビットから書き込む．
+    ///
+    /// バッファが足りなくなった場合，途中まで書き込まれた状態でエラーを返す．
+    pub fn write_codeword(&mut self, code: u32) -> Result<(), BufferFull> {
+        for i in (0..24).rev() {
+            let bit = ((code >> i) & 1) as u8;
+            self.put(bit)?;
+            if self.run.push(bit) {
+                self.put(bit ^ 1)?;
+                self.run.push(bit ^ 1);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ビットスタッフィングされたビット列から符号語を読み出す．
+pub struct RllReader<'a> {
+    buf: &'a [u8],
+    bit_len: usize,
+    bit_pos: usize,
+    run: Run,
+}
+
+impl<'a> RllReader<'a> {
+    /// * `buf`: 入力
+    /// * `bit_len`: 有効なビット数
+    /// * `max_run`: 最大ラン長（書き込み側と同じ値）
+    pub fn new(buf: &'a [u8], bit_len: usize, max_run: u8) -> Self {
+        let bit_len = bit_len.min(buf.len() * 8);
+        Self{ buf, bit_len, bit_pos: 0, run: Run::new(max_run) }
+    }
+
+    fn get(&mut self) -> Option<u8> {
+        if self.bit_pos >= self.bit_len {
+            return None;
+        }
+        let bit = (self.buf[self.bit_pos / 8] >> (7 - (self.bit_pos % 8))) & 1;
+        self.bit_pos += 1;
+        Some(bit)
+    }
+
+    /// 受信語を1つ読み出す．
+    ///
+    /// 残りのビットが足りなければNone．
+    /// スタッフィングビットは値を見ずに読み飛ばす．
+    pub fn read_codeword(&mut self) -> Option<u32> {
+        let mut r = 0;
+        for _ in 0..24 {
+            let bit = self.get()?;
+            r = (r << 1) | bit as u32;
+            if self.run.push(bit) {
+                let stuffed = self.get()?;
+                self.run.push(stuffed);
+            }
+        }
+        Some(r)
+    }
+}
+
+impl<'a> Iterator for RllReader<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        self.read_codeword()
+    }
+}
+
+#[test]
+fn test_rll() {
+    let codes = [super::encode(0x000), super::encode(0xFFF), super::encode(0x0F3)];
+    let mut buf = [0u8; 16];
+    let mut w = RllWriter::new(&mut buf, 5);
+    for code in codes.iter() {
+        w.write_codeword(*code).unwrap();
+    }
+    let bit_len = w.bit_len();
+    assert!(bit_len > 72);
+
+    // 最大ラン長の確認
+    let mut run = 0;
+    let mut last = 2;
+    for i in 0..bit_len {
+        let bit = (buf[i / 8] >> (7 - i % 8)) & 1;
+        run = if bit == last { run + 1 } else { 1 };
+        last = bit;
+        assert!(run <= 5);
+    }
+
+    let mut r = RllReader::new(&buf, bit_len, 5);
+    for code in codes.iter() {
+        assert_eq!(r.read_codeword(), Some(*code));
+    }
+    assert_eq!(r.read_codeword(), None);
+
+    let mut small = [0u8; 3];
+    assert_eq!(RllWriter::new(&mut small, 5).write_codeword(0), Err(BufferFull));
+}
@@ -0,0 +1,191 @@
+//! SRAMのスクラビング
+//!
+//! 符号化した語（`in_place::protect`と違い，訂正後も符号語のまま置いておく）を並べたRAM領域を
+//! 少しずつ読んで訂正し，書き戻す．放射線による単一事象反転（SEU）が積み重なって
+//! 訂正できなくなる前に直すためのもの．
+//!
+//! 1回の`Scrubber::step`で調べる語数は`budget`までなので，アイドル時間に呼べる．
+//! 進み具合は`Scrubber`が覚えていて，次の呼び出しで続きから調べる．
+//! 各領域は`stride`おきに調べる（0, s, 2s, ..., 1, s+1, ...）．
+//! 隣り合うセルの多重反転が同じ呼び出しに集中しないようにするため．
+
+use crate::Decode;
+use crate::metrics::MetricsSink;
+
+/// 1回の呼び出しの結果
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// 調べた語数
+    pub checked: usize,
+    /// 訂正して書き戻した語数
+    pub corrected_words: usize,
+    /// 訂正したビット数
+    pub corrected_bits: usize,
+    /// 訂正できなかった語数（そのまま残す）
+    pub uncorrectable: usize,
+    /// 最後に訂正できなかった語の（領域，添字）
+    pub last_uncorrectable: Option<(usize, usize)>,
+    /// この呼び出しで全領域を1周し終えた
+    pub pass_completed: bool,
+}
+
+/// 再開できるスクラバ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scrubber {
+    budget: usize,
+    stride: usize,
+    region: usize,
+    offset: usize,
+    index: usize,
+    passes: u32,
+    corrected_bits: u32,
+    uncorrectable: u32,
+}
+
+impl Scrubber {
+    /// * `budget`: 1回の呼び出しで調べる語数の上限（1以上）
+    /// * `stride`: 領域内で調べる間隔（1以上）
+    pub fn new(budget: usize, stride: usize) -> Option<Self> {
+        if budget == 0 || stride == 0 {
+            return None;
+        }
+        Some( Self{ budget, stride, region: 0, offset: 0, index: 0, passes: 0, corrected_bits: 0, uncorrectable: 0 } )
+    }
+
+    /// 領域の語を`budget`個まで調べて訂正する．
+    ///
+    /// 呼び出しのたびに同じ領域の並びを渡すこと．並びや長さが変わった場合は`reset`する．
+    ///
+    /// * `regions`: 符号語を並べたRAM領域
+    pub fn step(&mut self, regions: &mut [&mut [u32]]) -> Report {
+        self.step_metered(regions, &mut ())
+    }
+
+    /// `step`と同じく調べて訂正し，各語の結果を`sink`に渡す．
+    pub fn step_metered<M: MetricsSink + ?Sized>(&mut self, regions: &mut [&mut [u32]], sink: &mut M) -> Report {
+        let mut report = Report::default();
+        if regions.iter().all(|r| r.is_empty()) {
+            return report;
+        }
+        while report.checked < self.budget {
+            if self.region >= regions.len() {
+                self.region = 0;
+                self.passes = self.passes.wrapping_add(1);
+                report.pass_completed = true;
+            }
+            let words = &mut regions[self.region];
+            if self.index >= words.len() {
+                self.next_region();
+                continue;
+            }
+
+            let w = &mut words[self.index];
+            let result = crate::ecc_status(*w);
+            sink.record(result);
+            match result {
+                Decode::Clean(_) => (),
+                Decode::Corrected(code, errors) => {
+                    *w = code;
+                    report.corrected_words += 1;
+                    report.corrected_bits += errors as usize;
+                },
+                Decode::Detected => {
+                    report.uncorrectable += 1;
+                    report.last_uncorrectable = Some( (self.region, self.index) );
+                },
+            }
+            report.checked += 1;
+
+            self.index += self.stride;
+            if self.index >= words.len() {
+                self.offset += 1;
+                self.index = self.offset;
+                if self.offset >= self.stride {
+                    self.next_region();
+                }
+            }
+        }
+        self.corrected_bits = self.corrected_bits.wrapping_add(report.corrected_bits as u32);
+        self.uncorrectable = self.uncorrectable.wrapping_add(report.uncorrectable as u32);
+        report
+    }
+
+    fn next_region(&mut self) {
+        self.region += 1;
+        self.offset = 0;
+        self.index = 0;
+    }
+
+    /// 最初の領域の先頭から調べ直す．累計は残す．
+    pub fn reset(&mut self) {
+        self.region = 0;
+        self.offset = 0;
+        self.index = 0;
+    }
+
+    /// 今調べている（領域，添字）
+    #[inline]
+    pub fn position(&self) -> (usize, usize) {
+        (self.region, self.index)
+    }
+
+    /// 全領域を調べ終えた回数
+    #[inline]
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    /// 訂正したビット数の累計
+    #[inline]
+    pub fn corrected_bits(&self) -> u32 {
+        self.corrected_bits
+    }
+
+    /// 訂正できなかった語の数の累計（同じ語を何度も数える）
+    #[inline]
+    pub fn uncorrectable(&self) -> u32 {
+        self.uncorrectable
+    }
+}
+
+#[test]
+fn test_scrub() {
+    let mut a = [0u32; 10];
+    let mut b = [0u32; 5];
+    for (i, w) in a.iter_mut().chain(b.iter_mut()).enumerate() {
+        *w = crate::encode(i as u16 * 111);
+    }
+    a[3] ^= 0x100;
+    a[8] ^= 0x3;
+    b[4] ^= 0xF;
+    assert_eq!(Scrubber::new(0, 1), None);
+    let mut s = Scrubber::new(4, 3).unwrap();
+
+    // 領域aは 0, 3, 6, 9, 1, 4, 7, 2, 5, 8 の順に調べる
+    let r = s.step(&mut [&mut a, &mut b]);
+    assert_eq!((r.checked, r.corrected_words, r.corrected_bits), (4, 1, 1));
+    assert_eq!(a[3], crate::encode(333));
+    assert_eq!(s.position(), (0, 1));
+
+    let mut total = Report::default();
+    let mut steps = 0;
+    loop {
+        let r = s.step(&mut [&mut a, &mut b]);
+        total.corrected_bits += r.corrected_bits;
+        total.uncorrectable += r.uncorrectable;
+        if r.uncorrectable > 0 {
+            total.last_uncorrectable = r.last_uncorrectable;
+        }
+        steps += 1;
+        if r.pass_completed {
+            break;
+        }
+    }
+    assert_eq!(steps, 3);
+    assert_eq!(s.passes(), 1);
+    assert_eq!(total.corrected_bits, 2);
+    assert_eq!(total.uncorrectable, 1);
+    assert_eq!(total.last_uncorrectable, Some((1, 4)));
+    assert_eq!(a[8], crate::encode(888));
+    assert_eq!(s.corrected_bits(), 3);
+}
@@ -0,0 +1,158 @@
+//! 起動時の自己診断（BIST）
+//!
+//! 安全に関わるファームウェアで起動時に呼び出し，ROMの破損や誤ったコンパイルを検出する．
+//! 数千回の訂正を行うので，マイコンでは数ms程度かかる．
+//!
+//! 長時間動き続けるシステムでは，`verify_tables`を定期的に呼んで
+//! 符号化と訂正に使う表そのものが壊れていないかを確かめる．
+
+use core::hint::black_box;
+
+/// 自己診断のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelfTestError {
+    /// 生成行列か検査行列が壊れている
+    Table,
+    /// 既知の符号化結果と合わない
+    Encode,
+    /// 3bit以下の誤りを訂正できなかった
+    Correct,
+    /// 4bitの誤りを検出できなかった
+    Detect,
+}
+
+/// 既知の符号化結果（データ，符号語）．`vectors`フィーチャの符号化のベクタもこれを使う．
+pub(crate) const VECTORS: [(u16, u32); 11] = [
+    (0x000, 0x000000),
+    (0xFFF, 0xFFFFFF),
+    (0x555, 0x555555),
+    (0xAAA, 0xAAAAAA),
+    (0x123, 0x1239E7),
+    (0x9C3, 0x9C3DE0),
+    (0x0F0, 0x0F0264),
+    (0xF0F, 0xF0FD9B),
+    (0x3C5, 0x3C50DD),
+    (0xA5C, 0xA5CE7F),
+    (0x7FF, 0x7FF60E),
+];
+
+/// CRC-32（IEEE 802.3）を計算する．表の各要素はリトルエンディアンのバイト列として扱う．
+const fn crc32(table: &[u32]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    let mut i = 0;
+    while i < table.len() {
+        let bytes = table[i].to_le_bytes();
+        let mut j = 0;
+        while j < 4 {
+            crc ^= bytes[j] as u32;
+            let mut k = 0;
+            while k < 8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+                k += 1;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    !crc
+}
+
+/// コンパイル時に計算した生成行列のチェックサム
+const G_CRC: u32 = crc32(&super::G);
+
+/// コンパイル時に計算した検査行列のチェックサム
+const H_T_CRC: u32 = crc32(&super::H_T);
+
+/// コンパイル時に計算した`accel::Table`の表のチェックサム
+#[cfg(feature = "accel")]
+const PATTERNS_CRC: u32 = crc32(&super::accel::PATTERNS);
+
+/// 符号化と訂正に使う表のチェックサムを確かめる．
+///
+/// 表をメモリから読み直して計算し，コンパイル時に計算した値と比べる．
+/// `accel`フィーチャでは`accel::Table`の表も確かめる．
+pub fn verify_tables() -> Result<(), SelfTestError> {
+    // 最適化で読み出しが省かれないようにする
+    let g: &[u32] = black_box(&super::G);
+    let h_t: &[u32] = black_box(&super::H_T);
+    if crc32(g) != G_CRC || crc32(h_t) != H_T_CRC {
+        return Err(SelfTestError::Table);
+    }
+    #[cfg(feature = "accel")]
+    {
+        let patterns: &[u32] = black_box(&super::accel::PATTERNS);
+        if crc32(patterns) != PATTERNS_CRC {
+            return Err(SelfTestError::Table);
+        }
+    }
+    Ok(())
+}
+
+/// 生成行列と検査行列を確かめる．
+fn check_tables() -> Result<(), SelfTestError> {
+    for (i, g_line) in super::G.iter().enumerate() {
+        // G = [I | B] で，Bの行がH_Tの上半分と同じ
+        if g_line >> 12 != 0x800 >> i || g_line & 0xFFF != super::H_T[i] {
+            return Err(SelfTestError::Table);
+        }
+        // 各行の重みは8で，G・H^T = 0
+        if g_line.count_ones() != 8 || super::syndrome(*g_line) != 0 {
+            return Err(SelfTestError::Table);
+        }
+    }
+    for (i, h_t_line) in super::H_T.iter().skip(12).enumerate() {
+        if *h_t_line != 0x800 >> i {
+            return Err(SelfTestError::Table);
+        }
+    }
+    Ok(())
+}
+
+/// 符号化，訂正，検出を一通り確かめる．
+///
+/// * 行列の定数（チェックサムと構造）
+/// * 既知のデータの符号化結果
+/// * 1〜3bitの全ての誤りパターンの訂正
+/// * 最下位ビットを含む全ての4bit誤りパターンの検出
+pub fn self_test() -> Result<(), SelfTestError> {
+    verify_tables()?;
+    check_tables()?;
+
+    for (data, code) in VECTORS.iter() {
+        if super::encode(*data) != *code || super::decode(*code) != *data {
+            return Err(SelfTestError::Encode);
+        }
+    }
+
+    let code = VECTORS[5].1;
+    for i in 0..24 {
+        for j in i..24 {
+            for k in j..24 {
+                // i == j などの場合は1bitや2bitの誤りになる
+                let e = (1 << i) | (1 << j) | (1 << k);
+                if super::ecc(code ^ e) != Some(code) {
+                    return Err(SelfTestError::Correct);
+                }
+            }
+        }
+    }
+
+    for i in 1..24 {
+        for j in (i + 1)..24 {
+            for k in (j + 1)..24 {
+                let e = 1 | (1 << i) | (1 << j) | (1 << k);
+                if super::ecc(code ^ e).is_some() {
+                    return Err(SelfTestError::Detect);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_self_test() {
+    assert_eq!(self_test(), Ok(()));
+    assert_eq!(verify_tables(), Ok(()));
+    assert_ne!(crc32(&super::G[..11]), G_CRC);
+}
@@ -0,0 +1,134 @@
+//! 1バイトのデータと4bitの通し番号を1語で送る
+//!
+//! 12bitのデータを上位4bitの通し番号（0〜15で一周する）と下位8bitのデータに分ける．
+//! 受信側は通し番号から，抜けた語と重複した語を見つける．
+//! 簡単なコマンドの回線でよく使う形を決めておくためのもの．
+//!
+//! 一度に16語以上抜けると一周してしまうので見分けられない．
+//! 15語抜けた場合は重複と区別できないので，重複として扱う．
+
+/// 通し番号とデータを12bitにまとめる．
+#[inline]
+pub fn pack(data: u8, seq: u8) -> u16 {
+    ((seq as u16 & 0xF) << 8) | data as u16
+}
+
+/// 12bitから（データ，通し番号）を取り出す．
+#[inline]
+pub fn unpack(a: u16) -> (u8, u8) {
+    (a as u8, (a >> 8) as u8 & 0xF)
+}
+
+/// 送信側
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sender {
+    seq: u8,
+}
+
+impl Sender {
+    /// 通し番号0から始める．
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 1バイトを符号化し，通し番号を進める．
+    pub fn encode(&mut self, data: u8) -> u32 {
+        let code = crate::encode( pack(data, self.seq) );
+        self.seq = (self.seq + 1) & 0xF;
+        code
+    }
+
+    /// 次に使う通し番号
+    #[inline]
+    pub fn next_seq(&self) -> u8 {
+        self.seq
+    }
+}
+
+/// 受信した語の判定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Received {
+    /// 新しいデータ．`dropped`はその前に抜けた語の数（最初の語では0）．
+    Data { data: u8, seq: u8, dropped: u8 },
+    /// 直前の語と同じ通し番号（再送など）
+    Duplicate { data: u8, seq: u8 },
+    /// 訂正できなかった
+    Uncorrectable,
+}
+
+/// 受信側
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Receiver {
+    last: Option<u8>,
+    dropped: u32,
+    duplicates: u32,
+}
+
+impl Receiver {
+    /// 最初の語を待つ状態で作る．
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 受信語を訂正して判定する．
+    pub fn receive(&mut self, r: u32) -> Received {
+        let (data, seq) = match crate::ecc(r) {
+            Some(code) => unpack( crate::decode(code) ),
+            None => return Received::Uncorrectable,
+        };
+        let dropped = match self.last {
+            None => 0,
+            Some(last) if last == seq => {
+                self.duplicates += 1;
+                return Received::Duplicate{ data, seq };
+            },
+            Some(last) => (seq.wrapping_sub(last).wrapping_sub(1)) & 0xF,
+        };
+        self.last = Some(seq);
+        self.dropped += dropped as u32;
+        Received::Data{ data, seq, dropped }
+    }
+
+    /// 同期し直す（次の語を最初の語として扱う）．
+    #[inline]
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// これまでに抜けた語の数
+    #[inline]
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// これまでに重複した語の数
+    #[inline]
+    pub fn duplicates(&self) -> u32 {
+        self.duplicates
+    }
+}
+
+#[test]
+fn test_sequenced() {
+    assert_eq!(pack(0xAB, 0x13), 0x3AB);
+    assert_eq!(unpack(0x3AB), (0xAB, 3));
+
+    let mut tx = Sender::new();
+    let mut rx = Receiver::new();
+    let words: [u32; 20] = core::array::from_fn(|i| tx.encode(i as u8 * 10));
+    assert_eq!(tx.next_seq(), 4);
+
+    assert_eq!(rx.receive(words[0]), Received::Data{ data: 0, seq: 0, dropped: 0 });
+    assert_eq!(rx.receive(words[1] ^ 0x000111), Received::Data{ data: 10, seq: 1, dropped: 0 });
+    assert_eq!(rx.receive(words[1]), Received::Duplicate{ data: 10, seq: 1 });
+    assert_eq!(rx.receive(words[4]), Received::Data{ data: 40, seq: 4, dropped: 2 });
+    assert_eq!(rx.receive(words[5] ^ 0xF), Received::Uncorrectable);
+    // 一周をまたぐ
+    assert_eq!(rx.receive(words[17]), Received::Data{ data: 170, seq: 1, dropped: 12 });
+    assert_eq!((rx.dropped(), rx.duplicates()), (14, 1));
+
+    rx.reset();
+    assert_eq!(rx.receive(words[9]), Received::Data{ data: 90, seq: 9, dropped: 0 });
+}
@@ -0,0 +1,158 @@
+//! 軟判定入力
+//!
+//! 軟判定値は固定小数点の対数尤度比（LLR）で表す．
+//! * 型は`i8`で，正なら0，負なら1の可能性が高い．絶対値が大きいほど確からしい．
+//! * 1符号語分は`[i8; 24]`で，添字0が符号語の最上位ビット（bit 23）に対応する．
+//!
+//! 復調器の浮動小数点の出力はこの形式に量子化してから使う．
+//!
+//! 軟判定の復号まではしない場合でも，`ErasureFlagger`で絶対値の小さいビットを消失として
+//! `ecc_erasure`に渡せば，硬判定より多くの誤りを訂正できる．
+
+/// 1符号語分の軟判定値
+pub type SoftWord = [i8; 24];
+
+/// 軟判定値を硬判定して受信語にする．
+///
+/// 0は1と判定する（どちらでも良いが，決めておく）．
+pub fn hard_decision(soft: &SoftWord) -> u32 {
+    soft.iter().fold(0, |r, s| (r << 1) | (*s <= 0) as u32)
+}
+
+/// 浮動小数点の軟判定値を固定小数点のLLRに量子化する．
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantizer {
+    scale: f32,
+    saturation: i8,
+}
+
+impl Quantizer {
+    /// * `scale`: 入力に掛ける係数．負にすると符号を反転する（1が正になる復調器向け）．
+    /// * `saturation`: 出力の絶対値の上限（1〜127）
+    pub fn new(scale: f32, saturation: i8) -> Self {
+        assert!(saturation > 0);
+        Self{ scale, saturation }
+    }
+
+    /// 1つ量子化する．最も近い整数に丸めて飽和させる．NaNは0にする．
+    #[inline]
+    pub fn quantize(&self, x: f32) -> i8 {
+        let y = x * self.scale;
+        let sat = self.saturation as f32;
+        if y.is_nan() {
+            0
+        } else if y >= sat {
+            self.saturation
+        } else if y <= -sat {
+            -self.saturation
+        } else if y >= 0.0 {
+            (y + 0.5) as i8
+        } else {
+            (y - 0.5) as i8
+        }
+    }
+
+    /// f64の値を1つ量子化する．
+    #[inline]
+    pub fn quantize_f64(&self, x: f64) -> i8 {
+        self.quantize(x as f32)
+    }
+
+    /// 1符号語分を量子化する．
+    pub fn quantize_word(&self, x: &[f32; 24]) -> SoftWord {
+        let mut out = [0; 24];
+        for (o, v) in out.iter_mut().zip(x.iter()) {
+            *o = self.quantize(*v);
+        }
+        out
+    }
+
+    /// 任意の長さの列を量子化する．長さは短いほうに合わせる．
+    pub fn quantize_slice(&self, x: &[f32], out: &mut [i8]) {
+        for (o, v) in out.iter_mut().zip(x.iter()) {
+            *o = self.quantize(*v);
+        }
+    }
+
+    /// f64の列を量子化する．長さは短いほうに合わせる．
+    pub fn quantize_slice_f64(&self, x: &[f64], out: &mut [i8]) {
+        for (o, v) in out.iter_mut().zip(x.iter()) {
+            *o = self.quantize_f64(*v);
+        }
+    }
+}
+
+/// 絶対値がしきい値以下のビットを消失とみなす．
+///
+/// リミッタ型の復調器のように，信頼度を粗くしか出せない相手から
+/// 誤りと消失の訂正（`ecc_erasure`）に繋ぐためのもの．
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErasureFlagger {
+    threshold: u8,
+}
+
+impl ErasureFlagger {
+    /// * `threshold`: 絶対値がこれ以下のビットを消失にする（0なら値0のビットだけ）
+    pub fn new(threshold: u8) -> Self {
+        Self{ threshold }
+    }
+
+    /// 軟判定値を（硬判定した受信語，消失ビットの位置）にする．
+    pub fn flag(&self, soft: &SoftWord) -> (u32, u32) {
+        let erasure = soft.iter().fold(0, |m, s| (m << 1) | (s.unsigned_abs() <= self.threshold) as u32);
+        (hard_decision(soft), erasure)
+    }
+
+    /// 消失を付けて訂正する．訂正できなければNone．
+    ///
+    /// 消失が8bit以上あると訂正できないので，しきい値は回線に合わせて小さめにすること．
+    #[inline]
+    pub fn ecc(&self, soft: &SoftWord) -> Option<u32> {
+        let (r, erasure) = self.flag(soft);
+        super::ecc_erasure(r, erasure)
+    }
+}
+
+#[test]
+fn test_erasure_flagger() {
+    let code = super::encode(0x5E1);
+    let mut soft = [0i8; 24];
+    for (i, s) in soft.iter_mut().enumerate() {
+        *s = if (code >> (23 - i)) & 1 == 0 { 40 } else { -40 };
+    }
+    // 4bitの誤りは硬判定では訂正できないが，弱いので消失にすれば訂正できる
+    for i in [0, 5, 11, 20].iter() {
+        soft[*i] = -soft[*i].signum() * 3;
+    }
+    soft[8] = soft[8].signum();
+    let flagger = ErasureFlagger::new(3);
+    let (r, erasure) = flagger.flag(&soft);
+    assert_eq!(r, hard_decision(&soft));
+    assert_eq!(erasure, 0x800000 | 0x040000 | 0x008000 | 0x001000 | 0x000008);
+    assert_eq!(super::ecc(r), None);
+    assert_eq!(flagger.ecc(&soft), Some(code));
+
+    // しきい値が低すぎると消失にならない
+    assert_eq!(ErasureFlagger::new(2).flag(&soft).1, 0x008000);
+    assert_ne!(ErasureFlagger::new(2).ecc(&soft), Some(code));
+}
+
+#[test]
+fn test_quantizer() {
+    let q = Quantizer::new(16.0, 127);
+    assert_eq!(q.quantize(0.0), 0);
+    assert_eq!(q.quantize(1.0), 16);
+    assert_eq!(q.quantize(-0.53), -8);
+    assert_eq!(q.quantize(100.0), 127);
+    assert_eq!(q.quantize(f32::NEG_INFINITY), -127);
+    assert_eq!(q.quantize(f32::NAN), 0);
+    assert_eq!(Quantizer::new(-1.0, 7).quantize_f64(2.4), -2);
+
+    // BPSK（0 → +1, 1 → -1）
+    let code = super::encode(0x9C3);
+    let mut x = [0f32; 24];
+    for (i, v) in x.iter_mut().enumerate() {
+        *v = if (code >> (23 - i)) & 1 == 0 { 0.8 } else { -0.8 };
+    }
+    assert_eq!(hard_decision(&q.quantize_word(&x)), code);
+}
@@ -0,0 +1,365 @@
+//! Base64/Base32による文字列への変換
+//!
+//! 符号化したフレームを文字列だけを通す経路（SMSのようなゲートウェイ，MQTTの文字列，
+//! コピー＆ペーストでのデバッグなど）で送れるように，Base64とBase32（RFC 4648）に変換する．
+//! 出力は呼び出し側のバッファに書き込む．
+//!
+//! フレームの構成（`encode_frame`）
+//! * データのバイト数（12bit）の符号語
+//! * データを`protect`と同じく12bitずつ符号化したもの
+//!
+//! Base64では1つの符号語（3バイト）がちょうど4文字になる．
+//! 符号語はこのクレートの形式で上位バイトから並べる．約束事の違う相手とは
+//! `encode_frame_as`，`decode_frame_as`の型引数に配置とビット順を指定する（`layout`）．
+
+use core::marker::PhantomData;
+
+use crate::Decode;
+use crate::layout::{BitOrder, DataHigh, Layout, MsbFirst};
+use crate::metrics::MetricsSink;
+
+/// 文字列の形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Base64（`A-Z a-z 0-9 + /`，`=`で4文字の倍数に埋める）
+    Base64,
+    /// Base32（`A-Z 2-7`，`=`で8文字の倍数に埋める）
+    Base32,
+}
+
+/// 文字列の変換のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextError {
+    /// 使えない文字がある
+    InvalidChar,
+    /// 文字数が合わない
+    InvalidLength,
+    /// 出力先のバッファが足りない
+    BufferTooSmall,
+    /// 訂正できない符号語があった
+    Uncorrectable,
+}
+
+/// フレームのデータの最大バイト数
+pub const MAX_FRAME_LEN: usize = 0xFFF;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+impl Encoding {
+    /// 1文字のビット数
+    #[inline]
+    fn bits(&self) -> u32 {
+        match self {
+            Encoding::Base64 => 6,
+            Encoding::Base32 => 5,
+        }
+    }
+
+    /// 埋めたあとの文字数の倍数
+    #[inline]
+    fn group(&self) -> usize {
+        match self {
+            Encoding::Base64 => 4,
+            Encoding::Base32 => 8,
+        }
+    }
+
+    /// 文字の値．使えない文字ならNone．
+    fn value(&self, c: u8) -> Option<u32> {
+        let v = match (self, c) {
+            (_, b'A'..=b'Z') => c - b'A',
+            (Encoding::Base64, b'a'..=b'z') => c - b'a' + 26,
+            (Encoding::Base64, b'0'..=b'9') => c - b'0' + 52,
+            (Encoding::Base64, b'+') => 62,
+            (Encoding::Base64, b'/') => 63,
+            (Encoding::Base32, b'2'..=b'7') => c - b'2' + 26,
+            _ => return None,
+        };
+        Some(v as u32)
+    }
+
+    /// `bytes`バイトを変換したときの文字数（埋める文字を含む）
+    #[inline]
+    pub fn encoded_len(&self, bytes: usize) -> usize {
+        (bytes * 8).div_ceil(self.bits() as usize).next_multiple_of(self.group())
+    }
+
+    /// バイト列を文字列にする．
+    ///
+    /// * return: 書き込んだ文字数．`out`が足りなければNone．
+    pub fn encode(&self, input: &[u8], out: &mut [u8]) -> Option<usize> {
+        self.encode_iter(input.iter().copied(), out)
+    }
+
+    fn encode_iter<I: Iterator<Item = u8>>(&self, input: I, out: &mut [u8]) -> Option<usize> {
+        let bits = self.bits();
+        let mask = (1 << bits) - 1;
+        let mut acc = 0u32;
+        let mut n_bits = 0;
+        let mut n = 0;
+        let mut put = |v: u8, n: &mut usize| -> Option<()> {
+            *out.get_mut(*n)? = v;
+            *n += 1;
+            Some(())
+        };
+        for b in input {
+            acc = (acc << 8) | b as u32;
+            n_bits += 8;
+            while n_bits >= bits {
+                n_bits -= bits;
+                put(self.char((acc >> n_bits) & mask), &mut n)?;
+            }
+        }
+        if n_bits > 0 {
+            put(self.char((acc << (bits - n_bits)) & mask), &mut n)?;
+        }
+        while n % self.group() != 0 {
+            put(b'=', &mut n)?;
+        }
+        Some(n)
+    }
+
+    #[inline]
+    fn char(&self, v: u32) -> u8 {
+        match self {
+            Encoding::Base64 => BASE64[v as usize],
+            Encoding::Base32 => BASE32[v as usize],
+        }
+    }
+
+    /// 文字列をバイト列に戻す．埋める文字`=`は省略しても良い．
+    ///
+    /// * return: 書き込んだバイト数
+    pub fn decode(&self, input: &[u8], out: &mut [u8]) -> Result<usize, TextError> {
+        let mut n = 0;
+        self.decode_with(input, |b| {
+            *out.get_mut(n).ok_or(TextError::BufferTooSmall)? = b;
+            n += 1;
+            Ok(())
+        })?;
+        Ok(n)
+    }
+
+    /// 文字列をバイト列に戻しながら1バイトずつ`sink`に渡す．
+    fn decode_with<F>(&self, input: &[u8], mut sink: F) -> Result<(), TextError>
+    where F: FnMut(u8) -> Result<(), TextError> {
+        let bits = self.bits();
+        let len = input.iter().position(|c| *c == b'=').unwrap_or(input.len());
+        if input[len..].iter().any(|c| *c != b'=') {
+            return Err(TextError::InvalidChar);
+        }
+        // 最後の文字の余りのビットが1文字分以上になる長さは不正
+        if (len * bits as usize) % 8 >= bits as usize {
+            return Err(TextError::InvalidLength);
+        }
+        let mut acc = 0u32;
+        let mut n_bits = 0;
+        for c in input[..len].iter() {
+            acc = (acc << bits) | self.value(*c).ok_or(TextError::InvalidChar)?;
+            n_bits += bits;
+            if n_bits >= 8 {
+                n_bits -= 8;
+                sink((acc >> n_bits) as u8)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// データを符号化したフレームを文字列にする．
+///
+/// * return: 書き込んだ文字数．データが長すぎるか`out`が足りなければNone．
+#[inline]
+pub fn encode_frame(encoding: Encoding, data: &[u8], out: &mut [u8]) -> Option<usize> {
+    encode_frame_as::<DataHigh, MsbFirst>(encoding, data, out)
+}
+
+/// `encode_frame`と同じく，符号語を配置`L`，ビット順`O`にして文字列にする．
+pub fn encode_frame_as<L: Layout, O: BitOrder>(encoding: Encoding, data: &[u8], out: &mut [u8]) -> Option<usize> {
+    if data.len() > MAX_FRAME_LEN {
+        return None;
+    }
+    encoding.encode_iter(FrameBytes::<L, O>::new(data), out)
+}
+
+/// `encode_frame`で作った文字列を訂正してデータに戻す．
+///
+/// * return: （データのバイト数，訂正したビット数）
+#[inline]
+pub fn decode_frame(encoding: Encoding, text: &[u8], out: &mut [u8]) -> Result<(usize, u32), TextError> {
+    decode_frame_with::<DataHigh, MsbFirst, _>(encoding, text, out, &mut ())
+}
+
+/// `encode_frame_as`で作った文字列を，配置`L`，ビット順`O`の符号語として訂正してデータに戻す．
+#[inline]
+pub fn decode_frame_as<L: Layout, O: BitOrder>(encoding: Encoding, text: &[u8], out: &mut [u8])
+-> Result<(usize, u32), TextError> {
+    decode_frame_with::<L, O, _>(encoding, text, out, &mut ())
+}
+
+/// `decode_frame`と同じく戻し，各符号語の訂正の結果を`sink`に渡す．
+#[inline]
+pub fn decode_frame_metered<M: MetricsSink + ?Sized>(encoding: Encoding, text: &[u8], out: &mut [u8], sink: &mut M)
+-> Result<(usize, u32), TextError> {
+    decode_frame_with::<DataHigh, MsbFirst, M>(encoding, text, out, sink)
+}
+
+fn decode_frame_with<L: Layout, O: BitOrder, M: MetricsSink + ?Sized>(encoding: Encoding, text: &[u8], out: &mut [u8], sink: &mut M)
+-> Result<(usize, u32), TextError> {
+    let mut code = [0u8; 3];
+    let mut n_code = 0;
+    let mut len = None;
+    let mut corrected = 0;
+    let mut acc = 0u32;
+    let mut n_bits = 0;
+    let mut n = 0;
+    encoding.decode_with(text, |b| {
+        code[n_code] = b;
+        n_code += 1;
+        if n_code < 3 {
+            return Ok(());
+        }
+        n_code = 0;
+        let result = crate::ecc_status( L::to_native(O::from_bytes(code)) );
+        sink.record(result);
+        let (code, errors) = match result {
+            Decode::Clean(code) => (code, 0),
+            Decode::Corrected(code, errors) => (code, errors),
+            Decode::Detected => return Err(TextError::Uncorrectable),
+        };
+        corrected += errors as u32;
+        let a = crate::decode(code);
+        let len = match len {
+            None => {
+                len = Some(a as usize);
+                if a as usize > out.len() {
+                    return Err(TextError::BufferTooSmall);
+                }
+                return Ok(());
+            },
+            Some(l) => l,
+        };
+        acc = (acc << 12) | a as u32;
+        n_bits += 12;
+        while n_bits >= 8 && n < len {
+            n_bits -= 8;
+            out[n] = (acc >> n_bits) as u8;
+            n += 1;
+        }
+        Ok(())
+    })?;
+    match len {
+        Some(l) if l == n && n_code == 0 => Ok( (n, corrected) ),
+        _ => Err(TextError::InvalidLength),
+    }
+}
+
+/// フレームのバイト列を順に作るイテレータ
+struct FrameBytes<'a, L, O> {
+    data: &'a [u8],
+    pos: usize,       // 次に読むデータのバイト
+    bits: u32,        // 符号化待ちのビット
+    n_bits: u32,
+    code: [u8; 3],    // 出力中の符号語
+    n_code: usize,    // codeの残りバイト数
+    header: bool,     // 長さの符号語を出したか
+    _marker: PhantomData<(L, O)>,
+}
+
+impl<'a, L: Layout, O: BitOrder> FrameBytes<'a, L, O> {
+    fn new(data: &'a [u8]) -> Self {
+        Self{ data, pos: 0, bits: 0, n_bits: 0, code: [0; 3], n_code: 0, header: false, _marker: PhantomData }
+    }
+
+    fn load(&mut self, a: u16) {
+        self.code = O::to_bytes( L::from_native(crate::encode(a)) );
+        self.n_code = 3;
+    }
+}
+
+impl<'a, L: Layout, O: BitOrder> Iterator for FrameBytes<'a, L, O> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.n_code == 0 {
+            if !self.header {
+                self.header = true;
+                self.load(self.data.len() as u16);
+            } else {
+                while self.n_bits < 12 && self.pos < self.data.len() {
+                    self.bits = (self.bits << 8) | self.data[self.pos] as u32;
+                    self.n_bits += 8;
+                    self.pos += 1;
+                }
+                if self.n_bits >= 12 {
+                    self.n_bits -= 12;
+                    self.load( (self.bits >> self.n_bits) as u16 & 0xFFF );
+                } else if self.n_bits > 0 {
+                    self.load( (self.bits << (12 - self.n_bits)) as u16 & 0xFFF );
+                    self.n_bits = 0;
+                } else {
+                    return None;
+                }
+            }
+        }
+        let b = self.code[3 - self.n_code];
+        self.n_code -= 1;
+        Some(b)
+    }
+}
+
+#[test]
+fn test_text() {
+    // RFC 4648のテストベクタ
+    let mut buf = [0u8; 64];
+    let vectors: [(&[u8], &[u8], &[u8]); 4] = [
+        (b"", b"", b""),
+        (b"f", b"Zg==", b"MY======"),
+        (b"foo", b"Zm9v", b"MZXW6==="),
+        (b"foobar", b"Zm9vYmFy", b"MZXW6YTBOI======"),
+    ];
+    for (raw, b64, b32) in vectors.iter() {
+        let n = Encoding::Base64.encode(raw, &mut buf).unwrap();
+        assert_eq!(&buf[..n], *b64);
+        assert_eq!(n, Encoding::Base64.encoded_len(raw.len()));
+        let n = Encoding::Base32.encode(raw, &mut buf).unwrap();
+        assert_eq!(&buf[..n], *b32);
+        let mut out = [0u8; 8];
+        let n = Encoding::Base32.decode(b32, &mut out).unwrap();
+        assert_eq!(&out[..n], *raw);
+        let n = Encoding::Base64.decode(&b64[..b64.len().min(raw.len() * 4 / 3 + 1)], &mut out).unwrap();
+        assert_eq!(&out[..n], *raw);
+    }
+    assert_eq!(Encoding::Base64.decode(b"Zm!v", &mut buf), Err(TextError::InvalidChar));
+    assert_eq!(Encoding::Base64.decode(b"Z", &mut buf), Err(TextError::InvalidLength));
+    assert_eq!(Encoding::Base64.decode(b"Zm9v", &mut buf[..2]), Err(TextError::BufferTooSmall));
+
+    // フレーム
+    for enc in [Encoding::Base64, Encoding::Base32].iter() {
+        let msg = b"hello, golay";
+        let mut text = [0u8; 64];
+        let n = encode_frame(*enc, msg, &mut text).unwrap();
+        let mut out = [0u8; 16];
+        assert_eq!(decode_frame(*enc, &text[..n], &mut out), Ok((msg.len(), 0)));
+        assert_eq!(&out[..msg.len()], msg);
+
+        // 1文字を書き換えると，1つの符号語に最大6bitの誤りが入るので，訂正できるとは限らない．
+        // 同じ符号語の中の1bitだけを変える文字の組を使う．
+        let t = &mut text[..n];
+        t[4] = enc.char(enc.value(t[4]).unwrap() ^ 1);
+        assert_eq!(decode_frame(*enc, t, &mut out), Ok((msg.len(), 1)));
+        assert_eq!(&out[..msg.len()], msg);
+    }
+    let n = encode_frame(Encoding::Base64, b"abc", &mut buf).unwrap();
+    assert_eq!(n, 4 * 3);
+    assert_eq!(decode_frame(Encoding::Base64, &buf[..n], &mut [0u8; 2]), Err(TextError::BufferTooSmall));
+
+    // 約束事の違う相手
+    use crate::layout::{LsbFirst, ParityHigh};
+    let n = encode_frame_as::<ParityHigh, LsbFirst>(Encoding::Base64, b"abc", &mut buf).unwrap();
+    let mut out = [0u8; 3];
+    assert_eq!(decode_frame_as::<ParityHigh, LsbFirst>(Encoding::Base64, &buf[..n], &mut out), Ok((3, 0)));
+    assert_eq!(&out, b"abc");
+    assert!(decode_frame(Encoding::Base64, &buf[..n], &mut out).is_err());
+}
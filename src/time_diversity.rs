@@ -0,0 +1,247 @@
+//! 時間ダイバーシチ（水中音響通信向け）
+//!
+//! 数十msのバースト誤りが起きる音響モデム向けに，
+//! 符号語の繰り返し送信と，数秒にわたる深い畳み込みインターリーブを組み合わせる．
+//!
+//! * 各符号語を`repetition`回続けて送り，受信側はビットごとの多数決で合成してから訂正する．
+//! * 送信ビット列を枝数Bの畳み込みインターリーバに通す．t番目のビットは (t mod B)・M・B ビット遅らせる．
+//!   受信側は逆の遅延をかけるので，全体の遅延は (B-1)・M・B ビットになる．
+//!
+//! M・B ≥ B + 23 にしておけば，長さB以下のバースト誤りは各符号語に1bitずつしか入らない．
+//! `Profile::for_channel`はバースト長からBを決め，この条件を満たすMを選ぶ．
+//!
+//! インターリーバの履歴は呼び出し側が用意したバッファに持つ（`Profile::buffer_len`バイト）．
+
+/// 繰り返し回数の上限
+pub const MAX_REPETITION: usize = 5;
+
+/// 時間ダイバーシチのパラメータ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Profile {
+    branches: usize,
+    spacing: usize,
+    repetition: usize,
+}
+
+impl Profile {
+    /// * `branches`: インターリーバの枝数B（1以上）
+    /// * `spacing`: 遅延の刻みM（1以上）
+    /// * `repetition`: 繰り返し回数（1, 3, 5のどれか）
+    pub fn new(branches: usize, spacing: usize, repetition: usize) -> Option<Self> {
+        if branches == 0 || spacing == 0 || !matches!(repetition, 1 | 3 | 5) {
+            return None;
+        }
+        Some( Self{ branches, spacing, repetition } )
+    }
+
+    /// 伝送路の条件からパラメータを決める．
+    ///
+    /// * `bit_rate`: 伝送速度 [bit/s]
+    /// * `burst_ms`: 想定するバースト誤りの長さ（遅延広がり） [ms]
+    /// * `repetition`: 繰り返し回数（1, 3, 5のどれか）
+    pub fn for_channel(bit_rate: u32, burst_ms: u32, repetition: usize) -> Option<Self> {
+        let burst_bits = (bit_rate as u64 * burst_ms as u64).div_ceil(1000) as usize;
+        let b = burst_bits.max(1);
+        let m = (b + 23).div_ceil(b);
+        Self::new(b, m, repetition)
+    }
+
+    /// インターリーバの枝数B
+    #[inline]
+    pub fn branches(&self) -> usize {
+        self.branches
+    }
+
+    /// 繰り返し回数
+    #[inline]
+    pub fn repetition(&self) -> usize {
+        self.repetition
+    }
+
+    /// 送受信を通した遅延（bit）
+    #[inline]
+    pub fn delay_bits(&self) -> usize {
+        (self.branches - 1) * self.spacing * self.branches
+    }
+
+    /// インターリーバの履歴に必要なバッファの大きさ（バイト）
+    #[inline]
+    pub fn buffer_len(&self) -> usize {
+        (self.delay_bits() + 1).div_ceil(8)
+    }
+
+    /// 1符号語あたりの送信ビット数
+    #[inline]
+    pub fn bits_per_word(&self) -> usize {
+        24 * self.repetition
+    }
+
+    /// 最後のデータを受信側で取り出すまでに，余分に送る必要がある符号語の数
+    #[inline]
+    pub fn flush_words(&self) -> usize {
+        self.delay_bits().div_ceil(self.bits_per_word())
+    }
+}
+
+/// 畳み込みインターリーバ（またはデインターリーバ）
+struct Interleaver<'a> {
+    hist: &'a mut [u8],  // 過去の入力ビットのリングバッファ
+    len: usize,          // リングバッファのビット数
+    t: usize,            // 入力したビット数（len で割った余り）
+    branch: usize,       // 今の枝
+    branches: usize,
+    step: usize,         // M・B
+    reverse: bool,       // デインターリーバならtrue
+}
+
+impl<'a> Interleaver<'a> {
+    fn new(profile: &Profile, buf: &'a mut [u8], reverse: bool) -> Self {
+        let len = profile.delay_bits() + 1;
+        assert!(buf.len() * 8 >= len, "buffer too small");
+        for b in buf.iter_mut() {
+            *b = 0;
+        }
+        Self{
+            hist: buf, len, t: 0, branch: 0,
+            branches: profile.branches,
+            step: profile.spacing * profile.branches,
+            reverse,
+        }
+    }
+
+    fn push(&mut self, bit: u8) -> u8 {
+        let pos = self.t;
+        self.hist[pos / 8] = (self.hist[pos / 8] & !(1 << (pos % 8))) | (bit << (pos % 8));
+
+        let j = if self.reverse { self.branches - 1 - self.branch } else { self.branch };
+        let src = (pos + self.len - j * self.step) % self.len;
+        let out = (self.hist[src / 8] >> (src % 8)) & 1;
+
+        self.t = (self.t + 1) % self.len;
+        self.branch = (self.branch + 1) % self.branches;
+        out
+    }
+}
+
+/// 送信側
+pub struct TdEncoder<'a> {
+    profile: Profile,
+    il: Interleaver<'a>,
+}
+
+impl<'a> TdEncoder<'a> {
+    /// * `buf`: インターリーバの履歴（`profile.buffer_len()`バイト以上）
+    pub fn new(profile: Profile, buf: &'a mut [u8]) -> Self {
+        Self{ profile, il: Interleaver::new(&profile, buf, false) }
+    }
+
+    /// 12bitのデータを符号化して送信する24bitずつの語を書き込む．
+    ///
+    /// * `out`: 出力先（`repetition`語以上）
+    /// * return: 書き込んだ語数（`repetition`）
+    pub fn encode(&mut self, data: u16, out: &mut [u32]) -> usize {
+        let code = super::encode(data);
+        let n = self.profile.repetition;
+        for word in out[..n].iter_mut() {
+            let mut w = 0;
+            for i in (0..24).rev() {
+                w = (w << 1) | self.il.push( ((code >> i) & 1) as u8 ) as u32;
+            }
+            *word = w;
+        }
+        n
+    }
+}
+
+/// 受信側
+pub struct TdDecoder<'a> {
+    profile: Profile,
+    il: Interleaver<'a>,
+    skip: usize,                   // 読み捨てるビット数（遅延の分）
+    copies: [u32; MAX_REPETITION], // 集めている途中の受信語
+    n_bits: usize,
+}
+
+impl<'a> TdDecoder<'a> {
+    /// * `buf`: デインターリーバの履歴（`profile.buffer_len()`バイト以上）
+    pub fn new(profile: Profile, buf: &'a mut [u8]) -> Self {
+        Self{
+            profile, il: Interleaver::new(&profile, buf, true),
+            skip: profile.delay_bits(), copies: [0; MAX_REPETITION], n_bits: 0,
+        }
+    }
+
+    /// 受信した24bitの語を1つ入れる．
+    ///
+    /// 1符号語分（`repetition`語）がそろうたびに，多数決で合成した受信語を返す．
+    /// 最初の遅延分の受信語は捨てる．
+    pub fn push(&mut self, r: u32) -> Option<u32> {
+        let mut result = None;
+        let total = self.profile.bits_per_word();
+        for i in (0..24).rev() {
+            let bit = self.il.push( ((r >> i) & 1) as u8 );
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+            let copy = self.n_bits / 24;
+            self.copies[copy] = (self.copies[copy] << 1) | bit as u32;
+            self.n_bits += 1;
+            if self.n_bits == total {
+                result = Some( majority(&self.copies[..self.profile.repetition]) );
+                self.n_bits = 0;
+            }
+        }
+        result
+    }
+}
+
+/// ビットごとの多数決
+fn majority(copies: &[u32]) -> u32 {
+    let mut out = 0;
+    for i in 0..24 {
+        let ones = copies.iter().filter(|c| (*c >> i) & 1 == 1).count();
+        if 2 * ones > copies.len() {
+            out |= 1 << i;
+        }
+    }
+    out
+}
+
+#[test]
+fn test_time_diversity() {
+    // 1 kbit/s，20 msのバースト
+    let profile = Profile::for_channel(1000, 20, 3).unwrap();
+    assert_eq!(profile.branches(), 20);
+
+    let mut tx_buf = [0u8; 1024];
+    let mut rx_buf = [0u8; 1024];
+    assert!(profile.buffer_len() <= 1024);
+    let mut enc = TdEncoder::new(profile, &mut tx_buf);
+    let mut dec = TdDecoder::new(profile, &mut rx_buf);
+
+    let n_data = 40;
+    let mut received = 0;
+    let mut bit_count = 0;
+    for k in 0..(n_data + profile.flush_words()) {
+        let mut words = [0u32; MAX_REPETITION];
+        let n = enc.encode((k * 97 % 4096) as u16, &mut words);
+        for w in words[..n].iter_mut() {
+            // 長さ20bitのバースト誤りを何度か入れる
+            for i in 0..24 {
+                if (bit_count % 500) < 20 {
+                    *w ^= 1 << (23 - i);
+                }
+                bit_count += 1;
+            }
+            if let Some(r) = dec.push(*w) {
+                if received < n_data {
+                    let data = super::decode( super::ecc(r).unwrap() );
+                    assert_eq!(data as usize, received * 97 % 4096);
+                }
+                received += 1;
+            }
+        }
+    }
+    assert!(received >= n_data);
+}
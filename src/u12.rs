@@ -0,0 +1,275 @@
+//! 常に符号化して持つ12bitの整数
+//!
+//! `GolayU12`は値を符号語のまま持ち，読み出すたびに訂正する．
+//! 演算子は訂正した値で計算し，結果を符号化し直した新しい`GolayU12`を返すので，
+//! 放射線にさらされるファームウェアのカウンタや状態機械の変数をそのまま置き換えられる．
+//!
+//! 算術演算は4096を法とする（`u16`の`wrapping_*`を12bitに切り詰めたもの）．
+//! 訂正できない値を含む演算の結果は訂正できない値になる（浮動小数点数のNaNと同じ）ので，
+//! 一連の計算の後で`get`を1回確かめれば良い．
+//!
+//! 訂正できない値になったこと（汚染）は値の符号語とは別の符号語に印として持つ．
+//! 値の符号語に特別な語を入れる方法では，その語から距離3以内の語が
+//! 正しい値に訂正されてしまうため．印の符号語は0（汚染なし）と0xFFFFFF（汚染）で，
+//! 3bitまでの誤りは訂正し，それより多い誤りで訂正できなくなった場合は汚染とみなす．
+
+use core::convert::TryFrom;
+use core::ops::{Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign,
+    Mul, MulAssign, Not, Shl, ShlAssign, Shr, ShrAssign, Sub, SubAssign};
+
+use super::{Decode, Detected};
+
+/// 汚染なしの印（データ0x000の符号語）
+const CLEAN: u32 = 0x000000;
+
+/// 汚染の印（データ0xFFFの符号語．`CLEAN`から距離24）
+const POISONED: u32 = 0xFFFFFF;
+
+/// 常に符号化して持つ12bitの整数
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GolayU12 {
+    code: u32,
+    poison: u32,
+}
+
+impl GolayU12 {
+    /// 値0．0の符号語は0なので`static`の初期化に使える．
+    pub const ZERO: Self = Self{ code: 0, poison: CLEAN };
+
+    /// 値（下位12bit）を符号化して持つ．
+    #[inline]
+    pub fn new(value: u16) -> Self {
+        Self{ code: super::encode(value), poison: CLEAN }
+    }
+
+    /// 24bitの語をそのまま包む（誤りの注入や，保存しておいた語の読み込み用）．
+    #[inline]
+    pub fn from_raw(r: u32) -> Self {
+        Self{ code: r & 0xFFFFFF, poison: CLEAN }
+    }
+
+    /// 符号化したままの値（汚染の印は含まない）
+    #[inline]
+    pub fn raw(&self) -> u32 {
+        self.code
+    }
+
+    /// 訂正できない値を含む演算の結果か．印が訂正できなければ汚染とみなす．
+    #[inline]
+    fn is_poisoned(&self) -> bool {
+        super::ecc(self.poison) != Some(CLEAN)
+    }
+
+    /// 値を訂正して読み出す．訂正できなければNone．
+    #[inline]
+    pub fn get(&self) -> Option<u16> {
+        if self.is_poisoned() {
+            return None;
+        }
+        super::ecc(self.code).map(super::decode)
+    }
+
+    /// 訂正できる値を持っているか．
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.get().is_some()
+    }
+
+    /// 値（下位12bit）を符号化して書き込む．
+    #[inline]
+    pub fn set(&mut self, value: u16) {
+        self.code = super::encode(value);
+        self.poison = CLEAN;
+    }
+
+    /// 訂正した符号語を書き戻して，誤りが溜まらないようにする．
+    ///
+    /// 汚染の印も訂正して書き戻す．訂正できない場合や汚染されている場合は値をそのまま残す．
+    pub fn repair(&mut self) -> Decode {
+        self.poison = if self.is_poisoned() { POISONED } else { CLEAN };
+        if self.poison == POISONED {
+            return Decode::Detected;
+        }
+        let status = super::ecc_status(self.code);
+        if let Decode::Corrected(code, _) = status {
+            self.code = code;
+        }
+        status
+    }
+
+    /// 訂正した値に`f`を適用して符号化し直す．どちらかが訂正できなければ訂正できない値になる．
+    #[inline]
+    fn map2(self, rhs: Self, f: impl FnOnce(u16, u16) -> u16) -> Self {
+        match (self.get(), rhs.get()) {
+            (Some(a), Some(b)) => Self::new( f(a, b) & 0xFFF ),
+            _ => Self{ code: 0, poison: POISONED },
+        }
+    }
+}
+
+impl PartialEq for GolayU12 {
+    /// 訂正した値で比べる（訂正できないもの同士は等しいとする）．
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for GolayU12 {}
+
+impl From<u16> for GolayU12 {
+    #[inline]
+    fn from(value: u16) -> Self {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<GolayU12> for u16 {
+    type Error = Detected;
+
+    #[inline]
+    fn try_from(v: GolayU12) -> Result<Self, Detected> {
+        v.get().ok_or(Detected)
+    }
+}
+
+macro_rules! impl_binary {
+    ($Op:ident, $op:ident, $OpAssign:ident, $op_assign:ident, |$a:ident, $b:ident| $body:expr) => {
+        impl $Op for GolayU12 {
+            type Output = Self;
+
+            #[inline]
+            fn $op(self, rhs: Self) -> Self {
+                self.map2(rhs, |$a, $b| $body)
+            }
+        }
+
+        impl $Op<u16> for GolayU12 {
+            type Output = Self;
+
+            #[inline]
+            fn $op(self, rhs: u16) -> Self {
+                self.$op(Self::new(rhs))
+            }
+        }
+
+        impl $OpAssign for GolayU12 {
+            #[inline]
+            fn $op_assign(&mut self, rhs: Self) {
+                *self = self.$op(rhs);
+            }
+        }
+
+        impl $OpAssign<u16> for GolayU12 {
+            #[inline]
+            fn $op_assign(&mut self, rhs: u16) {
+                *self = self.$op(rhs);
+            }
+        }
+    };
+}
+
+impl_binary!(Add, add, AddAssign, add_assign, |a, b| a.wrapping_add(b));
+impl_binary!(Sub, sub, SubAssign, sub_assign, |a, b| a.wrapping_sub(b));
+impl_binary!(Mul, mul, MulAssign, mul_assign, |a, b| a.wrapping_mul(b));
+impl_binary!(BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a & b);
+impl_binary!(BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+impl_binary!(BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| a ^ b);
+
+impl Not for GolayU12 {
+    type Output = Self;
+
+    #[inline]
+    fn not(self) -> Self {
+        self.map2(self, |a, _| !a)
+    }
+}
+
+impl Shl<u32> for GolayU12 {
+    type Output = Self;
+
+    /// 12bitを超えた分は捨てる．
+    #[inline]
+    fn shl(self, n: u32) -> Self {
+        self.map2(self, |a, _| a.checked_shl(n).unwrap_or(0))
+    }
+}
+
+impl Shr<u32> for GolayU12 {
+    type Output = Self;
+
+    #[inline]
+    fn shr(self, n: u32) -> Self {
+        self.map2(self, |a, _| a.checked_shr(n).unwrap_or(0))
+    }
+}
+
+impl ShlAssign<u32> for GolayU12 {
+    #[inline]
+    fn shl_assign(&mut self, n: u32) {
+        *self = *self << n;
+    }
+}
+
+impl ShrAssign<u32> for GolayU12 {
+    #[inline]
+    fn shr_assign(&mut self, n: u32) {
+        *self = *self >> n;
+    }
+}
+
+#[test]
+fn test_u12() {
+    let mut counter = GolayU12::ZERO;
+    for _ in 0..5000 {
+        counter += 1;
+    }
+    assert_eq!(counter.get(), Some(5000 % 4096));
+    assert_eq!(counter.raw(), super::encode(5000 % 4096));
+
+    let a = GolayU12::new(0xABC);
+    let b = GolayU12::from_raw(GolayU12::new(0x00F).raw() ^ 0x800101);
+    assert_eq!(a + b, GolayU12::new(0xACB));
+    assert_eq!((GolayU12::new(1) - 2u16).get(), Some(0xFFF));
+    assert_eq!((a * 16u16).get(), Some(0xBC0));
+    assert_eq!((a & b).get(), Some(0x00C));
+    assert_eq!((a | b).get(), Some(0xABF));
+    assert_eq!((a ^ b).get(), Some(0xAB3));
+    assert_eq!((!a).get(), Some(0x543));
+    assert_eq!((a << 4).get(), Some(0xBC0));
+    assert_eq!((a >> 4).get(), Some(0x0AB));
+    assert_eq!((a << 40).get(), Some(0));
+    assert_eq!(u16::try_from(a), Ok(0xABC));
+
+    // 演算の結果は符号化し直されていて，誤りは残らない
+    assert_eq!((b | 0u16).raw(), super::encode(0x00F));
+    let mut c = b;
+    assert_eq!(c.repair(), Decode::Corrected(super::encode(0x00F), 3));
+    assert_eq!(c.raw(), super::encode(0x00F));
+
+    // 訂正できない値は演算の結果にも伝わる
+    let broken = GolayU12::from_raw(super::encode(0x123) ^ 0xF0);
+    assert!(!broken.is_valid());
+    assert!(!(a + broken).is_valid());
+    assert!(!(broken << 1).is_valid());
+    assert_eq!(u16::try_from(broken * a), Err(Detected));
+
+    // 汚染の印はどのビットが反転しても消えない（値の符号語は正しい値の0のまま）
+    let poisoned = a + broken;
+    for i in 0..24 {
+        let mut p = poisoned;
+        p.poison ^= 1 << i;
+        assert!(!p.is_valid(), "poison bit {}", i);
+        let mut p = poisoned;
+        p.code ^= 1 << i;
+        assert!(!p.is_valid(), "code bit {}", i);
+        assert_eq!(p.repair(), Decode::Detected);
+        assert_eq!(p.poison, POISONED);
+    }
+    // 印の3bitまでの誤りは訂正され，汚染とはみなさない
+    let mut p = a;
+    p.poison ^= 0x800101;
+    assert_eq!(p.get(), Some(0xABC));
+    assert_eq!(p.repair(), Decode::Clean(a.raw()));
+    assert_eq!(p.poison, CLEAN);
+}
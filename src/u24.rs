@@ -0,0 +1,84 @@
+//! 24bitの値を2つの符号語に斜めに分けて保護する
+//!
+//! 値の偶数番目のビットを1つめの符号語，奇数番目のビットを2つめの符号語のデータにし，
+//! 送るときは2つの符号語を1bitずつ交互に並べる（`interleave`と同じ，6バイト）．
+//! どのバイトにも両方の符号語のビットが4bitずつ入る．
+//!
+//! 連続したビットのバースト誤りは2つの符号語に半分ずつ分かれるので，
+//! * 6bit以下のバーストは必ず訂正できる（各符号語に3bit以下）
+//! * 8bit以下のバースト（1バイト分が壊れた場合を含む）は各符号語に4bit以下なので，誤訂正されず必ず検出できる
+//!
+//! 値の上位と下位が別々の符号語に入るので，片方だけ訂正できなかった場合に値の一部だけが正しいということはない
+//! （`decode`は両方訂正できた場合だけ値を返す）．
+
+/// 送るバイト数
+pub const WIRE_LEN: usize = 6;
+
+/// 24bitの値を（偶数番目のビット，奇数番目のビット）の12bitずつに分ける．
+pub fn split(value: u32) -> (u16, u16) {
+    let mut even = 0;
+    let mut odd = 0;
+    for i in 0..12 {
+        even |= (((value >> (2 * i)) & 1) as u16) << i;
+        odd |= (((value >> (2 * i + 1)) & 1) as u16) << i;
+    }
+    (even, odd)
+}
+
+/// `split`で分けたものを戻す．
+pub fn join(even: u16, odd: u16) -> u32 {
+    let mut value = 0;
+    for i in 0..12 {
+        value |= ((even as u32 >> i) & 1) << (2 * i);
+        value |= ((odd as u32 >> i) & 1) << (2 * i + 1);
+    }
+    value
+}
+
+/// 24bitの値（上位8bitは無視する）を符号化して6バイトにする．
+pub fn encode(value: u32) -> [u8; WIRE_LEN] {
+    let (even, odd) = split(value);
+    let mut out = [0u8; WIRE_LEN];
+    crate::interleave::interleave(&[crate::encode(even), crate::encode(odd)], &mut out);
+    out
+}
+
+/// 6バイトを訂正して24bitの値に戻す．
+///
+/// * return: （値，訂正したビット数）．どちらかの符号語を訂正できなければNone．
+pub fn decode(bytes: &[u8; WIRE_LEN]) -> Option<(u32, u8)> {
+    let mut codes = [0u32; 2];
+    crate::interleave::deinterleave(bytes, &mut codes);
+    let a = crate::ecc_verbose(codes[0])?;
+    let b = crate::ecc_verbose(codes[1])?;
+    Some( (join(crate::decode(a.code), crate::decode(b.code)), a.errors + b.errors) )
+}
+
+#[test]
+fn test_u24() {
+    for value in [0x000000, 0xFFFFFF, 0xABCDEF, 0x5A5A5A, 0x800001].iter() {
+        let (even, odd) = split(*value);
+        assert_eq!(join(even, odd), *value);
+        assert_eq!(decode(&encode(*value)), Some((*value, 0)));
+    }
+    assert_eq!(split(0x000003), (1, 1));
+
+    let value = 0x9C3A71;
+    let wire = encode(value);
+    // 長さ1〜6のバーストはどこでも訂正できる
+    for len in 1..=6 {
+        for start in 0..=(48 - len) {
+            let mut w = wire;
+            for k in start..start + len {
+                w[k / 8] ^= 0x80 >> (k % 8);
+            }
+            assert_eq!(decode(&w), Some((value, len as u8)));
+        }
+    }
+    // 1バイト全部が壊れても誤訂正しない
+    for j in 0..WIRE_LEN {
+        let mut w = wire;
+        w[j] ^= 0xFF;
+        assert_eq!(decode(&w), None);
+    }
+}
@@ -0,0 +1,213 @@
+//! UART向けの符号語の詰め方と再同期
+//!
+//! 符号語は3バイトに詰めて上位バイトから送る．
+//! PIOなどで作ったUARTのFIFOから1バイトずつ取り出して処理することを想定している．
+//!
+//! 送信側は`interval`個のデータ語ごとに同期語を1つ挟む．
+//! 受信側は同期語が見つかるまで1バイトずつずらして探し，見つかったら3バイトずつ区切る．
+//! 同期語の位置で2回続けて同期語が読めなければ，同期が外れたとみなして探し直す．
+//!
+//! 相手の約束事が違う場合は，`UartTx`と`UartRx`の型引数に配置`L`とビット順`O`を指定する
+//! （`layout`）．`new`で作ったものはこのクレートの形式（`DataHigh`，`MsbFirst`）．
+
+use core::marker::PhantomData;
+
+use super::Decode;
+use crate::layout::{BitOrder, DataHigh, Layout, MsbFirst};
+
+/// 同期語（12bit）
+pub const SYNC: u16 = 0xB38;
+
+/// 同期語を何回続けて読めなければ同期が外れたとみなすか
+const MAX_MISSES: u8 = 2;
+
+/// 符号語（下位24bit）を3バイトにする．
+#[inline]
+pub fn to_bytes(code: u32) -> [u8; 3] {
+    [(code >> 16) as u8, (code >> 8) as u8, code as u8]
+}
+
+/// 3バイトを受信語にする．
+#[inline]
+pub fn from_bytes(bytes: [u8; 3]) -> u32 {
+    ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32
+}
+
+/// 送信側
+#[derive(Clone, Copy, Debug)]
+pub struct UartTx<L: Layout = DataHigh, O: BitOrder = MsbFirst> {
+    interval: u16,
+    count: u16,
+    _marker: PhantomData<(L, O)>,
+}
+
+impl UartTx {
+    /// このクレートの形式で送る．
+    ///
+    /// * `interval`: 同期語を挟む間隔（データ語の数，1以上）
+    #[inline]
+    pub fn new(interval: u16) -> Self {
+        Self::with_layout(interval)
+    }
+}
+
+impl<L: Layout, O: BitOrder> UartTx<L, O> {
+    /// 配置`L`，ビット順`O`で送る．
+    ///
+    /// * `interval`: 同期語を挟む間隔（データ語の数，1以上）
+    pub fn with_layout(interval: u16) -> Self {
+        assert!(interval > 0);
+        Self{ interval, count: 0, _marker: PhantomData }
+    }
+
+    /// 12bitのデータを符号化して送るバイト列を書き込む．
+    ///
+    /// * return: 書き込んだバイト数（同期語を挟む場合は6，それ以外は3）
+    pub fn encode(&mut self, data: u16, out: &mut [u8; 6]) -> usize {
+        let mut len = 0;
+        if self.count == 0 {
+            out[..3].copy_from_slice( &O::to_bytes(L::from_native(super::encode(SYNC))) );
+            len = 3;
+        }
+        out[len..len + 3].copy_from_slice( &O::to_bytes(L::from_native(super::encode(data))) );
+        self.count = (self.count + 1) % self.interval;
+        len + 3
+    }
+}
+
+/// 受信側
+///
+/// 訂正の結果はこのクレートの形式の符号語で返す．
+#[derive(Clone, Copy, Debug)]
+pub struct UartRx<L: Layout = DataHigh, O: BitOrder = MsbFirst> {
+    interval: u16,
+    window: [u8; 3],
+    n: usize,       // windowに入っているバイト数
+    locked: bool,
+    count: u16,     // 同期語の後に受け取ったデータ語の数
+    misses: u8,
+    _marker: PhantomData<(L, O)>,
+}
+
+impl UartRx {
+    /// このクレートの形式で受け取る．
+    ///
+    /// * `interval`: 同期語を挟む間隔（送信側と同じ値）
+    #[inline]
+    pub fn new(interval: u16) -> Self {
+        Self::with_layout(interval)
+    }
+}
+
+impl<L: Layout, O: BitOrder> UartRx<L, O> {
+    /// 配置`L`，ビット順`O`で受け取る．
+    ///
+    /// * `interval`: 同期語を挟む間隔（送信側と同じ値）
+    pub fn with_layout(interval: u16) -> Self {
+        assert!(interval > 0);
+        Self{ interval, window: [0; 3], n: 0, locked: false, count: 0, misses: 0, _marker: PhantomData }
+    }
+
+    /// 受け取った3バイトをこのクレートの形式の受信語にする．
+    #[inline]
+    fn word(&self) -> u32 {
+        L::to_native( O::from_bytes(self.window) )
+    }
+
+    /// 同期がとれていればtrue
+    #[inline]
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// 受信したバイトを1つ入れる．
+    ///
+    /// * return: データ語がそろったら訂正結果を返す．
+    pub fn push(&mut self, byte: u8) -> Option<Decode> {
+        let sync = super::encode(SYNC);
+        if !self.locked {
+            self.window = [self.window[1], self.window[2], byte];
+            self.n = (self.n + 1).min(3);
+            if self.n == 3 && super::ecc(self.word()) == Some(sync) {
+                self.locked = true;
+                self.count = 0;
+                self.misses = 0;
+                self.n = 0;
+            }
+            return None;
+        }
+
+        self.window[self.n] = byte;
+        self.n += 1;
+        if self.n < 3 {
+            return None;
+        }
+        self.n = 0;
+        let r = self.word();
+
+        if self.count == self.interval {
+            self.count = 0;
+            if super::ecc(r) == Some(sync) {
+                self.misses = 0;
+            } else {
+                self.misses += 1;
+                if self.misses >= MAX_MISSES {
+                    // 今読んだ3バイトから探し直す
+                    self.locked = false;
+                    self.n = 3;
+                }
+            }
+            return None;
+        }
+        self.count += 1;
+        Some( super::ecc_status(r) )
+    }
+}
+
+#[test]
+fn test_uart() {
+    let mut tx = UartTx::new(4);
+    let mut wire = [0u8; 64];
+    let mut len = 0;
+    for data in 0..10u16 {
+        let mut out = [0u8; 6];
+        let n = tx.encode(data * 0x111, &mut out);
+        wire[len..len + n].copy_from_slice(&out[..n]);
+        len += n;
+    }
+    assert_eq!(len, 3 * (10 + 3));
+
+    // 先頭の2バイトを取りこぼして受信を始める
+    let mut rx = UartRx::new(4);
+    let mut received = [0u16; 10];
+    let mut n = 0;
+    wire[20] ^= 0x11;
+    for b in wire[2..len].iter() {
+        if let Some(d) = rx.push(*b) {
+            received[n] = super::decode(d.code().unwrap());
+            n += 1;
+        }
+    }
+    assert!(rx.is_locked());
+    // 最初の同期語を逃したので，2つ目の同期語の後から受け取る
+    assert_eq!(n, 6);
+    assert_eq!(&received[..6], &[0x444, 0x555, 0x666, 0x777, 0x888, 0x999]);
+
+    // 約束事の違う相手
+    use crate::layout::{LsbFirst, ParityHigh};
+    let mut tx = UartTx::<ParityHigh, LsbFirst>::with_layout(2);
+    let mut rx = UartRx::<ParityHigh, LsbFirst>::with_layout(2);
+    let mut native = UartRx::new(2);
+    let mut out = [0u8; 6];
+    let mut received = None;
+    for data in [0x123u16, 0xABC].iter() {
+        let n = tx.encode(*data, &mut out);
+        assert_eq!(n, if *data == 0x123 { 6 } else { 3 });
+        for b in out[..n].iter() {
+            received = rx.push(*b).or(received);
+            assert_eq!(native.push(*b), None);
+        }
+        assert_eq!(received.and_then(|d| d.code()).map(super::decode), Some(*data));
+    }
+    assert!(!native.is_locked());
+}
@@ -0,0 +1,134 @@
+//! 検証用の固定の試験ベクタ（`vectors`フィーチャ）
+//!
+//! FFIで呼ぶ側やHDLの実装を，このクレートと同じ値で確かめるためのもの．
+//! 各ベクタには由来（`source`）を付けてある．
+//!
+//! 符号語の値は生成行列の選び方（列の並べ方）で変わる．教科書や規格の表は
+//! それぞれ別の生成行列を使っているので，符号語の値をそのまま持ってくることはできない．
+//! 公開された生成行列から列の置換を通して求め，ベクタごとに出典を示すことは見送った
+//! （置換を確かめられる出典が手元にないため）．
+//! したがって符号化と復号のベクタは文献の値（golden vector）ではなく，
+//! このクレートの生成行列（組織符号 [I | B]，データが上位12bit）から求めた回帰試験用のもの．
+//! 文献を出典にしているのは，生成行列によらない重み分布だけ．
+
+/// このクレートの生成行列から求めた値
+pub const SOURCE_MATRIX: &str = "generator matrix G = [I | B] of this crate (data in bits 23..12)";
+
+/// 生成行列の行そのもの
+pub const SOURCE_MATRIX_ROW: &str = "row of the generator matrix G of this crate";
+
+/// このクレートの符号語に誤りを加えて作った，復号の回帰試験用の値
+pub const SOURCE_REGRESSION: &str =
+    "self-generated regression vector: codeword of this crate with a chosen error pattern";
+
+/// 重み分布の出典
+pub const SOURCE_WEIGHT_DISTRIBUTION: &str =
+    "weight distribution of the extended Golay code (MacWilliams & Sloane, The Theory of Error-Correcting Codes, 1977, ch. 2 sec. 6)";
+
+/// 符号化のベクタ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodeVector {
+    /// データ（12bit）
+    pub data: u16,
+    /// 符号語（24bit）
+    pub code: u32,
+    /// 出典
+    pub source: &'static str,
+}
+
+/// 復号のベクタ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeVector {
+    /// 受信語（24bit）
+    pub received: u32,
+    /// 訂正後の符号語．訂正できない（4bit誤りを検出する）場合はNone．
+    pub expected: Option<u32>,
+    /// 加えた誤りのビット数
+    pub errors: u8,
+    /// 出典
+    pub source: &'static str,
+}
+
+const fn enc(data: u16, code: u32, source: &'static str) -> EncodeVector {
+    EncodeVector{ data, code, source }
+}
+
+const fn dec(received: u32, expected: Option<u32>, errors: u8) -> DecodeVector {
+    DecodeVector{ received, expected, errors, source: SOURCE_REGRESSION }
+}
+
+/// `selftest::VECTORS`と生成行列の各行
+static ENCODE: [EncodeVector; 23] = encode_table();
+
+const fn encode_table() -> [EncodeVector; 23] {
+    let mut table = [enc(0, 0, SOURCE_MATRIX); 23];
+    let n = crate::selftest::VECTORS.len();
+    let mut i = 0;
+    while i < n {
+        let (data, code) = crate::selftest::VECTORS[i];
+        table[i] = enc(data, code, SOURCE_MATRIX);
+        i += 1;
+    }
+    let mut j = 0;
+    while j < 12 {
+        table[n + j] = enc(0x800 >> j, crate::G[j], SOURCE_MATRIX_ROW);
+        j += 1;
+    }
+    table
+}
+
+static DECODE: [DecodeVector; 12] = [
+    dec(0x1239E7, Some(0x1239E7), 0),
+    dec(0x1239E6, Some(0x1239E7), 1),
+    dec(0x9239E7, Some(0x1239E7), 1),
+    dec(0x9C3CE1, Some(0x9C3DE0), 2),
+    dec(0xE5C67D, Some(0xA5CE7F), 3),
+    dec(0x525555, Some(0x555555), 3),
+    dec(0xEFFBEF, Some(0xFFFFFF), 3),
+    dec(0x000007, Some(0x000000), 3),
+    dec(0x3C50D2, None, 4),
+    dec(0xFF0264, None, 4),
+    dec(0x808DD0, None, 4),
+    dec(0x00000F, None, 4),
+];
+
+/// 重み分布（重み，符号語の数）．0以外．出典は`SOURCE_WEIGHT_DISTRIBUTION`．
+pub use crate::miscorrection::WEIGHT_DISTRIBUTION;
+
+/// 符号化のベクタを順に返す．
+#[inline]
+pub fn encode_vectors() -> core::slice::Iter<'static, EncodeVector> {
+    ENCODE.iter()
+}
+
+/// 復号のベクタを順に返す．
+#[inline]
+pub fn decode_vectors() -> core::slice::Iter<'static, DecodeVector> {
+    DECODE.iter()
+}
+
+#[test]
+fn test_vectors() {
+    for v in encode_vectors() {
+        assert_eq!(crate::encode(v.data), v.code, "{:03X}", v.data);
+        assert_eq!(crate::decode(v.code), v.data);
+    }
+    for (i, v) in encode_vectors().filter(|v| v.source == SOURCE_MATRIX_ROW).enumerate() {
+        assert_eq!(v.code, crate::G[i]);
+    }
+    for v in decode_vectors() {
+        assert_eq!(crate::ecc(v.received), v.expected);
+        match v.expected {
+            Some(code) => assert_eq!((v.received ^ code).count_ones(), v.errors as u32),
+            None => assert_eq!(v.errors, 4),
+        }
+    }
+    let mut dist = [0usize; 25];
+    for data in 0..4096 {
+        dist[crate::encode(data).count_ones() as usize] += 1;
+    }
+    for (w, n) in WEIGHT_DISTRIBUTION.iter() {
+        assert_eq!(dist[*w as usize], *n);
+    }
+    assert_eq!(1 + WEIGHT_DISTRIBUTION.iter().map(|(_, n)| n).sum::<usize>(), 4096);
+}
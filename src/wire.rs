@@ -0,0 +1,280 @@
+//! 符号語を運ぶメッセージの構造体とpostcard形式の直列化
+//!
+//! `ProtectedMessage`は版，フラグ，符号語の列を持つ．
+//! 直列化したバイト列はpostcardで次の構造体を直列化したものと同じになるので，
+//! postcardを使う既存のマイコン向けプロトコルの中にそのまま入れられる．
+//!
+//! ```text
+//! #[derive(Serialize, Deserialize)]
+//! struct ProtectedMessage {
+//!     version: u8,
+//!     flags: u16,
+//!     codewords: heapless::Vec<u32, N>,
+//! }
+//! ```
+//!
+//! postcardの形式
+//! * `u8`: そのまま1バイト
+//! * `u16`，`u32`，長さ: 下位7bitずつのLEB128（varint）
+//!
+//! `to_postcard`と`from_postcard`はserdeなしで使えるように形式を手で書いている．
+//! `serde`フィーチャを有効にすると`Serialize`と`Deserialize`も実装し，
+//! （版，フラグ，符号語の列）の組として直列化する．
+
+use core::convert::TryFrom;
+
+/// 直列化のエラー
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireError {
+    /// 出力先のバッファが足りない
+    BufferTooSmall,
+    /// 途中でバイト列が終わった
+    UnexpectedEnd,
+    /// varintが型に収まらない
+    Overflow,
+    /// 符号語の数が容量を超える
+    TooManyCodewords,
+    /// 符号語が24bitに収まらない
+    InvalidCodeword,
+}
+
+/// 符号語を`N`個まで運ぶメッセージ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtectedMessage<const N: usize> {
+    /// 版
+    pub version: u8,
+    /// フラグ（使い方は利用者が決める）
+    pub flags: u16,
+    len: usize,
+    codewords: [u32; N],
+}
+
+impl<const N: usize> ProtectedMessage<N> {
+    /// 符号語のない空のメッセージを作る．
+    pub fn new(version: u8, flags: u16) -> Self {
+        Self{ version, flags, len: 0, codewords: [0; N] }
+    }
+
+    /// 12bitのデータを符号化して詰める．`N`個を超えればNone．
+    pub fn from_data(version: u8, flags: u16, data: &[u16]) -> Option<Self> {
+        let mut msg = Self::new(version, flags);
+        for a in data {
+            msg.push( crate::encode(*a) ).ok()?;
+        }
+        Some(msg)
+    }
+
+    /// 符号語を1つ加える．
+    pub fn push(&mut self, code: u32) -> Result<(), WireError> {
+        if code > 0xFFFFFF {
+            return Err(WireError::InvalidCodeword);
+        }
+        *self.codewords.get_mut(self.len).ok_or(WireError::TooManyCodewords)? = code;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// 符号語（受信語）の列
+    #[inline]
+    pub fn codewords(&self) -> &[u32] {
+        &self.codewords[..self.len]
+    }
+
+    /// 各符号語を訂正して12bitのデータを`out`に書き込む．
+    ///
+    /// * return: 書き込んだ数．訂正できない符号語があるか`out`が足りなければNone．
+    pub fn decode_into(&self, out: &mut [u16]) -> Option<usize> {
+        let out = out.get_mut(..self.len)?;
+        for (o, r) in out.iter_mut().zip(self.codewords()) {
+            *o = crate::decode( crate::ecc(*r)? );
+        }
+        Some(self.len)
+    }
+
+    /// postcard形式で`buf`に書き込む．
+    ///
+    /// * return: 書き込んだバイト数
+    pub fn to_postcard(&self, buf: &mut [u8]) -> Result<usize, WireError> {
+        let mut n = 0;
+        *buf.get_mut(0).ok_or(WireError::BufferTooSmall)? = self.version;
+        n += 1;
+        n += put_varint(&mut buf[n..], self.flags as u32)?;
+        n += put_varint(&mut buf[n..], self.len as u32)?;
+        for c in self.codewords() {
+            n += put_varint(&mut buf[n..], *c)?;
+        }
+        Ok(n)
+    }
+
+    /// postcard形式から読み込む．
+    ///
+    /// * return: （メッセージ，読んだバイト数）．後ろに続くバイト列は読まない．
+    pub fn from_postcard(bytes: &[u8]) -> Result<(Self, usize), WireError> {
+        let version = *bytes.first().ok_or(WireError::UnexpectedEnd)?;
+        let mut n = 1;
+        let (flags, k) = get_varint(&bytes[n..])?;
+        n += k;
+        let flags = u16::try_from(flags).map_err(|_| WireError::Overflow)?;
+        let (len, k) = get_varint(&bytes[n..])?;
+        n += k;
+        if len as usize > N {
+            return Err(WireError::TooManyCodewords);
+        }
+        let mut msg = Self::new(version, flags);
+        for _ in 0..len {
+            let (c, k) = get_varint(&bytes[n..])?;
+            n += k;
+            msg.push(c)?;
+        }
+        Ok( (msg, n) )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for ProtectedMessage<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeTuple;
+        let mut t = serializer.serialize_tuple(3)?;
+        t.serialize_element(&self.version)?;
+        t.serialize_element(&self.flags)?;
+        t.serialize_element(self.codewords())?;
+        t.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for ProtectedMessage<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_tuple(3, MessageVisitor::<N>)
+    }
+}
+
+/// （版，フラグ，符号語の列）の組を読む．
+#[cfg(feature = "serde")]
+struct MessageVisitor<const N: usize>;
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::de::Visitor<'de> for MessageVisitor<N> {
+    type Value = ProtectedMessage<N>;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str("a tuple of version, flags and codewords")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        use serde::de::Error;
+        let version = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let flags = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        let Codewords(mut msg) = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(2, &self))?;
+        msg.version = version;
+        msg.flags = flags;
+        Ok(msg)
+    }
+}
+
+/// 符号語の列だけを読むための包み（版とフラグは後で入れる）
+#[cfg(feature = "serde")]
+struct Codewords<const N: usize>(ProtectedMessage<N>);
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for Codewords<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(CodewordsVisitor::<N>)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct CodewordsVisitor<const N: usize>;
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::de::Visitor<'de> for CodewordsVisitor<N> {
+    type Value = Codewords<N>;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "at most {} codewords of 24 bits", N)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        use serde::de::{Error, Unexpected};
+        let mut msg = ProtectedMessage::new(0, 0);
+        while let Some(c) = seq.next_element::<u32>()? {
+            match msg.push(c) {
+                Ok(()) => (),
+                Err(WireError::TooManyCodewords) => return Err( A::Error::invalid_length(N + 1, &self) ),
+                Err(_) => return Err( A::Error::invalid_value(Unexpected::Unsigned(c as u64), &self) ),
+            }
+        }
+        Ok( Codewords(msg) )
+    }
+}
+
+/// LEB128で書き込む．
+fn put_varint(buf: &mut [u8], mut v: u32) -> Result<usize, WireError> {
+    let mut n = 0;
+    loop {
+        let b = buf.get_mut(n).ok_or(WireError::BufferTooSmall)?;
+        n += 1;
+        if v < 0x80 {
+            *b = v as u8;
+            return Ok(n);
+        }
+        *b = (v as u8 & 0x7F) | 0x80;
+        v >>= 7;
+    }
+}
+
+/// LEB128を読む．
+fn get_varint(bytes: &[u8]) -> Result<(u32, usize), WireError> {
+    let mut v = 0u32;
+    for (i, b) in bytes.iter().enumerate() {
+        if i == 4 && *b > 0x0F {
+            return Err(WireError::Overflow);  // 32bitを超える
+        }
+        v |= ((b & 0x7F) as u32) << (7 * i);
+        if b & 0x80 == 0 {
+            return Ok( (v, i + 1) );
+        }
+    }
+    Err(WireError::UnexpectedEnd)
+}
+
+#[test]
+fn test_wire() {
+    let msg = ProtectedMessage::<8>::from_data(2, 0x1234, &[0x000, 0xFFF, 0x001]).unwrap();
+    let mut buf = [0u8; 32];
+    let n = msg.to_postcard(&mut buf).unwrap();
+    // version, flags（0x1234 = 0xB4 0x24）, 長さ3, 符号語（0，0xFFFFFF = 4バイト，0x001AAB = 2バイト）
+    assert_eq!(&buf[..n], &[2, 0xB4, 0x24, 3, 0, 0xFF, 0xFF, 0xFF, 0x07, 0xAB, 0x35]);
+    assert_eq!(ProtectedMessage::<8>::from_postcard(&buf[..n]), Ok((msg, n)));
+
+    // 受信側で訂正する
+    let (mut rx, _) = ProtectedMessage::<8>::from_postcard(&buf[..n]).unwrap();
+    rx.codewords[1] ^= 0x800001;
+    let mut out = [0u16; 3];
+    assert_eq!(rx.decode_into(&mut out), Some(3));
+    assert_eq!(out, [0x000, 0xFFF, 0x001]);
+
+    assert_eq!(ProtectedMessage::<2>::from_postcard(&buf[..n]), Err(WireError::TooManyCodewords));
+    assert_eq!(ProtectedMessage::<8>::from_postcard(&buf[..n - 1]), Err(WireError::UnexpectedEnd));
+    assert_eq!(msg.to_postcard(&mut buf[..5]), Err(WireError::BufferTooSmall));
+    assert_eq!(get_varint(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F]), Err(WireError::Overflow));
+    assert_eq!(ProtectedMessage::<1>::from_postcard(&[0, 0x80, 0x80, 0x04, 0]), Err(WireError::Overflow));
+    assert_eq!(ProtectedMessage::<1>::new(0, 0).push(0x1000000), Err(WireError::InvalidCodeword));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_wire_serde() {
+    let msg = ProtectedMessage::<8>::from_data(2, 0x1234, &[0x000, 0xFFF, 0x001]).unwrap();
+    let mut buf = [0u8; 32];
+    let bytes = postcard::to_slice(&msg, &mut buf).unwrap();
+    // 手書きの形式と同じバイト列になる
+    let mut expected = [0u8; 32];
+    let n = msg.to_postcard(&mut expected).unwrap();
+    assert_eq!(bytes, &expected[..n]);
+    assert_eq!(postcard::from_bytes::<ProtectedMessage<8>>(bytes), Ok(msg));
+
+    assert!(postcard::from_bytes::<ProtectedMessage<2>>(bytes).is_err());
+    assert!(postcard::from_bytes::<ProtectedMessage<8>>(&bytes[..bytes.len() - 1]).is_err());
+    assert!(postcard::from_bytes::<ProtectedMessage<1>>(&[0, 0, 1, 0x80, 0x80, 0x80, 0x08]).is_err());  // 25bit
+}